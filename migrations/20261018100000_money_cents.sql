@@ -0,0 +1,48 @@
+-- Store all amounts as integer cents instead of REAL euros. Every stored
+-- amount is rounded to the cent on its own, so the totals stay exactly what
+-- was printed on the issued PDFs.
+CREATE TABLE invoice_cents
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    nummer      INTEGER NOT NULL UNIQUE,
+    client      INTEGER NOT NULL,
+    pdf         INTEGER NOT NULL,
+    -- JSON serialized list of objects, amounts in cents
+    work_items  TEXT NOT NULL,
+    subtotal    INTEGER NOT NULL,
+    btw         INTEGER NOT NULL,
+    total       INTEGER NOT NULL,
+    created_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted     BOOLEAN DEFAULT 0,
+
+    FOREIGN KEY(client) REFERENCES client(id)
+    FOREIGN KEY(pdf) REFERENCES pdf(id)
+);
+
+INSERT INTO invoice_cents ( id, nummer, client, pdf, work_items, subtotal, btw, total, created_at, deleted )
+SELECT
+    id,
+    nummer,
+    client,
+    pdf,
+    (
+        SELECT json_group_array(
+            json_object(
+                'desc', json_extract(item.value, '$.desc'),
+                'euro', CAST(ROUND(json_extract(item.value, '$.euro') * 100) AS INTEGER)
+            )
+        )
+        FROM (SELECT value FROM json_each(invoice.work_items) ORDER BY key) AS item
+    ),
+    CAST(ROUND(subtotal * 100) AS INTEGER),
+    CAST(ROUND(btw * 100) AS INTEGER),
+    CAST(ROUND(total * 100) AS INTEGER),
+    created_at,
+    deleted
+FROM invoice;
+
+DROP TABLE invoice;
+ALTER TABLE invoice_cents RENAME TO invoice;
+
+CREATE INDEX nummer_idx ON invoice(nummer);
+CREATE INDEX date_idx ON invoice(created_at);
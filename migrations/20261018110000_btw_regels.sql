@@ -0,0 +1,9 @@
+-- JSON serialized BTW breakdown per category. Everything invoiced so far
+-- was at the 21% rate.
+ALTER TABLE invoice ADD COLUMN btw_regels TEXT NOT NULL DEFAULT '[]';
+
+UPDATE invoice
+SET btw_regels = json_array(
+    json_object('categorie', 'hoog', 'grondslag', subtotal, 'btw', btw)
+)
+WHERE subtotal != 0 OR btw != 0;
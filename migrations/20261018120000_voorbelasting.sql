@@ -0,0 +1,10 @@
+-- BTW paid on purchases per quarter, in cents, as recorded by hand
+CREATE TABLE IF NOT EXISTS voorbelasting
+(
+    id       INTEGER PRIMARY KEY NOT NULL,
+    year     INTEGER NOT NULL,
+    quarter  INTEGER NOT NULL,
+    amount   INTEGER NOT NULL,
+
+    UNIQUE(year, quarter)
+);
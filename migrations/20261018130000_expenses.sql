@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS receipt
+(
+    id            INTEGER PRIMARY KEY NOT NULL,
+    filename      TEXT NOT NULL,
+    content_type  TEXT NOT NULL,
+    file          BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS expense
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+    date           DATE NOT NULL,
+    supplier       TEXT NOT NULL,
+    category       TEXT NOT NULL,
+    btw_categorie  TEXT NOT NULL,
+    -- Amounts in cents
+    excl           INTEGER NOT NULL,
+    btw            INTEGER NOT NULL,
+    receipt        INTEGER,
+    created_at     DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted        BOOLEAN NOT NULL DEFAULT 0,
+
+    FOREIGN KEY(receipt) REFERENCES receipt(id)
+);
+
+CREATE INDEX expense_date_idx ON expense(date);
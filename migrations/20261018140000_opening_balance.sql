@@ -0,0 +1,9 @@
+-- Beginbalans per year, amounts in cents
+CREATE TABLE IF NOT EXISTS opening_balance
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    year        INTEGER NOT NULL UNIQUE,
+    bank        INTEGER NOT NULL,
+    debiteuren  INTEGER NOT NULL,
+    btw         INTEGER NOT NULL
+);
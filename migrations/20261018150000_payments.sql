@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS payment
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    invoice     INTEGER NOT NULL,
+    date        DATE NOT NULL,
+    -- Amount in cents
+    amount      INTEGER NOT NULL,
+    method      TEXT NOT NULL,
+    created_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(invoice) REFERENCES invoice(id)
+);
+
+CREATE INDEX payment_invoice_idx ON payment(invoice);
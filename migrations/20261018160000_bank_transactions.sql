@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS bank_transaction
+(
+    id            INTEGER PRIMARY KEY NOT NULL,
+    date          DATE NOT NULL,
+    -- Amount in cents, negative for money going out
+    amount        INTEGER NOT NULL,
+    counterparty  TEXT NOT NULL,
+    iban          TEXT NOT NULL,
+    description   TEXT NOT NULL,
+    -- Used to skip transactions that were imported before
+    fingerprint   TEXT NOT NULL UNIQUE,
+    -- A transaction is handled once it is linked to a payment or an expense,
+    -- or when it was ignored in the review queue
+    payment       INTEGER,
+    expense       INTEGER,
+    ignored       BOOLEAN NOT NULL DEFAULT 0,
+    created_at    DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(payment) REFERENCES payment(id),
+    FOREIGN KEY(expense) REFERENCES expense(id)
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS reminder
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    invoice     INTEGER NOT NULL,
+    kind        TEXT NOT NULL,
+    date        DATE NOT NULL,
+    pdf         INTEGER NOT NULL,
+    created_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(invoice) REFERENCES invoice(id),
+    FOREIGN KEY(pdf) REFERENCES pdf(id),
+    UNIQUE(invoice, kind)
+);
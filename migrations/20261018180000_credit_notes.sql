@@ -0,0 +1,2 @@
+-- The nummer of the invoice that a credit note corrects
+ALTER TABLE invoice ADD COLUMN credit_for INTEGER;
@@ -0,0 +1,13 @@
+-- Every invoice keeps its nummer as internal sequence, but is printed with a
+-- kenmerk that is built from the volgnummer within its year, e.g. 2026-0042.
+-- Existing invoices keep being printed as their plain nummer.
+ALTER TABLE invoice ADD COLUMN jaar INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoice ADD COLUMN volgnummer INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoice ADD COLUMN kenmerk TEXT NOT NULL DEFAULT '';
+
+UPDATE invoice SET
+    jaar = CAST(substr(created_at, 1, 4) AS INTEGER),
+    volgnummer = nummer,
+    kenmerk = CAST(nummer AS TEXT);
+
+CREATE UNIQUE INDEX invoice_kenmerk_idx ON invoice(kenmerk);
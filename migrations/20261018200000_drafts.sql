@@ -0,0 +1,15 @@
+-- Invoices that are still being worked on. A draft has no number or PDF
+-- yet; it is removed once it is finalized into an invoice.
+CREATE TABLE IF NOT EXISTS draft
+(
+    id              INTEGER PRIMARY KEY NOT NULL,
+    client_name     TEXT NOT NULL,
+    client_address  TEXT NOT NULL,
+    client_zip      TEXT NOT NULL,
+    -- JSON serialized list of objects, as in invoice
+    work_items      TEXT NOT NULL,
+    credit_for      INTEGER,
+    updated_at      DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(credit_for) REFERENCES invoice(nummer)
+);
@@ -0,0 +1,16 @@
+-- Invoices that are sent every period with the same work items. The work
+-- item descriptions can hold placeholders like {month}, which are filled in
+-- for each period. Due periods become drafts, linked back to their schedule.
+CREATE TABLE IF NOT EXISTS recurring_invoice
+(
+    id              INTEGER PRIMARY KEY NOT NULL,
+    client_name     TEXT NOT NULL,
+    client_address  TEXT NOT NULL,
+    client_zip      TEXT NOT NULL,
+    -- JSON serialized list of objects, as in invoice
+    work_items      TEXT NOT NULL,
+    frequency       TEXT NOT NULL,
+    next_run        DATE NOT NULL
+);
+
+ALTER TABLE draft ADD COLUMN recurring INTEGER REFERENCES recurring_invoice(id) ON DELETE SET NULL;
@@ -0,0 +1,25 @@
+-- Everything we know about a client besides the address on the invoice.
+-- Archived clients are kept for their invoices, but are not offered for new
+-- ones anymore.
+ALTER TABLE client ADD COLUMN kvk TEXT NOT NULL DEFAULT '';
+ALTER TABLE client ADD COLUMN btw_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE client ADD COLUMN email TEXT NOT NULL DEFAULT '';
+ALTER TABLE client ADD COLUMN contact TEXT NOT NULL DEFAULT '';
+ALTER TABLE client ADD COLUMN country TEXT NOT NULL DEFAULT 'Nederland';
+-- In days, or NULL for the default payment term
+ALTER TABLE client ADD COLUMN payment_term INTEGER;
+-- In cents, or NULL for the default rate
+ALTER TABLE client ADD COLUMN hourly_rate INTEGER;
+ALTER TABLE client ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0;
+
+-- The client as it was when the invoice was issued, so that editing a client
+-- does not change invoices that were already sent
+ALTER TABLE invoice ADD COLUMN client_name TEXT NOT NULL DEFAULT '';
+ALTER TABLE invoice ADD COLUMN client_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE invoice ADD COLUMN client_zip TEXT NOT NULL DEFAULT '';
+ALTER TABLE invoice ADD COLUMN payment_term INTEGER NOT NULL DEFAULT 14;
+
+UPDATE invoice SET
+    client_name = ( SELECT name FROM client WHERE client.id = invoice.client ),
+    client_address = ( SELECT address FROM client WHERE client.id = invoice.client ),
+    client_zip = ( SELECT zip FROM client WHERE client.id = invoice.client );
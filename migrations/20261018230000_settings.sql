@@ -0,0 +1,45 @@
+-- Who sends the invoices and how they look. There is always exactly one
+-- row, which starts out without a business. Its details are filled in on the
+-- settings page, or once with `settings import`.
+CREATE TABLE IF NOT EXISTS settings
+(
+    id              INTEGER PRIMARY KEY NOT NULL CHECK ( id = 1 ),
+    business_name   TEXT NOT NULL,
+    owner_name      TEXT NOT NULL,
+    email           TEXT NOT NULL,
+    btw_number      TEXT NOT NULL,
+    kvk_number      TEXT NOT NULL,
+    -- The bank account that invoices are paid to
+    account_holder  TEXT NOT NULL,
+    iban            TEXT NOT NULL,
+    city            TEXT NOT NULL,
+    -- In days, for clients without a payment term of their own
+    payment_term    INTEGER NOT NULL,
+    -- Markdown, where {dagen} is the payment term of the invoice
+    closing_note    TEXT NOT NULL,
+    greeting        TEXT NOT NULL,
+    serif_font      TEXT NOT NULL,
+    sans_font       TEXT NOT NULL,
+    font_size       TEXT NOT NULL,
+    -- Page margins, in the syntax of the LaTeX geometry package
+    geometry        TEXT NOT NULL
+);
+
+INSERT INTO settings VALUES (
+    1,
+    '',
+    '',
+    '',
+    '',
+    '',
+    '',
+    '',
+    '',
+    14,
+    'Gelieve het verschuldigde bedrag binnen {dagen} dagen over te maken naar:',
+    'Met vriendelijke groeten,',
+    'Liberation Serif',
+    'Liberation Sans',
+    '10pt',
+    'a4paper, left=43mm, right=43mm, top=51mm, bottom=17mm'
+);
@@ -0,0 +1,198 @@
+-- Every business that invoices from this deployment is a tenant, with its
+-- own users, clients, invoices, numbering and settings. Everything that was
+-- stored before belongs to the first tenant.
+CREATE TABLE IF NOT EXISTS tenant
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    name        TEXT NOT NULL,
+    created_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO tenant ( id, name ) SELECT 1, business_name FROM settings;
+
+-- Usernames are unique over all tenants, as they decide the tenant at login.
+-- The first user gets the password hash in USER_SECRET when the server starts.
+CREATE TABLE IF NOT EXISTS user
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+    tenant         INTEGER NOT NULL,
+    username       TEXT NOT NULL UNIQUE,
+    -- Argon2 PHC string
+    password_hash  TEXT NOT NULL,
+    created_at     DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id)
+);
+
+INSERT INTO user ( id, tenant, username, password_hash ) VALUES ( 1, 1, 'noemi', '' );
+
+-- The tables below are rebuilt, as their unique constraints now hold per
+-- tenant. The clients are set aside while their table is rebuilt under the
+-- same name, so that the invoices referring to them are valid again once
+-- they are copied back.
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TABLE client_old AS SELECT * FROM client;
+DROP TABLE client;
+
+CREATE TABLE client
+(
+    id            INTEGER PRIMARY KEY NOT NULL,
+    tenant        INTEGER NOT NULL,
+    name          TEXT NOT NULL,
+    address       TEXT NOT NULL,
+    zip           TEXT NOT NULL,
+    kvk           TEXT NOT NULL DEFAULT '',
+    btw_id        TEXT NOT NULL DEFAULT '',
+    email         TEXT NOT NULL DEFAULT '',
+    contact       TEXT NOT NULL DEFAULT '',
+    country       TEXT NOT NULL DEFAULT 'Nederland',
+    -- In days, or NULL for the default payment term
+    payment_term  INTEGER,
+    -- In cents, or NULL for the default rate
+    hourly_rate   INTEGER,
+    archived      BOOLEAN NOT NULL DEFAULT 0,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    UNIQUE(tenant, name)
+);
+
+INSERT INTO client ( id, tenant, name, address, zip, kvk, btw_id, email, contact, country,
+    payment_term, hourly_rate, archived )
+SELECT id, 1, name, address, zip, kvk, btw_id, email, contact, country, payment_term, hourly_rate, archived
+FROM client_old;
+
+DROP TABLE client_old;
+
+CREATE INDEX client_idx ON client(tenant, name);
+
+CREATE TABLE voorbelasting_tenant
+(
+    id       INTEGER PRIMARY KEY NOT NULL,
+    tenant   INTEGER NOT NULL,
+    year     INTEGER NOT NULL,
+    quarter  INTEGER NOT NULL,
+    amount   INTEGER NOT NULL,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    UNIQUE(tenant, year, quarter)
+);
+
+INSERT INTO voorbelasting_tenant ( id, tenant, year, quarter, amount )
+SELECT id, 1, year, quarter, amount FROM voorbelasting;
+
+DROP TABLE voorbelasting;
+ALTER TABLE voorbelasting_tenant RENAME TO voorbelasting;
+
+CREATE TABLE opening_balance_tenant
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+    tenant      INTEGER NOT NULL,
+    year        INTEGER NOT NULL,
+    bank        INTEGER NOT NULL,
+    debiteuren  INTEGER NOT NULL,
+    btw         INTEGER NOT NULL,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    UNIQUE(tenant, year)
+);
+
+INSERT INTO opening_balance_tenant ( id, tenant, year, bank, debiteuren, btw )
+SELECT id, 1, year, bank, debiteuren, btw FROM opening_balance;
+
+DROP TABLE opening_balance;
+ALTER TABLE opening_balance_tenant RENAME TO opening_balance;
+
+CREATE TABLE bank_transaction_tenant
+(
+    id            INTEGER PRIMARY KEY NOT NULL,
+    tenant        INTEGER NOT NULL,
+    date          DATE NOT NULL,
+    -- Amount in cents, negative for money going out
+    amount        INTEGER NOT NULL,
+    counterparty  TEXT NOT NULL,
+    iban          TEXT NOT NULL,
+    description   TEXT NOT NULL,
+    -- Used to skip transactions that were imported before
+    fingerprint   TEXT NOT NULL,
+    -- A transaction is handled once it is linked to a payment or an expense,
+    -- or when it was ignored in the review queue
+    payment       INTEGER,
+    expense       INTEGER,
+    ignored       BOOLEAN NOT NULL DEFAULT 0,
+    created_at    DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    FOREIGN KEY(payment) REFERENCES payment(id),
+    FOREIGN KEY(expense) REFERENCES expense(id),
+    UNIQUE(tenant, fingerprint)
+);
+
+INSERT INTO bank_transaction_tenant ( id, tenant, date, amount, counterparty, iban, description,
+    fingerprint, payment, expense, ignored, created_at )
+SELECT id, 1, date, amount, counterparty, iban, description, fingerprint, payment, expense, ignored, created_at
+FROM bank_transaction;
+
+DROP TABLE bank_transaction;
+ALTER TABLE bank_transaction_tenant RENAME TO bank_transaction;
+
+-- New tenants start out with the layout of the first one, and fill in who
+-- they are on the settings page
+CREATE TABLE settings_tenant
+(
+    tenant          INTEGER PRIMARY KEY NOT NULL,
+    business_name   TEXT NOT NULL,
+    owner_name      TEXT NOT NULL DEFAULT '',
+    email           TEXT NOT NULL DEFAULT '',
+    btw_number      TEXT NOT NULL DEFAULT '',
+    kvk_number      TEXT NOT NULL DEFAULT '',
+    -- The bank account that invoices are paid to
+    account_holder  TEXT NOT NULL DEFAULT '',
+    iban            TEXT NOT NULL DEFAULT '',
+    city            TEXT NOT NULL DEFAULT '',
+    -- In days, for clients without a payment term of their own
+    payment_term    INTEGER NOT NULL DEFAULT 14,
+    -- Markdown, where {dagen} is the payment term of the invoice
+    closing_note    TEXT NOT NULL
+        DEFAULT 'Gelieve het verschuldigde bedrag binnen {dagen} dagen over te maken naar:',
+    greeting        TEXT NOT NULL DEFAULT 'Met vriendelijke groeten,',
+    serif_font      TEXT NOT NULL DEFAULT 'Liberation Serif',
+    sans_font       TEXT NOT NULL DEFAULT 'Liberation Sans',
+    font_size       TEXT NOT NULL DEFAULT '10pt',
+    -- Page margins, in the syntax of the LaTeX geometry package
+    geometry        TEXT NOT NULL DEFAULT 'a4paper, left=43mm, right=43mm, top=51mm, bottom=17mm',
+    -- How invoice numbers are printed, or empty for FACTUUR_NUMMERING
+    nummering       TEXT NOT NULL DEFAULT '',
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id)
+);
+
+INSERT INTO settings_tenant ( tenant, business_name, owner_name, email, btw_number, kvk_number,
+    account_holder, iban, city, payment_term, closing_note, greeting, serif_font, sans_font, font_size, geometry )
+SELECT 1, business_name, owner_name, email, btw_number, kvk_number, account_holder, iban, city,
+    payment_term, closing_note, greeting, serif_font, sans_font, font_size, geometry
+FROM settings;
+
+DROP TABLE settings;
+ALTER TABLE settings_tenant RENAME TO settings;
+
+-- The other tables only get a column. A row that is stored without a tenant
+-- ends up with tenant 0, which nobody can log in to, rather than with the
+-- first business. Payments, reminders, PDFs and receipts belong to the
+-- tenant of their invoice or expense.
+ALTER TABLE invoice ADD COLUMN tenant INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE expense ADD COLUMN tenant INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE draft ADD COLUMN tenant INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE recurring_invoice ADD COLUMN tenant INTEGER NOT NULL DEFAULT 0;
+
+UPDATE invoice SET tenant = 1;
+UPDATE expense SET tenant = 1;
+UPDATE draft SET tenant = 1;
+UPDATE recurring_invoice SET tenant = 1;
+
+-- The nummer stays the internal sequence over all tenants, while every
+-- tenant counts its own volgnummers and kenmerken
+DROP INDEX invoice_kenmerk_idx;
+CREATE UNIQUE INDEX invoice_kenmerk_idx ON invoice(tenant, kenmerk);
+CREATE INDEX invoice_tenant_idx ON invoice(tenant, jaar, volgnummer);
+CREATE INDEX expense_tenant_idx ON expense(tenant);
@@ -0,0 +1,3 @@
+-- What a user may do: 'eigenaar' can change everything, 'boekhouder' can
+-- only read the reports and PDFs. The users from before roles are owners.
+ALTER TABLE user ADD COLUMN role TEXT NOT NULL DEFAULT 'eigenaar';
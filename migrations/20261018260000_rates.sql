@@ -0,0 +1,26 @@
+-- Hourly rates that apply from a date on, for a client, a kind of work from
+-- the schedule, or both. Work that no rate matches is billed at the hourly
+-- rate of the client.
+CREATE TABLE IF NOT EXISTS rate
+(
+    id                 INTEGER PRIMARY KEY NOT NULL,
+    tenant             INTEGER NOT NULL,
+    -- NULL for all clients
+    client             INTEGER,
+    -- Like 'Bar', as in the schedule, or NULL for all work
+    event_type         TEXT,
+    valid_from         DATE NOT NULL,
+    -- The last day the rate applies, or NULL until a newer rate starts
+    valid_until        DATE,
+    -- In cents
+    hourly_rate        INTEGER NOT NULL,
+    -- In percent on top of the hourly rate, for hours from 20:00 to 06:00
+    evening_surcharge  INTEGER NOT NULL DEFAULT 0,
+    -- In percent on top of the hourly rate, for hours on Saturday and Sunday
+    weekend_surcharge  INTEGER NOT NULL DEFAULT 0,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    FOREIGN KEY(client) REFERENCES client(id)
+);
+
+CREATE INDEX rate_tenant_idx ON rate(tenant);
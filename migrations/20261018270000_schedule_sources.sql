@@ -0,0 +1,27 @@
+-- The schedules of employers that shifts are imported from, to invoice them
+-- to the linked client.
+CREATE TABLE IF NOT EXISTS schedule_source
+(
+    id         INTEGER PRIMARY KEY NOT NULL,
+    tenant     INTEGER NOT NULL,
+    name       TEXT NOT NULL,
+    -- Like 'l1nda'
+    kind       TEXT NOT NULL,
+    -- Like 'denieuweanita' for denieuweanita.l1nda.nl
+    subdomain  TEXT NOT NULL,
+    -- The name of the employee in the schedule
+    employee   TEXT NOT NULL,
+    client     INTEGER,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    FOREIGN KEY(client) REFERENCES client(id)
+);
+
+CREATE INDEX schedule_source_tenant_idx ON schedule_source(tenant);
+
+-- The schedule that used to be the only one
+INSERT INTO schedule_source ( tenant, name, kind, subdomain, employee, client )
+SELECT id, 'De Nieuwe Anita', 'l1nda', 'denieuweanita', 'Noemi',
+    ( SELECT client.id FROM client WHERE client.tenant = tenant.id AND client.name = 'V.O.F. De Nieuwe Anita' )
+FROM tenant
+WHERE id = 1;
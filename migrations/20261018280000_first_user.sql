@@ -0,0 +1,5 @@
+-- The tenants migration added a user 'noemi' that only got a password from
+-- USER_SECRET. The first user is now added by the server from USER_NAME and
+-- USER_SECRET, or with `users add`, so the placeholder goes if it was never
+-- given a password.
+DELETE FROM user WHERE id = 1 AND username = 'noemi' AND password_hash = '';
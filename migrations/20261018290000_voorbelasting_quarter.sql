@@ -0,0 +1,21 @@
+-- Only the quarters 1 up to and including 4 exist. Others could be stored
+-- before, but were never shown and made the balance sheet fail, so they are
+-- left out.
+CREATE TABLE voorbelasting_quarter
+(
+    id       INTEGER PRIMARY KEY NOT NULL,
+    tenant   INTEGER NOT NULL,
+    year     INTEGER NOT NULL,
+    quarter  INTEGER NOT NULL CHECK ( quarter BETWEEN 1 AND 4 ),
+    amount   INTEGER NOT NULL,
+
+    FOREIGN KEY(tenant) REFERENCES tenant(id),
+    UNIQUE(tenant, year, quarter)
+);
+
+INSERT INTO voorbelasting_quarter ( id, tenant, year, quarter, amount )
+SELECT id, tenant, year, quarter, amount FROM voorbelasting
+WHERE quarter BETWEEN 1 AND 4;
+
+DROP TABLE voorbelasting;
+ALTER TABLE voorbelasting_quarter RENAME TO voorbelasting;
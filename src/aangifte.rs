@@ -0,0 +1,301 @@
+use anyhow::{bail, Result};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+use crate::btw::BtwCategorie;
+use crate::expense::Expense;
+use crate::factuur::Factuur;
+use crate::money::{serialize_euros, Money};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Quarter {
+    year: i32,
+    quarter: u32,
+}
+
+impl std::fmt::Display for Quarter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quarters = HashMap::from([
+            (1, "jan–maa"),
+            (2, "apr–jun"),
+            (3, "jul–sep"),
+            (4, "okt–dec"),
+        ]);
+
+        let display_quarter = match quarters.get(&self.quarter) {
+            Some(q) => q,
+            None => "ooit",
+        };
+
+        write!(f, "{}Q{} ({})", self.year, self.quarter, display_quarter)
+    }
+}
+
+impl Quarter {
+    /// Fails for quarters other than 1 up to and including 4.
+    pub fn new(year: i32, quarter: u32) -> Result<Self> {
+        if !(1..=4).contains(&quarter) || !(1..=9999).contains(&year) {
+            bail!("kwartaal {quarter} van {year} bestaat niet");
+        }
+        Ok(Quarter { year, quarter })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn quarter(&self) -> u32 {
+        self.quarter
+    }
+
+    pub fn of(date: &impl Datelike) -> Self {
+        Quarter {
+            year: date.year(),
+            quarter: date.month0() / 3 + 1,
+        }
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, (self.quarter - 1) * 3 + 1, 1).unwrap()
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        self.first_day() + Months::new(3) - Days::new(1)
+    }
+
+    /// The last day to file and pay the aangifte: the end of the month after
+    /// the quarter.
+    pub fn deadline(&self) -> NaiveDate {
+        self.first_day() + Months::new(4) - Days::new(1)
+    }
+}
+
+/// Omzet and BTW as entered in a single rubriek of the aangifte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Rubriek {
+    #[serde(serialize_with = "serialize_euros")]
+    pub omzet: Money,
+    #[serde(serialize_with = "serialize_euros")]
+    pub btw: Money,
+}
+
+/// The quarterly BTW-aangifte, as filled in on the Belastingdienst portal.
+///
+/// The portal only takes whole euros. Omzet and verschuldigde BTW are rounded
+/// down and voorbelasting is rounded up, which the Belastingdienst allows.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Aangifte {
+    /// Leveringen/diensten belast met hoog tarief
+    #[serde(rename = "1a")]
+    pub rubriek_1a: Rubriek,
+    /// Leveringen/diensten belast met laag tarief
+    #[serde(rename = "1b")]
+    pub rubriek_1b: Rubriek,
+    /// Leveringen/diensten belast met overige tarieven, behalve 0%
+    #[serde(rename = "1c")]
+    pub rubriek_1c: Rubriek,
+    /// Leveringen/diensten belast met 0% of niet bij u belast
+    #[serde(rename = "1e")]
+    pub rubriek_1e: Rubriek,
+    /// Leveringen/diensten waarbij de heffing van omzetbelasting naar u is verlegd
+    #[serde(rename = "2a")]
+    pub rubriek_2a: Rubriek,
+    /// Leveringen naar of diensten in landen binnen de EU
+    #[serde(rename = "3b")]
+    pub rubriek_3b: Rubriek,
+    /// Leveringen/diensten uit landen buiten de EU
+    #[serde(rename = "4a")]
+    pub rubriek_4a: Rubriek,
+    /// Leveringen/diensten uit landen binnen de EU
+    #[serde(rename = "4b")]
+    pub rubriek_4b: Rubriek,
+    /// Verschuldigde omzetbelasting
+    #[serde(rename = "5a")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5a: Money,
+    /// Voorbelasting
+    #[serde(rename = "5b")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5b: Money,
+    /// Subtotaal
+    #[serde(rename = "5c")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5c: Money,
+    /// Vermindering volgens de kleineondernemersregeling
+    #[serde(rename = "5d")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5d: Money,
+    /// Schatting vorige aangifte(n)
+    #[serde(rename = "5e")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5e: Money,
+    /// Schatting deze aangifte
+    #[serde(rename = "5f")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5f: Money,
+    /// Totaal te betalen of terug te vragen
+    #[serde(rename = "5g")]
+    #[serde(serialize_with = "serialize_euros")]
+    pub rubriek_5g: Money,
+}
+
+impl Aangifte {
+    /// Fills the aangifte from the BTW lines of all invoices and the expenses
+    /// in a quarter, plus any voorbelasting recorded by hand for that quarter.
+    ///
+    /// Expenses with verlegde BTW are taken to be purchases from other EU
+    /// countries, which is what they are for a small business in practice.
+    pub fn new(invoices: &[Factuur], expenses: &[Expense], voorbelasting: Money) -> Self {
+        let mut aangifte = Aangifte::default();
+
+        for regel in invoices.iter().flat_map(|f| &f.btw) {
+            let rubriek = match regel.categorie {
+                BtwCategorie::Hoog => &mut aangifte.rubriek_1a,
+                BtwCategorie::Laag => &mut aangifte.rubriek_1b,
+                BtwCategorie::Nul => &mut aangifte.rubriek_1e,
+                BtwCategorie::Verlegd => &mut aangifte.rubriek_3b,
+                // Vrijgestelde prestaties are not part of the aangifte
+                BtwCategorie::Vrijgesteld => continue,
+            };
+            rubriek.omzet += regel.grondslag;
+            rubriek.btw += regel.btw;
+        }
+
+        for expense in expenses.iter() {
+            if expense.btw_categorie == BtwCategorie::Verlegd {
+                aangifte.rubriek_4b.omzet += expense.excl;
+                aangifte.rubriek_4b.btw += expense.verlegde_btw();
+            }
+        }
+        let voorbelasting = voorbelasting + expenses.iter().map(|e| e.voorbelasting()).sum();
+
+        for rubriek in aangifte.rubrieken_mut() {
+            rubriek.omzet = rubriek.omzet.floor_euros();
+            rubriek.btw = rubriek.btw.floor_euros();
+        }
+
+        aangifte.rubriek_5a = aangifte.rubrieken().iter().map(|(_, _, r)| r.btw).sum();
+        aangifte.rubriek_5b = voorbelasting.ceil_euros();
+        aangifte.rubriek_5c = aangifte.rubriek_5a - aangifte.rubriek_5b;
+        aangifte.rubriek_5g =
+            aangifte.rubriek_5c - aangifte.rubriek_5d + aangifte.rubriek_5e - aangifte.rubriek_5f;
+
+        aangifte
+    }
+
+    /// Rubrieken 1 through 4 with their number and description.
+    pub fn rubrieken(&self) -> [(&'static str, &'static str, Rubriek); 8] {
+        [
+            (
+                "1a",
+                "Leveringen/diensten belast met hoog tarief",
+                self.rubriek_1a,
+            ),
+            (
+                "1b",
+                "Leveringen/diensten belast met laag tarief",
+                self.rubriek_1b,
+            ),
+            (
+                "1c",
+                "Leveringen/diensten belast met overige tarieven, behalve 0%",
+                self.rubriek_1c,
+            ),
+            (
+                "1e",
+                "Leveringen/diensten belast met 0% of niet bij u belast",
+                self.rubriek_1e,
+            ),
+            (
+                "2a",
+                "Leveringen/diensten waarbij de heffing van omzetbelasting naar u is verlegd",
+                self.rubriek_2a,
+            ),
+            (
+                "3b",
+                "Leveringen naar of diensten in landen binnen de EU",
+                self.rubriek_3b,
+            ),
+            (
+                "4a",
+                "Leveringen/diensten uit landen buiten de EU",
+                self.rubriek_4a,
+            ),
+            (
+                "4b",
+                "Leveringen/diensten uit landen binnen de EU",
+                self.rubriek_4b,
+            ),
+        ]
+    }
+
+    /// Rubriek 5 with its number and description.
+    pub fn totalen(&self) -> [(&'static str, &'static str, Money); 7] {
+        [
+            ("5a", "Verschuldigde omzetbelasting", self.rubriek_5a),
+            ("5b", "Voorbelasting", self.rubriek_5b),
+            ("5c", "Subtotaal", self.rubriek_5c),
+            (
+                "5d",
+                "Vermindering volgens de kleineondernemersregeling",
+                self.rubriek_5d,
+            ),
+            ("5e", "Schatting vorige aangifte(n)", self.rubriek_5e),
+            ("5f", "Schatting deze aangifte", self.rubriek_5f),
+            (
+                "5g",
+                "Totaal te betalen of terug te vragen",
+                self.rubriek_5g,
+            ),
+        ]
+    }
+
+    fn rubrieken_mut(&mut self) -> [&mut Rubriek; 8] {
+        [
+            &mut self.rubriek_1a,
+            &mut self.rubriek_1b,
+            &mut self.rubriek_1c,
+            &mut self.rubriek_1e,
+            &mut self.rubriek_2a,
+            &mut self.rubriek_3b,
+            &mut self.rubriek_4a,
+            &mut self.rubriek_4b,
+        ]
+    }
+
+    /// Whether BTW has to be paid (`true`) or can be reclaimed (`false`).
+    pub fn te_betalen(&self) -> bool {
+        self.rubriek_5g >= Money::ZERO
+    }
+
+    /// Semicolon separated export with one line per rubriek and decimal
+    /// commas, as spreadsheets with a Dutch locale expect.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("rubriek;omschrijving;omzet;btw\n");
+        for (nummer, omschrijving, rubriek) in self.rubrieken() {
+            csv.push_str(&format!(
+                "{};{};{};{}\n",
+                nummer,
+                omschrijving,
+                bedrag(rubriek.omzet),
+                bedrag(rubriek.btw)
+            ));
+        }
+        for (nummer, omschrijving, totaal) in self.totalen() {
+            csv.push_str(&format!(
+                "{};{};;{}\n",
+                nummer,
+                omschrijving,
+                bedrag(totaal)
+            ));
+        }
+        csv
+    }
+}
+
+/// Formats like `12,05`, the way the `bedrag` filter prints amounts.
+fn bedrag(money: Money) -> String {
+    money.to_string().replace('.', ",")
+}
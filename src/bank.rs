@@ -0,0 +1,258 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::NaiveDate;
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+
+use crate::expense::ExpenseForm;
+use crate::factuur::Factuur;
+use crate::money::Money;
+
+/// A single line on a bank statement.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    /// Positive for money coming in, negative for money going out
+    pub amount: Money,
+    pub counterparty: String,
+    pub iban: String,
+    pub description: String,
+}
+
+impl Transaction {
+    pub fn is_credit(&self) -> bool {
+        self.amount > Money::ZERO
+    }
+
+    /// Identifies a transaction across imports, so that importing
+    /// overlapping statements does not add the same transaction twice.
+    pub fn fingerprint(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.date,
+            self.amount.cents(),
+            self.iban,
+            self.description
+        )
+    }
+
+    /// A debit prefilled as kosten, to be completed with the right BTW.
+    pub fn expense_suggestion(&self) -> ExpenseForm {
+        ExpenseForm {
+            date: self.date.to_string(),
+            supplier: self.counterparty.clone(),
+            incl: (-self.amount).to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The invoice this transaction pays, if it is the only open invoice
+    /// with exactly the outstanding amount whose number or client name is
+    /// mentioned. When several invoices qualify, the one whose number is
+    /// mentioned wins.
+    pub fn match_invoice<'a>(&self, invoices: &'a [Factuur]) -> Option<&'a Factuur> {
+        if !self.is_credit() {
+            return None;
+        }
+
+        let text = format!("{} {}", self.counterparty, self.description).to_lowercase();
+        let candidates: Vec<(&Factuur, bool)> = invoices
+            .iter()
+            .filter(|i| i.outstanding(NaiveDate::MAX) == self.amount)
+            .filter_map(|i| {
+                let nummer = mentions_kenmerk(&text, &i.kenmerk);
+                let name = text.contains(&i.client.name.to_lowercase());
+                (nummer || name).then_some((i, nummer))
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [(i, _)] => Some(i),
+            _ => {
+                let mut by_nummer = candidates.iter().filter(|(_, nummer)| *nummer);
+                match (by_nummer.next(), by_nummer.next()) {
+                    (Some((i, _)), None) => Some(i),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Whether the invoice number occurs in `text`, and not as part of a longer
+/// number such as an amount, an IBAN or another invoice number: `2026-0042`
+/// is not mentioned in `2026-00421`. A plain number may be written without
+/// its leading zeroes.
+fn mentions_kenmerk(text: &str, kenmerk: &str) -> bool {
+    if !kenmerk.chars().all(|c| c.is_ascii_digit()) {
+        let kenmerk = kenmerk.to_lowercase();
+        let digit_at = |i: Option<char>| i.is_some_and(|c| c.is_ascii_digit());
+        return text.match_indices(&kenmerk).any(|(start, found)| {
+            !digit_at(text[..start].chars().next_back())
+                && !digit_at(text[start + found.len()..].chars().next())
+        });
+    }
+    text.split(|c: char| !c.is_ascii_digit())
+        .any(|n| !n.is_empty() && n.trim_start_matches('0') == kenmerk.trim_start_matches('0'))
+}
+
+/// Parses a bank statement, either a CAMT.053 XML file or an ING CSV export.
+pub fn parse(statement: &str) -> Result<Vec<Transaction>> {
+    let statement = statement.trim_start_matches('\u{feff}').trim_start();
+    if statement.starts_with('<') {
+        parse_camt053(statement)
+    } else {
+        parse_ing_csv(statement)
+    }
+}
+
+/// Parses the entries of a CAMT.053 (BankToCustomerStatement) file.
+pub fn parse_camt053(xml: &str) -> Result<Vec<Transaction>> {
+    let doc = roxmltree::Document::parse(xml).context("invalid XML")?;
+    let root = doc.root_element();
+    if child(root, "BkToCstmrStmt").is_none() {
+        bail!("not a CAMT.053 statement");
+    }
+
+    let mut transactions = vec![];
+    for entry in root.descendants().filter(|n| n.has_tag_name("Ntry")) {
+        let credit = match text(entry, &["CdtDbtInd"]) {
+            Some("CRDT") => true,
+            Some("DBIT") => false,
+            other => bail!("invalid CdtDbtInd: {other:?}"),
+        };
+        let date = text(entry, &["BookgDt", "Dt"])
+            .or_else(|| text(entry, &["BookgDt", "DtTm"]))
+            .or_else(|| text(entry, &["ValDt", "Dt"]))
+            .ok_or_else(|| anyhow!("entry without booking date"))?;
+        let date = NaiveDate::parse_from_str(&date[..10.min(date.len())], "%Y-%m-%d")?;
+        let signed = |amount: Money| if credit { amount } else { -amount };
+
+        // A batch entry holds several transactions, each with its own amount
+        let details: Vec<Node> = entry
+            .descendants()
+            .filter(|n| n.has_tag_name("TxDtls"))
+            .collect();
+        let batch = details.len() > 1
+            && details
+                .iter()
+                .all(|d| text(*d, &["AmtDtls", "TxAmt", "Amt"]).is_some());
+
+        if batch {
+            for d in details {
+                let amount: Money = text(d, &["AmtDtls", "TxAmt", "Amt"]).unwrap().parse()?;
+                transactions.push(camt_transaction(date, signed(amount), credit, d, entry));
+            }
+        } else {
+            let amount: Money = text(entry, &["Amt"])
+                .ok_or_else(|| anyhow!("entry without amount"))?
+                .parse()?;
+            let d = details.first().copied().unwrap_or(entry);
+            transactions.push(camt_transaction(date, signed(amount), credit, d, entry));
+        }
+    }
+
+    Ok(transactions)
+}
+
+fn camt_transaction(
+    date: NaiveDate,
+    amount: Money,
+    credit: bool,
+    details: Node,
+    entry: Node,
+) -> Transaction {
+    // For money coming in the other party is the debtor, and vice versa
+    let (party, account) = match credit {
+        true => ("Dbtr", "DbtrAcct"),
+        false => ("Cdtr", "CdtrAcct"),
+    };
+    let parties = child(details, "RltdPties");
+    let counterparty = parties
+        .and_then(|p| child(p, party))
+        .and_then(|p| p.descendants().find(|n| n.has_tag_name("Nm")))
+        .and_then(|n| n.text())
+        .unwrap_or_default();
+    let iban = parties
+        .and_then(|p| text(p, &[account, "Id", "IBAN"]))
+        .unwrap_or_default();
+
+    let mut description: Vec<&str> = details
+        .descendants()
+        .filter(|n| n.has_tag_name("Ustrd") || n.has_tag_name("Ref"))
+        .filter_map(|n| n.text())
+        .collect();
+    if description.is_empty() {
+        description.extend(text(entry, &["AddtlNtryInf"]));
+    }
+
+    Transaction {
+        date,
+        amount,
+        counterparty: counterparty.trim().to_owned(),
+        iban: iban.trim().to_owned(),
+        description: description.join(" ").trim().to_owned(),
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+/// Text of the element at `path` below `node`.
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    path.iter()
+        .try_fold(node, |n, tag| child(n, tag))
+        .and_then(|n| n.text())
+}
+
+/// Parses the CSV export of ING, either the older comma separated format or
+/// the current one separated by semicolons.
+pub fn parse_ing_csv(csv: &str) -> Result<Vec<Transaction>> {
+    let header = csv.lines().next().unwrap_or_default();
+    let delimiter = if header.contains(';') { b';' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("missing column {name:?}, is this an ING export?"))
+    };
+    let datum = column("Datum")?;
+    let naam = column("Naam / Omschrijving")?;
+    let tegenrekening = column("Tegenrekening")?;
+    let af_bij = column("Af Bij")?;
+    let bedrag = column("Bedrag (EUR)")?;
+    let mededelingen = column("Mededelingen")?;
+
+    let mut transactions = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let field = |i: usize| record.get(i).unwrap_or_default().trim();
+
+        let date = NaiveDate::parse_from_str(field(datum), "%Y%m%d")
+            .or_else(|_| NaiveDate::parse_from_str(field(datum), "%Y-%m-%d"))
+            .map_err(|err| anyhow!("invalid date {:?}: {err}", field(datum)))?;
+        let amount: Money = field(bedrag).parse()?;
+        let amount = match field(af_bij) {
+            "Bij" => amount,
+            "Af" => -amount,
+            other => bail!("invalid Af Bij: {other:?}"),
+        };
+
+        transactions.push(Transaction {
+            date,
+            amount,
+            counterparty: field(naam).to_owned(),
+            iban: field(tegenrekening).to_owned(),
+            description: field(mededelingen).to_owned(),
+        });
+    }
+
+    Ok(transactions)
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::factuur::WorkItem;
+use crate::money::Money;
+
+/// The BTW treatment of a single work item.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BtwCategorie {
+    /// Algemeen tarief, 21%.
+    #[default]
+    Hoog,
+    /// Verlaagd tarief, 9%.
+    Laag,
+    /// Nultarief, 0%.
+    Nul,
+    /// Vrijgesteld van BTW, e.g. some educational work.
+    Vrijgesteld,
+    /// BTW verlegd naar de afnemer, for EU B2B customers.
+    Verlegd,
+}
+
+impl BtwCategorie {
+    pub const ALL: [BtwCategorie; 5] = [
+        BtwCategorie::Hoog,
+        BtwCategorie::Laag,
+        BtwCategorie::Nul,
+        BtwCategorie::Vrijgesteld,
+        BtwCategorie::Verlegd,
+    ];
+
+    /// Rate in basis points (2100 is 21%).
+    pub fn rate(&self) -> i64 {
+        match self {
+            BtwCategorie::Hoog => 2100,
+            BtwCategorie::Laag => 900,
+            BtwCategorie::Nul | BtwCategorie::Vrijgesteld | BtwCategorie::Verlegd => 0,
+        }
+    }
+
+    /// Key used in forms and in the serialized work items.
+    pub fn key(&self) -> &'static str {
+        match self {
+            BtwCategorie::Hoog => "hoog",
+            BtwCategorie::Laag => "laag",
+            BtwCategorie::Nul => "nul",
+            BtwCategorie::Vrijgesteld => "vrijgesteld",
+            BtwCategorie::Verlegd => "verlegd",
+        }
+    }
+}
+
+impl Display for BtwCategorie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            BtwCategorie::Hoog => "21%",
+            BtwCategorie::Laag => "9%",
+            BtwCategorie::Nul => "0%",
+            BtwCategorie::Vrijgesteld => "vrijgesteld",
+            BtwCategorie::Verlegd => "verlegd",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for BtwCategorie {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BtwCategorie::ALL
+            .into_iter()
+            .find(|c| c.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown BTW category: {s}"))
+    }
+}
+
+/// BTW over all work items of one category on a single invoice.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BtwRegel {
+    pub categorie: BtwCategorie,
+    /// The amount excl. BTW this line is calculated over.
+    pub grondslag: Money,
+    pub btw: Money,
+}
+
+impl BtwRegel {
+    /// How the line is labeled on the invoice.
+    pub fn label(&self) -> String {
+        match self.categorie {
+            BtwCategorie::Verlegd => "BTW verlegd".to_owned(),
+            BtwCategorie::Vrijgesteld => "Vrijgesteld van BTW".to_owned(),
+            categorie => format!("BTW {categorie}"),
+        }
+    }
+}
+
+/// Groups the work items per category and calculates the BTW for each.
+///
+/// BTW is rounded per rate per invoice: the line amounts of a category are
+/// summed first, and only the BTW over that sum is rounded to the cent.
+pub fn specificatie(work_items: &[WorkItem]) -> Vec<BtwRegel> {
+    let mut grondslagen: BTreeMap<BtwCategorie, Money> = BTreeMap::new();
+    for item in work_items {
+        *grondslagen.entry(item.btw).or_default() += item.euro;
+    }
+
+    grondslagen
+        .into_iter()
+        .map(|(categorie, grondslag)| BtwRegel {
+            categorie,
+            grondslag,
+            btw: grondslag.percentage(categorie.rate()),
+        })
+        .collect()
+}
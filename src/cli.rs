@@ -1,5 +1,8 @@
+use chrono::NaiveDate;
 use clap::{Parser, Subcommand};
 
+use std::path::PathBuf;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 pub struct Cli {
@@ -9,11 +12,118 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    Anita {
+    /// Prices the events in an .ics file, like an invoice
+    Agenda {
+        #[arg(short, long, default_value_t = 1)]
+        tenant: i64,
+        file: PathBuf,
+        /// The first day, as YYYY-MM-DD
+        #[arg(long)]
+        from: NaiveDate,
+        /// The last day, as YYYY-MM-DD
+        #[arg(long)]
+        until: NaiveDate,
+        /// The client to price the events for, by name
+        #[arg(long)]
+        client: Option<String>,
+        /// Only events in the calendar with this name
+        #[arg(long)]
+        calendar: Option<String>,
+        /// Only events with this category
+        #[arg(long)]
+        category: Option<String>,
+        /// Only events with this in their title, or matching it with * for any text
+        #[arg(long)]
+        title: Option<String>,
+    },
+    Clients {
+        /// The business whose clients these are
+        #[arg(short, long, default_value_t = 1)]
+        tenant: i64,
+        #[clap(subcommand)]
+        command: ClientsCommands,
+    },
+    /// Renders an existing invoice to stdout
+    Factuur {
+        #[arg(short, long, default_value_t = 1)]
+        tenant: i64,
+        #[arg(short, long)]
+        nummer: usize,
+        /// One of pdf, html or tekst
+        #[arg(short, long, default_value = "pdf")]
+        formaat: String,
+    },
+    /// Drafts the recurring invoices that are due, e.g. from cron
+    Herhaal,
+    /// Prices the shifts of a month in a schedule, like an invoice
+    #[command(alias = "anita")]
+    Rooster {
+        #[arg(short, long, default_value_t = 1)]
+        tenant: i64,
+        /// Of the form YYYY-MM
         #[arg(short, long)]
         month: String,
-        #[arg(short, long, default_value = "Noemi")]
-        name: String,
+        /// The name or id of the schedule, when the tenant has more than one
+        #[arg(short, long)]
+        bron: Option<String>,
     },
     Server,
+    Settings {
+        /// The business whose settings these are
+        #[arg(short, long, default_value_t = 1)]
+        tenant: i64,
+        #[clap(subcommand)]
+        command: SettingsCommands,
+    },
+    /// Manages the businesses that invoice from this deployment
+    Tenants {
+        #[clap(subcommand)]
+        command: TenantsCommands,
+    },
+    /// Manages who can log in
+    Users {
+        #[clap(subcommand)]
+        command: UsersCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClientsCommands {
+    /// Imports clients from clients.json, a CSV file or vCards. Only shows
+    /// what would change, unless --apply is given
+    Import {
+        file: PathBuf,
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SettingsCommands {
+    /// Fills in the settings from a JSON object with the fields of the
+    /// settings page, e.g. `{"business_name": "Creatief Vakvrouw"}`. Fields
+    /// that are left out keep their value
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TenantsCommands {
+    /// Adds a business, which fills in its details on the settings page
+    Add {
+        name: String,
+    },
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UsersCommands {
+    /// Adds a user to a tenant, with the password read from stdin
+    Add {
+        username: String,
+        #[arg(short, long)]
+        tenant: i64,
+        /// Either eigenaar, or boekhouder to only read reports and PDFs
+        #[arg(short, long, default_value = "eigenaar")]
+        role: String,
+    },
 }
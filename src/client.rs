@@ -0,0 +1,405 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::iter::zip;
+
+use crate::factuur::{Client, DEFAULT_HOURLY_RATE};
+use crate::money::Money;
+
+/// Everything we know about a client. An invoice only keeps the `Client`
+/// address, as it was when the invoice was issued.
+#[derive(Clone, Debug, Default)]
+pub struct ClientDetails {
+    pub id: i64,
+    pub name: String,
+    pub address: String,
+    pub zip: String,
+    /// Number in the Kamer van Koophandel register
+    pub kvk: String,
+    pub btw_id: String,
+    pub email: String,
+    /// Who to address at the client
+    pub contact: String,
+    pub country: String,
+    /// In days, when it differs from our usual payment term
+    pub payment_term: Option<u64>,
+    /// When it differs from our usual hourly rate
+    pub hourly_rate: Option<Money>,
+    /// Archived clients are not offered for new invoices anymore
+    pub archived: bool,
+}
+
+impl ClientDetails {
+    /// The address as it goes on an invoice.
+    pub fn client(&self) -> Client {
+        Client {
+            name: self.name.clone(),
+            address: self.address.clone(),
+            zip: self.zip.clone(),
+        }
+    }
+
+    pub fn hourly_rate(&self) -> Money {
+        self.hourly_rate.unwrap_or(DEFAULT_HOURLY_RATE)
+    }
+}
+
+/// A client in `clients.json`, keyed by a short name of our own.
+#[derive(Debug, Deserialize)]
+struct JsonClient {
+    name: String,
+    address: String,
+    zip: String,
+    #[serde(default)]
+    kvk: String,
+    #[serde(default)]
+    btw_id: String,
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    contact: String,
+    #[serde(default)]
+    country: String,
+    #[serde(default)]
+    payment_term: Option<u64>,
+    /// In euros, like on the client page
+    #[serde(default)]
+    hourly_rate: Option<String>,
+}
+
+/// Parses a list of clients, either as the `clients.json` from the README,
+/// a CSV file with a header row, or vCards.
+pub fn parse(contents: &str) -> Result<Vec<ClientDetails>> {
+    let contents = contents.trim_start_matches('\u{feff}').trim_start();
+    if contents.starts_with('{') {
+        parse_json(contents)
+    } else if contents
+        .get(..11)
+        .is_some_and(|s| s.eq_ignore_ascii_case("BEGIN:VCARD"))
+    {
+        parse_vcard(contents)
+    } else {
+        parse_csv(contents)
+    }
+}
+
+pub fn parse_json(json: &str) -> Result<Vec<ClientDetails>> {
+    let clients: BTreeMap<String, JsonClient> =
+        serde_json::from_str(json).context("invalid clients.json")?;
+
+    clients
+        .into_values()
+        .map(|c| {
+            Ok(ClientDetails {
+                name: c.name,
+                address: c.address,
+                zip: c.zip,
+                kvk: c.kvk,
+                btw_id: c.btw_id,
+                email: c.email,
+                contact: c.contact,
+                country: c.country,
+                payment_term: c.payment_term,
+                hourly_rate: c.hourly_rate.as_deref().map(str::parse).transpose()?,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Parses a CSV file whose header names the columns, in English like the
+/// fields of `clients.json` or in Dutch like on the client page. Only the
+/// name is required.
+pub fn parse_csv(csv: &str) -> Result<Vec<ClientDetails>> {
+    let header = csv.lines().next().unwrap_or_default();
+    let delimiter = if header.contains(';') { b';' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers.iter().position(|h| {
+            let h = h.trim().to_lowercase().replace([' ', '-'], "_");
+            names.contains(&h.as_str())
+        })
+    };
+    let name = column(&["name", "naam"]).ok_or_else(|| anyhow!("missing column \"name\""))?;
+    let address = column(&["address", "adres"]);
+    let zip = column(&["zip", "postcode"]);
+    let kvk = column(&["kvk", "kvk_nummer"]);
+    let btw_id = column(&["btw_id", "btw", "vat", "vat_id"]);
+    let email = column(&["email", "e_mail"]);
+    let contact = column(&["contact", "contactpersoon"]);
+    let country = column(&["country", "land"]);
+    let payment_term = column(&["payment_term", "betalingstermijn"]);
+    let hourly_rate = column(&["hourly_rate", "uurtarief"]);
+
+    let mut clients = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .unwrap_or_default()
+                .trim()
+                .to_owned()
+        };
+        if field(Some(name)).is_empty() {
+            continue;
+        }
+
+        let payment_term = field(payment_term);
+        let hourly_rate = field(hourly_rate);
+        clients.push(ClientDetails {
+            name: field(Some(name)),
+            address: field(address),
+            zip: field(zip),
+            kvk: field(kvk),
+            btw_id: field(btw_id),
+            email: field(email),
+            contact: field(contact),
+            country: field(country),
+            payment_term: match payment_term.is_empty() {
+                true => None,
+                false => Some(payment_term.parse()?),
+            },
+            hourly_rate: match hourly_rate.is_empty() {
+                true => None,
+                false => Some(hourly_rate.parse()?),
+            },
+            ..Default::default()
+        });
+    }
+
+    Ok(clients)
+}
+
+/// Parses vCards as exported from an address book. For a company the `ORG`
+/// is the client and the person in `FN` its contact.
+pub fn parse_vcard(vcard: &str) -> Result<Vec<ClientDetails>> {
+    // Long lines are folded onto lines that start with whitespace
+    let mut lines: Vec<String> = vec![];
+    for line in vcard.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end().to_owned()),
+        }
+    }
+
+    let mut clients = vec![];
+    let mut card: Option<(ClientDetails, String)> = None;
+    for line in lines {
+        let Some((property, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = property
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .to_uppercase();
+        // Grouped properties like item1.ADR
+        let property = property.rsplit('.').next().unwrap_or_default();
+
+        match (property, card.as_mut()) {
+            ("BEGIN", _) => card = Some((ClientDetails::default(), String::new())),
+            ("END", Some(_)) => {
+                let (mut client, org) = card.take().unwrap();
+                if !org.is_empty() {
+                    client.contact = std::mem::replace(&mut client.name, org);
+                }
+                if client.name.is_empty() {
+                    bail!("vCard without FN or ORG");
+                }
+                clients.push(client);
+            }
+            ("FN", Some((client, _))) => client.name = unescape(value),
+            ("ORG", Some((_, org))) => {
+                *org = split_escaped(value).into_iter().next().unwrap_or_default()
+            }
+            ("EMAIL", Some((client, _))) if client.email.is_empty() => {
+                client.email = unescape(value)
+            }
+            ("ADR", Some((client, _))) if client.address.is_empty() => {
+                // PO box; extended address; street; locality; region; postal code; country
+                let parts = split_escaped(value);
+                let part = |i: usize| parts.get(i).map(|s| s.trim()).unwrap_or_default();
+                client.address = part(2).replace('\n', ", ");
+                client.zip = [part(5), part(3)]
+                    .into_iter()
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                client.country = part(6).to_owned();
+            }
+            _ => (),
+        }
+    }
+
+    Ok(clients)
+}
+
+/// Splits a structured vCard value on the semicolons that are not escaped.
+fn split_escaped(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => parts.last_mut().unwrap().push('\n'),
+                Some(c) => parts.last_mut().unwrap().push(c),
+                None => (),
+            },
+            ';' => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    split_escaped(value).join(";").trim().to_owned()
+}
+
+/// What importing a client would change in the client table.
+#[derive(Clone, Debug)]
+pub enum Wijziging {
+    Nieuw(ClientDetails),
+    /// An existing client, with the fields that change as (field, old, new)
+    Bijgewerkt {
+        klant: ClientDetails,
+        velden: Vec<(&'static str, String, String)>,
+    },
+    Ongewijzigd(ClientDetails),
+}
+
+impl Wijziging {
+    /// The client as it is stored when the import goes through.
+    pub fn klant(&self) -> &ClientDetails {
+        match self {
+            Wijziging::Nieuw(klant) => klant,
+            Wijziging::Bijgewerkt { klant, .. } => klant,
+            Wijziging::Ongewijzigd(klant) => klant,
+        }
+    }
+
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Wijziging::Ongewijzigd(_))
+    }
+}
+
+impl Display for Wijziging {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Wijziging::Nieuw(klant) => {
+                write!(f, "+ {} ({}, {})", klant.name, klant.address, klant.zip)
+            }
+            Wijziging::Bijgewerkt { klant, velden } => {
+                write!(f, "~ {}", klant.name)?;
+                for (veld, oud, nieuw) in velden {
+                    write!(f, "\n    {veld}: {oud:?} -> {nieuw:?}")?;
+                }
+                Ok(())
+            }
+            Wijziging::Ongewijzigd(klant) => write!(f, "= {}", klant.name),
+        }
+    }
+}
+
+impl ClientDetails {
+    /// The fields that can be imported, by their label on the client page.
+    fn velden(&self) -> [(&'static str, String); 9] {
+        [
+            ("Adres", self.address.clone()),
+            ("Postcode en stad", self.zip.clone()),
+            ("Land", self.country.clone()),
+            ("KvK-nummer", self.kvk.clone()),
+            ("BTW-id", self.btw_id.clone()),
+            ("E-mail", self.email.clone()),
+            ("Contactpersoon", self.contact.clone()),
+            (
+                "Betalingstermijn",
+                self.payment_term.map(|t| t.to_string()).unwrap_or_default(),
+            ),
+            (
+                "Uurtarief",
+                self.hourly_rate.map(|r| r.to_string()).unwrap_or_default(),
+            ),
+        ]
+    }
+
+    /// This client with the fields that are filled in on `other`.
+    fn updated_with(&self, other: &ClientDetails) -> ClientDetails {
+        let pick = |new: &String, old: &String| match new.is_empty() {
+            true => old.clone(),
+            false => new.clone(),
+        };
+        ClientDetails {
+            id: self.id,
+            name: self.name.clone(),
+            address: pick(&other.address, &self.address),
+            zip: pick(&other.zip, &self.zip),
+            kvk: pick(&other.kvk, &self.kvk),
+            btw_id: pick(&other.btw_id, &self.btw_id),
+            email: pick(&other.email, &self.email),
+            contact: pick(&other.contact, &self.contact),
+            country: pick(&other.country, &self.country),
+            payment_term: other.payment_term.or(self.payment_term),
+            hourly_rate: other.hourly_rate.or(self.hourly_rate),
+            archived: self.archived,
+        }
+    }
+}
+
+/// Compares imported clients with the existing ones, matching them by name.
+/// Fields that are empty in the import leave the existing value alone, and
+/// a client that occurs twice in the import is combined into one.
+pub fn vergelijk(bestaand: &[ClientDetails], import: Vec<ClientDetails>) -> Vec<Wijziging> {
+    let key = |name: &str| name.trim().to_lowercase();
+
+    let mut combined: Vec<ClientDetails> = vec![];
+    for client in import {
+        match combined
+            .iter_mut()
+            .find(|c| key(&c.name) == key(&client.name))
+        {
+            Some(c) => *c = c.updated_with(&client),
+            None => combined.push(ClientDetails {
+                id: 0,
+                name: client.name.trim().to_owned(),
+                archived: false,
+                ..client
+            }),
+        }
+    }
+
+    combined
+        .into_iter()
+        .map(
+            |client| match bestaand.iter().find(|b| key(&b.name) == key(&client.name)) {
+                None => Wijziging::Nieuw(ClientDetails {
+                    country: match client.country.is_empty() {
+                        true => "Nederland".to_owned(),
+                        false => client.country.clone(),
+                    },
+                    ..client
+                }),
+                Some(b) => {
+                    let klant = b.updated_with(&client);
+                    let velden: Vec<(&'static str, String, String)> =
+                        zip(b.velden(), klant.velden())
+                            .filter(|((_, oud), (_, nieuw))| oud != nieuw)
+                            .map(|((veld, oud), (_, nieuw))| (veld, oud, nieuw))
+                            .collect();
+                    match velden.is_empty() {
+                        true => Wijziging::Ongewijzigd(klant),
+                        false => Wijziging::Bijgewerkt { klant, velden },
+                    }
+                }
+            },
+        )
+        .collect()
+}
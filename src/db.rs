@@ -1,31 +1,115 @@
+use std::collections::{BTreeMap, HashMap};
+
 use anyhow::{anyhow, Result};
-use chrono::{TimeZone, Utc};
-use sqlx::SqliteConnection;
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use sqlx::{Connection, SqliteConnection};
+
+use crate::aangifte::Quarter;
+use crate::bank::Transaction;
+use crate::client::{ClientDetails, Wijziging};
+use crate::expense::{Expense, Receipt};
+use crate::factuur::{Client, Concept, Factuur, FactuurRef, WorkItem};
+use crate::ledger::OpeningBalance;
+use crate::money::Money;
+use crate::nummering::{self, Nummering};
+use crate::payment::Payment;
+use crate::rate::Rate;
+use crate::recurring::RecurringInvoice;
+use crate::reminder::{self, Reminder, ReminderKind};
+use crate::schedule::ScheduleSource;
+use crate::settings::Settings;
+use crate::tenant::Tenant;
+use crate::user::{Role, User};
 
-use crate::factuur::{Client, Factuur};
+/// How often `add_invoice` renders the PDF again when another invoice of the
+/// tenant took its number in the meantime.
+const NUMBER_ATTEMPTS: usize = 3;
 
-pub async fn add_invoice(
+/// Assigns the next number in the sequence of `tenant` to `factuur` and
+/// stores it with its PDF in a single transaction. The PDF is rendered by
+/// `render` before the transaction starts, so that the database is not
+/// locked while it renders. If another invoice of the tenant took the number
+/// in the meantime, the PDF is rendered again with the next one. The draft
+/// `concept` it was made from is removed in the same transaction, so that a
+/// draft is only ever made into one invoice.
+pub async fn add_invoice<F>(
     conn: &mut SqliteConnection,
-    factuur: &Factuur,
-    pdf: &Vec<u8>,
-) -> Result<()> {
-    // First make sure that the respective Client entry exists
+    tenant: i64,
+    factuur: &mut Factuur,
+    concept: Option<i64>,
+    nummering: &Nummering,
+    render: F,
+) -> Result<()>
+where
+    F: Fn(&Factuur) -> Result<Vec<u8>>,
+{
+    let jaar = factuur.date.year();
+    for _ in 0..NUMBER_ATTEMPTS {
+        let volgnummer = next_volgnummer(&mut *conn, tenant, nummering, jaar).await?;
+        factuur.kenmerk = nummering.format(jaar, volgnummer);
+
+        let pdf = render(factuur)?;
+
+        // Rolls back when dropped, also when this future is
+        let mut tx = conn.begin().await?;
+        ensure_client(&mut tx, tenant, &factuur.client).await?;
+        // The insert took the write lock, so the numbers can't change
+        // anymore after this check. The nummer is not on the PDF, so invoices
+        // of other tenants don't make us render it again.
+        if next_volgnummer(&mut tx, tenant, nummering, jaar).await? != volgnummer {
+            continue;
+        }
+        factuur.nummer = next_nummer(&mut tx).await? as usize;
+        insert_invoice(&mut tx, tenant, factuur, jaar, volgnummer, pdf).await?;
+        if let Some(id) = concept {
+            let deleted = sqlx::query!("DELETE FROM draft WHERE tenant = ? AND id = ?", tenant, id)
+                .execute(&mut *tx)
+                .await?;
+            if deleted.rows_affected() != 1 {
+                return Err(anyhow!("draft {id} is already an invoice"));
+            }
+        }
+        tx.commit().await?;
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "the invoice number was taken {NUMBER_ATTEMPTS} times while rendering the PDF"
+    ))
+}
+
+/// Makes sure that a Client entry with the name of `client` exists. As it
+/// always writes, this also takes the write lock of a transaction.
+async fn ensure_client(conn: &mut SqliteConnection, tenant: i64, client: &Client) -> Result<()> {
     sqlx::query!(
         r#"
-INSERT OR IGNORE INTO client ( name, address, zip )
-VALUES ( ?, ?, ? )
+INSERT OR IGNORE INTO client ( tenant, name, address, zip )
+VALUES ( ?, ?, ?, ? )
         "#,
-        factuur.client.name,
-        factuur.client.address,
-        factuur.client.zip
+        tenant,
+        client.name,
+        client.address,
+        client.zip
     )
     .execute(&mut *conn)
     .await?;
 
+    Ok(())
+}
+
+async fn insert_invoice(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur: &Factuur,
+    jaar: i32,
+    volgnummer: u32,
+    pdf: Vec<u8>,
+) -> Result<()> {
     let client = sqlx::query!(
         r#"
-SELECT id FROM client WHERE name = ?
+SELECT id FROM client WHERE tenant = ? AND name = ?
         "#,
+        tenant,
         factuur.client.name
     )
     .fetch_one(&mut *conn)
@@ -45,24 +129,275 @@ VALUES ( ? )
 
     // Calculate some additional information to store alongside the mvp
     // invoice in the database.
-    let nummer = factuur.nummer as i32;
     let work_items = serde_json::to_string(&factuur.work_items)?;
+    let btw_regels = serde_json::to_string(&factuur.btw)?;
+    let (subtotal, btw, total) = (
+        factuur.subtotal.cents(),
+        factuur.btw_totaal().cents(),
+        factuur.total.cents(),
+    );
     let date = factuur.date.to_rfc3339();
+    let credit_for = factuur.credit_for.as_ref().map(|f| f.nummer as i64);
+    let payment_term = factuur.payment_term as i64;
+    let nummer = factuur.nummer as i64;
 
-    // Insert the new invoice into the database
+    // Insert the new invoice into the database, with the client address as
+    // it is printed on the PDF
     sqlx::query!(
         r#"
-INSERT INTO invoice ( nummer, client, pdf, work_items, subtotal, btw, total, created_at )
-VALUES ( ?, ?, ?, ?, ?, ?, ?, ? )
+INSERT INTO invoice ( tenant, nummer, jaar, volgnummer, kenmerk, client, client_name, client_address,
+    client_zip, pdf, work_items, subtotal, btw, btw_regels, total, created_at, credit_for, payment_term )
+VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ? )
         "#,
+        tenant,
         nummer,
+        jaar,
+        volgnummer,
+        factuur.kenmerk,
         client.id,
+        factuur.client.name,
+        factuur.client.address,
+        factuur.client.zip,
         pdf_id,
         work_items,
-        factuur.subtotal,
-        factuur.btw,
-        factuur.total,
-        date
+        subtotal,
+        btw,
+        btw_regels,
+        total,
+        date,
+        credit_for,
+        payment_term
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The nummer of the next invoice. It is what payments, reminders and URLs
+/// refer to invoices by, so it counts over all tenants like its unique column
+/// does. It is never printed, unlike the volgnummer. Deleted invoices keep
+/// their numbers, so these are never handed out twice.
+async fn next_nummer(conn: &mut SqliteConnection) -> Result<i64> {
+    let nummer = sqlx::query!(r#"SELECT COALESCE(MAX(nummer), 0) AS "nummer!: i64" FROM invoice"#)
+        .fetch_one(&mut *conn)
+        .await?
+        .nummer;
+
+    Ok(nummer + 1)
+}
+
+/// The volgnummer of the next invoice of `tenant` in `jaar`, counted per
+/// tenant and, when `nummering` starts over every year, per year.
+async fn next_volgnummer(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    nummering: &Nummering,
+    jaar: i32,
+) -> Result<u32> {
+    let volgnummer = match nummering.resets_yearly() {
+        true => {
+            sqlx::query!(
+                r#"SELECT COALESCE(MAX(volgnummer), 0) AS "volgnummer!: i64" FROM invoice WHERE tenant = ? AND jaar = ?"#,
+                tenant,
+                jaar
+            )
+            .fetch_one(&mut *conn)
+            .await?
+            .volgnummer
+        }
+        false => {
+            sqlx::query!(
+                r#"SELECT COALESCE(MAX(volgnummer), 0) AS "volgnummer!: i64" FROM invoice WHERE tenant = ?"#,
+                tenant
+            )
+            .fetch_one(&mut *conn)
+            .await?
+            .volgnummer
+        }
+    };
+
+    Ok(volgnummer as u32 + 1)
+}
+
+/// The kenmerk the next invoice in `jaar` would get, to show in the form.
+pub async fn next_kenmerk(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    nummering: &Nummering,
+    jaar: i32,
+) -> Result<String> {
+    let volgnummer = next_volgnummer(&mut *conn, tenant, nummering, jaar).await?;
+    Ok(nummering.format(jaar, volgnummer))
+}
+
+/// Invoice numbers that are missing from the sequence, formatted as ranges
+/// of kenmerken. Deleted invoices still count as issued.
+pub async fn get_invoice_gaps(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    nummering: &Nummering,
+) -> Result<Vec<String>> {
+    let rows = sqlx::query!(
+        "SELECT jaar, volgnummer FROM invoice WHERE tenant = ?",
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut periodes: BTreeMap<i64, Vec<u32>> = BTreeMap::new();
+    for row in rows {
+        let periode = if nummering.resets_yearly() {
+            row.jaar
+        } else {
+            0
+        };
+        periodes
+            .entry(periode)
+            .or_default()
+            .push(row.volgnummer as u32);
+    }
+
+    Ok(periodes
+        .into_iter()
+        .flat_map(|(jaar, volgnummers)| {
+            nummering::gaps(volgnummers).into_iter().map(move |gap| {
+                let (first, last) = (
+                    nummering.format(jaar as i32, *gap.start()),
+                    nummering.format(jaar as i32, *gap.end()),
+                );
+                match gap.start() == gap.end() {
+                    true => first,
+                    false => format!("{first} t/m {last}"),
+                }
+            })
+        })
+        .collect())
+}
+
+pub async fn add_expense(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    expense: &Expense,
+    receipt: Option<&Receipt>,
+) -> Result<i64> {
+    // Without the expense the receipt would be left behind
+    let mut tx = conn.begin().await?;
+
+    // Insert the receipt binary blob into the database, if there is one
+    let receipt_id = match receipt {
+        Some(receipt) => Some(
+            sqlx::query!(
+                r#"
+INSERT INTO receipt ( filename, content_type, file )
+VALUES ( ?, ?, ? )
+                "#,
+                receipt.filename,
+                receipt.content_type,
+                receipt.file
+            )
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid(),
+        ),
+        None => None,
+    };
+
+    let btw_categorie = expense.btw_categorie.key();
+    let (excl, btw) = (expense.excl.cents(), expense.btw.cents());
+
+    let id = sqlx::query!(
+        r#"
+INSERT INTO expense ( tenant, date, supplier, category, btw_categorie, excl, btw, receipt )
+VALUES ( ?, ?, ?, ?, ?, ?, ?, ? )
+        "#,
+        tenant,
+        expense.date,
+        expense.supplier,
+        expense.category,
+        btw_categorie,
+        excl,
+        btw,
+        receipt_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// All expenses that were not deleted, with their id and whether a receipt
+/// was uploaded.
+pub async fn get_expenses(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<(i64, Expense, bool)>> {
+    let expenses = sqlx::query!(
+        r#"
+SELECT id, date, supplier, category, btw_categorie, excl, btw, receipt IS NOT NULL AS "has_receipt: bool"
+FROM expense
+WHERE tenant = ? AND deleted = 0
+ORDER BY date DESC, id DESC
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    expenses
+        .into_iter()
+        .map(|row| {
+            Ok((
+                row.id,
+                Expense {
+                    date: row.date,
+                    supplier: row.supplier,
+                    category: row.category,
+                    btw_categorie: row.btw_categorie.parse()?,
+                    excl: Money::from_cents(row.excl),
+                    btw: Money::from_cents(row.btw),
+                },
+                row.has_receipt,
+            ))
+        })
+        .collect()
+}
+
+pub async fn get_receipt(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    expense_id: i64,
+) -> Result<Receipt> {
+    sqlx::query_as!(
+        Receipt,
+        r#"
+SELECT filename, content_type, file FROM receipt
+INNER JOIN expense ON expense.receipt = receipt.id
+WHERE expense.tenant = ? AND expense.id = ?
+        "#,
+        tenant,
+        expense_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| anyhow!(err))
+}
+
+pub async fn delete_expense(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    expense_id: i64,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+UPDATE expense
+SET deleted = 1
+WHERE tenant = ? AND id = ?
+        "#,
+        tenant,
+        expense_id
     )
     .execute(&mut *conn)
     .await?;
@@ -77,6 +412,7 @@ pub enum InvoiceStatus {
 
 pub async fn get_invoices(
     conn: &mut SqliteConnection,
+    tenant: i64,
     invoice_status: InvoiceStatus,
 ) -> Result<Vec<Factuur>> {
     let deleted = match invoice_status {
@@ -86,118 +422,1518 @@ pub async fn get_invoices(
 
     let invoices = sqlx::query!(
         r#"
-SELECT nummer, client.name, client.address, client.zip, work_items, subtotal, btw, total, created_at FROM invoice
-INNER JOIN client ON client.id = invoice.client
-WHERE deleted = ?
+SELECT invoice.id, invoice.nummer, invoice.kenmerk, invoice.client_name, invoice.client_address, invoice.client_zip,
+    invoice.work_items, invoice.subtotal, invoice.btw_regels, invoice.total, invoice.created_at, invoice.payment_term,
+    invoice.credit_for, original.kenmerk AS original_kenmerk
+FROM invoice
+LEFT JOIN invoice AS original ON original.nummer = invoice.credit_for
+WHERE invoice.tenant = ? AND invoice.deleted = ?
         "#,
+        tenant,
         deleted
     )
     .fetch_all(&mut *conn)
     .await?;
 
+    let mut payments = get_payments(&mut *conn, tenant).await?;
+    let mut reminders = get_reminders(&mut *conn, tenant).await?;
+
     let res = invoices
         .iter()
         .map(|row| Factuur {
             nummer: row.nummer as usize,
+            kenmerk: row.kenmerk.clone(),
             client: Client {
-                name: row.name.clone(),
-                address: row.address.clone(),
-                zip: row.zip.clone(),
+                name: row.client_name.clone(),
+                address: row.client_address.clone(),
+                zip: row.client_zip.clone(),
             },
             work_items: serde_json::from_str(&row.work_items).unwrap(),
-            subtotal: row.subtotal,
-            btw: row.btw,
-            total: row.total,
+            subtotal: Money::from_cents(row.subtotal),
+            btw: serde_json::from_str(&row.btw_regels).unwrap(),
+            total: Money::from_cents(row.total),
             date: Utc.from_local_datetime(&row.created_at).unwrap(),
+            payment_term: row.payment_term as u64,
+            payments: payments.remove(&row.id).unwrap_or_default(),
+            reminders: reminders.remove(&row.id).unwrap_or_default(),
+            credit_for: row.credit_for.map(|n| FactuurRef {
+                nummer: n as usize,
+                kenmerk: row
+                    .original_kenmerk
+                    .clone()
+                    .unwrap_or_else(|| n.to_string()),
+            }),
+            credits: vec![],
         })
         .collect();
 
-    Ok(res)
+    Ok(with_credits(res))
 }
 
-pub async fn get_all_clients(conn: &mut SqliteConnection) -> Result<Vec<Client>> {
-    sqlx::query_as!(Client, "SELECT name, address, zip FROM client")
-        .fetch_all(&mut *conn)
-        .await
-        .map_err(|err| anyhow!(err))
+/// Links the credit notes among `invoices` to the invoices they correct.
+fn with_credits(mut invoices: Vec<Factuur>) -> Vec<Factuur> {
+    let mut credits: HashMap<usize, Vec<(NaiveDate, Money)>> = HashMap::new();
+    for i in invoices.iter() {
+        if let Some(original) = &i.credit_for {
+            credits
+                .entry(original.nummer)
+                .or_default()
+                .push((i.date.date_naive(), -i.total));
+        }
+    }
+    for i in invoices.iter_mut() {
+        i.credits = credits.remove(&i.nummer).unwrap_or_default();
+    }
+    invoices
 }
 
-pub async fn get_client(conn: &mut SqliteConnection, client_name: &str) -> Result<Option<Client>> {
-    sqlx::query_as!(
-        Client,
+/// All payments of `tenant`, keyed by the id of the invoice they belong to.
+async fn get_payments(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<HashMap<i64, Vec<Payment>>> {
+    let rows = sqlx::query!(
         r#"
-SELECT name, address, zip FROM client
-WHERE name = ?
+SELECT payment.invoice, payment.date, payment.amount, payment.method FROM payment
+INNER JOIN invoice ON invoice.id = payment.invoice
+WHERE invoice.tenant = ?
+ORDER BY payment.date, payment.id
         "#,
-        client_name
+        tenant
     )
-    .fetch_optional(&mut *conn)
-    .await
-    .map_err(|err| anyhow!(err))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut payments: HashMap<i64, Vec<Payment>> = HashMap::new();
+    for row in rows {
+        payments.entry(row.invoice).or_default().push(Payment {
+            date: row.date,
+            amount: Money::from_cents(row.amount),
+            method: row.method.parse()?,
+        });
+    }
+
+    Ok(payments)
 }
 
-pub async fn most_recent_invoice(conn: &mut SqliteConnection) -> Result<Option<usize>> {
-    let res = sqlx::query!("SELECT MAX(nummer) AS nummer FROM invoice")
-        .fetch_one(&mut *conn)
-        .await?;
+/// All reminders that `tenant` sent, keyed by the id of their invoice.
+async fn get_reminders(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<HashMap<i64, Vec<Reminder>>> {
+    let rows = sqlx::query!(
+        r#"
+SELECT reminder.invoice, reminder.kind, reminder.date FROM reminder
+INNER JOIN invoice ON invoice.id = reminder.invoice
+WHERE invoice.tenant = ?
+ORDER BY reminder.date, reminder.id
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
 
-    match res.nummer {
-        Some(n) => Ok(Some(n as usize)),
-        None => Ok(None),
+    let mut reminders: HashMap<i64, Vec<Reminder>> = HashMap::new();
+    for row in rows {
+        reminders.entry(row.invoice).or_default().push(Reminder {
+            kind: row.kind.parse()?,
+            date: row.date,
+        });
     }
+
+    Ok(reminders)
 }
 
-pub async fn get_pdf(
+/// Stores a reminder together with its PDF, which logs it as sent. Returns
+/// false when this kind of reminder was already sent for the invoice.
+pub async fn add_reminder(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur_nummer: u32,
+    reminder: &Reminder,
+    pdf: &Vec<u8>,
+) -> Result<bool> {
+    let mut tx = conn.begin().await?;
+    let invoice = sqlx::query!(
+        "SELECT id FROM invoice WHERE tenant = ? AND nummer = ?",
+        tenant,
+        factuur_nummer
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow!("no invoice with nummer {factuur_nummer}"))?;
+
+    let pdf_id = sqlx::query!("INSERT INTO pdf ( file ) VALUES ( ? )", pdf)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+    // Each kind of reminder is sent once per invoice. When it was stored in
+    // the meantime, like on a double submit, the PDF is rolled back too.
+    let kind = reminder.kind.key();
+    let inserted = sqlx::query!(
+        r#"
+INSERT INTO reminder ( invoice, kind, date, pdf ) VALUES ( ?, ?, ?, ? )
+ON CONFLICT ( invoice, kind ) DO NOTHING
+        "#,
+        invoice.id,
+        kind,
+        reminder.date,
+        pdf_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if inserted.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn get_reminder_pdf(
     conn: &mut SqliteConnection,
+    tenant: i64,
     factuur_nummer: u32,
+    kind: ReminderKind,
 ) -> Result<(String, Vec<u8>)> {
+    let key = kind.key();
     let res = sqlx::query!(
         r#"
-SELECT client.name, pdf FROM invoice
-INNER JOIN client ON client.id = invoice.client
-WHERE nummer = ?
+SELECT invoice.client_name, invoice.kenmerk, pdf.file FROM reminder
+INNER JOIN invoice ON invoice.id = reminder.invoice
+INNER JOIN pdf ON pdf.id = reminder.pdf
+WHERE invoice.tenant = ? AND invoice.nummer = ? AND reminder.kind = ?
         "#,
-        factuur_nummer
+        tenant,
+        factuur_nummer,
+        key
     )
     .fetch_one(&mut *conn)
     .await?;
 
-    let pdf = sqlx::query!("SELECT file FROM pdf WHERE id = ?", res.pdf)
-        .fetch_one(&mut *conn)
-        .await?;
-
     Ok((
-        format!("Factuur {} {}.pdf", res.name.clone(), factuur_nummer),
-        pdf.file,
+        reminder::filename(kind, &res.client_name, &res.kenmerk),
+        res.file,
     ))
 }
 
-pub enum SoftDeleteAction {
-    Restore,
-    Delete,
+pub async fn add_payment(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur_nummer: u32,
+    payment: &Payment,
+) -> Result<i64> {
+    let amount = payment.amount.cents();
+    let method = payment.method.key();
+
+    let res = sqlx::query!(
+        r#"
+INSERT INTO payment ( invoice, date, amount, method )
+SELECT id, ?, ?, ? FROM invoice
+WHERE tenant = ? AND nummer = ?
+        "#,
+        payment.date,
+        amount,
+        method,
+        tenant,
+        factuur_nummer
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(anyhow!("no invoice with nummer {factuur_nummer}"));
+    }
+
+    Ok(res.last_insert_rowid())
 }
 
-pub async fn soft_delete_invoice(
+/// Stores the draft with `id`, or a new draft when there is none. Returns
+/// the id of the draft.
+pub async fn save_draft(
     conn: &mut SqliteConnection,
-    factuur_nummer: u32,
-    action: SoftDeleteAction,
-) -> Result<()> {
-    let deleted = match action {
-        SoftDeleteAction::Restore => 0,
-        SoftDeleteAction::Delete => 1,
+    tenant: i64,
+    id: Option<i64>,
+    client: &Client,
+    work_items: &[WorkItem],
+    credit_for: Option<usize>,
+) -> Result<i64> {
+    let work_items = serde_json::to_string(work_items)?;
+    let credit_for = credit_for.map(|n| n as i64);
+
+    let Some(id) = id else {
+        let id = sqlx::query!(
+            r#"
+INSERT INTO draft ( tenant, client_name, client_address, client_zip, work_items, credit_for )
+VALUES ( ?, ?, ?, ?, ?, ? )
+            "#,
+            tenant,
+            client.name,
+            client.address,
+            client.zip,
+            work_items,
+            credit_for
+        )
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+        return Ok(id);
     };
 
+    let res = sqlx::query!(
+        r#"
+UPDATE draft
+SET client_name = ?, client_address = ?, client_zip = ?, work_items = ?, credit_for = ?, updated_at = datetime('now')
+WHERE tenant = ? AND id = ?
+        "#,
+        client.name,
+        client.address,
+        client.zip,
+        work_items,
+        credit_for,
+        tenant,
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(anyhow!("no draft with id {id}"));
+    }
+
+    Ok(id)
+}
+
+/// All drafts, most recently edited first.
+pub async fn get_drafts(conn: &mut SqliteConnection, tenant: i64) -> Result<Vec<Concept>> {
+    let rows = sqlx::query!(
+        r#"
+SELECT id, client_name, client_address, client_zip, work_items, credit_for, recurring, updated_at
+FROM draft
+WHERE tenant = ?
+ORDER BY updated_at DESC, id DESC
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(Concept {
+                id: row.id,
+                client: Client {
+                    name: row.client_name,
+                    address: row.client_address,
+                    zip: row.client_zip,
+                },
+                work_items: serde_json::from_str(&row.work_items)?,
+                credit_for: row.credit_for.map(|n| n as usize),
+                recurring: row.recurring,
+                updated_at: Utc.from_utc_datetime(&row.updated_at),
+            })
+        })
+        .collect()
+}
+
+pub async fn get_draft(conn: &mut SqliteConnection, tenant: i64, id: i64) -> Result<Concept> {
+    get_drafts(conn, tenant)
+        .await?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| anyhow!("no draft with id {id}"))
+}
+
+pub async fn delete_draft(conn: &mut SqliteConnection, tenant: i64, id: i64) -> Result<()> {
+    sqlx::query!("DELETE FROM draft WHERE tenant = ? AND id = ?", tenant, id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn add_recurring_invoice(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    recurring: &RecurringInvoice,
+) -> Result<i64> {
+    let work_items = serde_json::to_string(&recurring.work_items)?;
+    let frequency = recurring.frequency.key();
+
+    let id = sqlx::query!(
+        r#"
+INSERT INTO recurring_invoice ( tenant, client_name, client_address, client_zip, work_items, frequency, next_run )
+VALUES ( ?, ?, ?, ?, ?, ?, ? )
+        "#,
+        tenant,
+        recurring.client.name,
+        recurring.client.address,
+        recurring.client.zip,
+        work_items,
+        frequency,
+        recurring.next_run
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(id)
+}
+
+pub async fn get_recurring_invoices(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<RecurringInvoice>> {
+    let rows = sqlx::query!(
+        r#"
+SELECT id, client_name, client_address, client_zip, work_items, frequency, next_run
+FROM recurring_invoice
+WHERE tenant = ?
+ORDER BY next_run, id
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(RecurringInvoice {
+                id: row.id,
+                client: Client {
+                    name: row.client_name,
+                    address: row.client_address,
+                    zip: row.client_zip,
+                },
+                work_items: serde_json::from_str(&row.work_items)?,
+                frequency: row.frequency.parse()?,
+                next_run: row.next_run,
+            })
+        })
+        .collect()
+}
+
+pub async fn delete_recurring_invoice(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+) -> Result<()> {
     sqlx::query!(
+        "DELETE FROM recurring_invoice WHERE tenant = ? AND id = ?",
+        tenant,
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Drafts every run of a recurring invoice that is due on `today`, for all
+/// tenants, and moves their next run forward. Returns the number of drafts
+/// that were made.
+pub async fn create_due_drafts(conn: &mut SqliteConnection, today: NaiveDate) -> Result<usize> {
+    // When a cron job and the server run at the same time, SQLite lets only
+    // one of them write after reading what is due, so that they do not both
+    // draft the same run. The other one fails and is rolled back.
+    let mut tx = conn.begin().await?;
+    let created = insert_due_drafts(&mut tx, today).await?;
+    tx.commit().await?;
+    Ok(created)
+}
+
+async fn insert_due_drafts(conn: &mut SqliteConnection, today: NaiveDate) -> Result<usize> {
+    let mut created = 0;
+    for tenant in get_tenants(&mut *conn).await? {
+        created += insert_due_drafts_of(&mut *conn, tenant.id, today).await?;
+    }
+
+    Ok(created)
+}
+
+async fn insert_due_drafts_of(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    today: NaiveDate,
+) -> Result<usize> {
+    let mut created = 0;
+    for recurring in get_recurring_invoices(&mut *conn, tenant).await? {
+        let runs = recurring.due_runs(today);
+        let Some(last) = runs.last() else {
+            continue;
+        };
+
+        for run in &runs {
+            let work_items = serde_json::to_string(&recurring.work_items_for(*run))?;
+            sqlx::query!(
+                r#"
+INSERT INTO draft ( tenant, client_name, client_address, client_zip, work_items, recurring )
+VALUES ( ?, ?, ?, ?, ?, ? )
+                "#,
+                tenant,
+                recurring.client.name,
+                recurring.client.address,
+                recurring.client.zip,
+                work_items,
+                recurring.id
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let next_run = recurring.frequency.next(*last);
+        sqlx::query!(
+            "UPDATE recurring_invoice SET next_run = ? WHERE id = ?",
+            next_run,
+            recurring.id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        created += runs.len();
+    }
+
+    Ok(created)
+}
+
+/// Stores a transaction from a bank statement, unless it was imported
+/// before. Returns the id of the new transaction.
+pub async fn add_bank_transaction(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    transaction: &Transaction,
+) -> Result<Option<i64>> {
+    let amount = transaction.amount.cents();
+    let fingerprint = transaction.fingerprint();
+
+    let res = sqlx::query!(
         r#"
-UPDATE invoice
-SET deleted = ?
-WHERE nummer = ?
+INSERT OR IGNORE INTO bank_transaction ( tenant, date, amount, counterparty, iban, description, fingerprint )
+VALUES ( ?, ?, ?, ?, ?, ?, ? )
         "#,
-        deleted,
-        factuur_nummer
+        tenant,
+        transaction.date,
+        amount,
+        transaction.counterparty,
+        transaction.iban,
+        transaction.description,
+        fingerprint
     )
     .execute(&mut *conn)
     .await?;
 
+    Ok((res.rows_affected() > 0).then(|| res.last_insert_rowid()))
+}
+
+pub async fn get_bank_transaction(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+) -> Result<Transaction> {
+    let row = sqlx::query!(
+        r#"
+SELECT date, amount, counterparty, iban, description FROM bank_transaction
+WHERE tenant = ? AND id = ?
+        "#,
+        tenant,
+        id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Transaction {
+        date: row.date,
+        amount: Money::from_cents(row.amount),
+        counterparty: row.counterparty,
+        iban: row.iban,
+        description: row.description,
+    })
+}
+
+/// Transactions that still have to be reviewed, i.e. that are not linked
+/// to a payment or an expense and were not ignored.
+pub async fn get_open_bank_transactions(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<(i64, Transaction)>> {
+    let rows = sqlx::query!(
+        r#"
+SELECT id, date, amount, counterparty, iban, description FROM bank_transaction
+WHERE tenant = ? AND payment IS NULL AND expense IS NULL AND ignored = 0
+ORDER BY date DESC, id DESC
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.id,
+                Transaction {
+                    date: row.date,
+                    amount: Money::from_cents(row.amount),
+                    counterparty: row.counterparty,
+                    iban: row.iban,
+                    description: row.description,
+                },
+            )
+        })
+        .collect())
+}
+
+pub enum BankLink {
+    Payment(i64),
+    Expense(i64),
+    Ignored,
+}
+
+/// Marks a bank transaction as handled.
+pub async fn link_bank_transaction(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+    link: BankLink,
+) -> Result<()> {
+    let (payment, expense, ignored) = match link {
+        BankLink::Payment(payment) => (Some(payment), None, false),
+        BankLink::Expense(expense) => (None, Some(expense), false),
+        BankLink::Ignored => (None, None, true),
+    };
+
+    sqlx::query!(
+        r#"
+UPDATE bank_transaction
+SET payment = ?, expense = ?, ignored = ?
+WHERE tenant = ? AND id = ?
+        "#,
+        payment,
+        expense,
+        ignored,
+        tenant,
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The clients that can be picked for a new invoice.
+pub async fn get_all_clients(conn: &mut SqliteConnection, tenant: i64) -> Result<Vec<Client>> {
+    sqlx::query_as!(
+        Client,
+        "SELECT name, address, zip FROM client WHERE tenant = ? AND archived = 0 ORDER BY name",
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|err| anyhow!(err))
+}
+
+/// A row of the client table, before the amounts and terms are typed.
+struct ClientRow {
+    id: i64,
+    name: String,
+    address: String,
+    zip: String,
+    kvk: String,
+    btw_id: String,
+    email: String,
+    contact: String,
+    country: String,
+    payment_term: Option<i64>,
+    hourly_rate: Option<i64>,
+    archived: bool,
+}
+
+impl From<ClientRow> for ClientDetails {
+    fn from(row: ClientRow) -> Self {
+        ClientDetails {
+            id: row.id,
+            name: row.name,
+            address: row.address,
+            zip: row.zip,
+            kvk: row.kvk,
+            btw_id: row.btw_id,
+            email: row.email,
+            contact: row.contact,
+            country: row.country,
+            payment_term: row.payment_term.map(|t| t as u64),
+            hourly_rate: row.hourly_rate.map(Money::from_cents),
+            archived: row.archived,
+        }
+    }
+}
+
+/// All clients, including the archived ones.
+pub async fn get_client_details(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<ClientDetails>> {
+    let rows = sqlx::query_as!(
+        ClientRow,
+        r#"
+SELECT id, name, address, zip, kvk, btw_id, email, contact, country, payment_term, hourly_rate,
+    archived AS "archived: bool"
+FROM client
+WHERE tenant = ?
+ORDER BY name
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(ClientDetails::from).collect())
+}
+
+pub async fn get_client(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    client_name: &str,
+) -> Result<Option<ClientDetails>> {
+    let row = sqlx::query_as!(
+        ClientRow,
+        r#"
+SELECT id, name, address, zip, kvk, btw_id, email, contact, country, payment_term, hourly_rate,
+    archived AS "archived: bool"
+FROM client
+WHERE tenant = ? AND name = ?
+        "#,
+        tenant,
+        client_name
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(row.map(ClientDetails::from))
+}
+
+pub async fn get_client_by_id(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+) -> Result<ClientDetails> {
+    let row = sqlx::query_as!(
+        ClientRow,
+        r#"
+SELECT id, name, address, zip, kvk, btw_id, email, contact, country, payment_term, hourly_rate,
+    archived AS "archived: bool"
+FROM client
+WHERE tenant = ? AND id = ?
+        "#,
+        tenant,
+        id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Stores `client`, as a new client when its id is 0. Returns the id of the
+/// client.
+///
+/// Drafts and recurring invoices follow a client that is renamed, so that
+/// they do not bring back the old name once they are issued. Invoices that
+/// were issued already keep the address they were sent to.
+pub async fn save_client(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    client: &ClientDetails,
+) -> Result<i64> {
+    let mut tx = conn.begin().await?;
+    let id = upsert_client(&mut tx, tenant, client).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+async fn upsert_client(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    client: &ClientDetails,
+) -> Result<i64> {
+    let payment_term = client.payment_term.map(|t| t as i64);
+    let hourly_rate = client.hourly_rate.map(|r| r.cents());
+
+    if client.id == 0 {
+        let id = sqlx::query!(
+            r#"
+INSERT INTO client ( tenant, name, address, zip, kvk, btw_id, email, contact, country, payment_term, hourly_rate )
+VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ? )
+            "#,
+            tenant,
+            client.name,
+            client.address,
+            client.zip,
+            client.kvk,
+            client.btw_id,
+            client.email,
+            client.contact,
+            client.country,
+            payment_term,
+            hourly_rate
+        )
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+        return Ok(id);
+    }
+
+    let old = get_client_by_id(&mut *conn, tenant, client.id).await?;
+    sqlx::query!(
+        r#"
+UPDATE client
+SET name = ?, address = ?, zip = ?, kvk = ?, btw_id = ?, email = ?, contact = ?, country = ?,
+    payment_term = ?, hourly_rate = ?
+WHERE tenant = ? AND id = ?
+        "#,
+        client.name,
+        client.address,
+        client.zip,
+        client.kvk,
+        client.btw_id,
+        client.email,
+        client.contact,
+        client.country,
+        payment_term,
+        hourly_rate,
+        tenant,
+        client.id
+    )
+    .execute(&mut *conn)
+    .await?;
+    follow_client(&mut *conn, tenant, &old.name, &client.client()).await?;
+
+    Ok(client.id)
+}
+
+/// Points the drafts and recurring invoices for `old_name` at `client`.
+async fn follow_client(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    old_name: &str,
+    client: &Client,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+UPDATE draft
+SET client_name = ?, client_address = ?, client_zip = ?
+WHERE tenant = ? AND client_name = ?
+        "#,
+        client.name,
+        client.address,
+        client.zip,
+        tenant,
+        old_name
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+UPDATE recurring_invoice
+SET client_name = ?, client_address = ?, client_zip = ?
+WHERE tenant = ? AND client_name = ?
+        "#,
+        client.name,
+        client.address,
+        client.zip,
+        tenant,
+        old_name
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Stores the clients that an import adds or changes, all or nothing.
+/// Returns the number of clients that were stored.
+pub async fn import_clients(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    wijzigingen: &[Wijziging],
+) -> Result<usize> {
+    let mut tx = conn.begin().await?;
+
+    let mut stored = 0;
+    for wijziging in wijzigingen.iter().filter(|w| w.is_change()) {
+        upsert_client(&mut tx, tenant, wijziging.klant()).await?;
+        stored += 1;
+    }
+
+    tx.commit().await?;
+    Ok(stored)
+}
+
+pub async fn set_client_archived(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+    archived: bool,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE client SET archived = ? WHERE tenant = ? AND id = ?",
+        archived,
+        tenant,
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves everything of the duplicate client `remove` over to `keep`, and
+/// deletes the duplicate. The invoices of the duplicate keep the address
+/// they were sent to.
+pub async fn merge_clients(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    keep: i64,
+    remove: i64,
+) -> Result<()> {
+    if keep == remove {
+        return Err(anyhow!("cannot merge client {keep} with itself"));
+    }
+
+    let mut tx = conn.begin().await?;
+    move_client(&mut tx, tenant, keep, remove).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn move_client(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    keep: i64,
+    remove: i64,
+) -> Result<()> {
+    let kept = get_client_by_id(&mut *conn, tenant, keep).await?;
+    let removed = get_client_by_id(&mut *conn, tenant, remove).await?;
+
+    sqlx::query!(
+        "UPDATE invoice SET client = ? WHERE tenant = ? AND client = ?",
+        keep,
+        tenant,
+        remove
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE rate SET client = ? WHERE tenant = ? AND client = ?",
+        keep,
+        tenant,
+        remove
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE schedule_source SET client = ? WHERE tenant = ? AND client = ?",
+        keep,
+        tenant,
+        remove
+    )
+    .execute(&mut *conn)
+    .await?;
+    follow_client(&mut *conn, tenant, &removed.name, &kept.client()).await?;
+    sqlx::query!(
+        "DELETE FROM client WHERE tenant = ? AND id = ?",
+        tenant,
+        remove
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_pdf(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur_nummer: u32,
+) -> Result<(String, Vec<u8>)> {
+    let res = sqlx::query!(
+        r#"
+SELECT client_name, kenmerk, pdf FROM invoice
+WHERE tenant = ? AND nummer = ?
+        "#,
+        tenant,
+        factuur_nummer
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let pdf = sqlx::query!("SELECT file FROM pdf WHERE id = ?", res.pdf)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok((
+        format!("Factuur {} {}.pdf", res.client_name, res.kenmerk),
+        pdf.file,
+    ))
+}
+
+pub enum SoftDeleteAction {
+    Restore,
+    Delete,
+}
+
+pub async fn soft_delete_invoice(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur_nummer: u32,
+    action: SoftDeleteAction,
+) -> Result<()> {
+    let deleted = match action {
+        SoftDeleteAction::Restore => 0,
+        SoftDeleteAction::Delete => 1,
+    };
+
+    sqlx::query!(
+        r#"
+UPDATE invoice
+SET deleted = ?
+WHERE tenant = ? AND nummer = ?
+        "#,
+        deleted,
+        tenant,
+        factuur_nummer
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_voorbelasting(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    q: &Quarter,
+) -> Result<Money> {
+    let (year, quarter) = (q.year(), q.quarter());
+    let res = sqlx::query!(
+        r#"
+SELECT amount FROM voorbelasting
+WHERE tenant = ? AND year = ? AND quarter = ?
+        "#,
+        tenant,
+        year,
+        quarter
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(res.map(|r| Money::from_cents(r.amount)).unwrap_or_default())
+}
+
+pub async fn set_voorbelasting(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    q: &Quarter,
+    amount: Money,
+) -> Result<()> {
+    let (year, quarter) = (q.year(), q.quarter());
+    let amount = amount.cents();
+
+    sqlx::query!(
+        r#"
+INSERT INTO voorbelasting ( tenant, year, quarter, amount )
+VALUES ( ?, ?, ?, ? )
+ON CONFLICT ( tenant, year, quarter ) DO UPDATE SET amount = excluded.amount
+        "#,
+        tenant,
+        year,
+        quarter,
+        amount
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// All voorbelasting recorded by hand, per quarter.
+pub async fn get_all_voorbelasting(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<(Quarter, Money)>> {
+    let res = sqlx::query!(
+        "SELECT year, quarter, amount FROM voorbelasting WHERE tenant = ?",
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    res.into_iter()
+        .map(|r| {
+            let q = Quarter::new(r.year as i32, r.quarter as u32)?;
+            Ok((q, Money::from_cents(r.amount)))
+        })
+        .collect()
+}
+
+/// The most recent opening balance at or before the given year.
+pub async fn get_opening_balance(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    year: i32,
+) -> Result<Option<OpeningBalance>> {
+    let res = sqlx::query!(
+        r#"
+SELECT year, bank, debiteuren, btw FROM opening_balance
+WHERE tenant = ? AND year <= ?
+ORDER BY year DESC
+LIMIT 1
+        "#,
+        tenant,
+        year
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(res.map(|r| OpeningBalance {
+        year: r.year as i32,
+        bank: Money::from_cents(r.bank),
+        debiteuren: Money::from_cents(r.debiteuren),
+        btw: Money::from_cents(r.btw),
+    }))
+}
+
+pub async fn set_opening_balance(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    opening: &OpeningBalance,
+) -> Result<()> {
+    let (bank, debiteuren, btw) = (
+        opening.bank.cents(),
+        opening.debiteuren.cents(),
+        opening.btw.cents(),
+    );
+
+    sqlx::query!(
+        r#"
+INSERT INTO opening_balance ( tenant, year, bank, debiteuren, btw )
+VALUES ( ?, ?, ?, ?, ? )
+ON CONFLICT ( tenant, year ) DO UPDATE SET
+    bank = excluded.bank,
+    debiteuren = excluded.debiteuren,
+    btw = excluded.btw
+        "#,
+        tenant,
+        opening.year,
+        bank,
+        debiteuren,
+        btw
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_settings(conn: &mut SqliteConnection, tenant: i64) -> Result<Settings> {
+    let row = sqlx::query!(
+        r#"
+SELECT business_name, owner_name, email, btw_number, kvk_number, account_holder, iban, city,
+    payment_term, closing_note, greeting, serif_font, sans_font, font_size, geometry, nummering
+FROM settings
+WHERE tenant = ?
+        "#,
+        tenant
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Settings {
+        business_name: row.business_name,
+        owner_name: row.owner_name,
+        email: row.email,
+        btw_number: row.btw_number,
+        kvk_number: row.kvk_number,
+        account_holder: row.account_holder,
+        iban: row.iban,
+        city: row.city,
+        payment_term: row.payment_term as u64,
+        closing_note: row.closing_note,
+        greeting: row.greeting,
+        serif_font: row.serif_font,
+        sans_font: row.sans_font,
+        font_size: row.font_size,
+        geometry: row.geometry,
+        nummering: row.nummering,
+    })
+}
+
+pub async fn set_settings(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    settings: &Settings,
+) -> Result<()> {
+    let payment_term = settings.payment_term as i64;
+
+    let mut tx = conn.begin().await?;
+    sqlx::query!(
+        r#"
+UPDATE settings
+SET business_name = ?, owner_name = ?, email = ?, btw_number = ?, kvk_number = ?, account_holder = ?,
+    iban = ?, city = ?, payment_term = ?, closing_note = ?, greeting = ?, serif_font = ?, sans_font = ?,
+    font_size = ?, geometry = ?, nummering = ?
+WHERE tenant = ?
+        "#,
+        settings.business_name,
+        settings.owner_name,
+        settings.email,
+        settings.btw_number,
+        settings.kvk_number,
+        settings.account_holder,
+        settings.iban,
+        settings.city,
+        payment_term,
+        settings.closing_note,
+        settings.greeting,
+        settings.serif_font,
+        settings.sans_font,
+        settings.font_size,
+        settings.geometry,
+        settings.nummering,
+        tenant
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // The first tenant is named after its business once that is filled in
+    sqlx::query!(
+        "UPDATE tenant SET name = ? WHERE id = ? AND name = ''",
+        settings.business_name,
+        tenant
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// All rates of `tenant`, for all clients.
+pub async fn get_rates(conn: &mut SqliteConnection, tenant: i64) -> Result<Vec<Rate>> {
+    let rows = sqlx::query!(
+        r#"
+SELECT id, client, event_type, valid_from, valid_until, hourly_rate, evening_surcharge, weekend_surcharge
+FROM rate
+WHERE tenant = ?
+ORDER BY valid_from, id
+        "#,
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Rate {
+            id: row.id,
+            client: row.client,
+            event_type: row.event_type,
+            valid_from: row.valid_from,
+            valid_until: row.valid_until,
+            hourly_rate: Money::from_cents(row.hourly_rate),
+            evening_surcharge: row.evening_surcharge,
+            weekend_surcharge: row.weekend_surcharge,
+        })
+        .collect())
+}
+
+pub async fn add_rate(conn: &mut SqliteConnection, tenant: i64, rate: &Rate) -> Result<i64> {
+    let hourly_rate = rate.hourly_rate.cents();
+
+    let id = sqlx::query!(
+        r#"
+INSERT INTO rate ( tenant, client, event_type, valid_from, valid_until, hourly_rate,
+    evening_surcharge, weekend_surcharge )
+VALUES ( ?, ?, ?, ?, ?, ?, ?, ? )
+        "#,
+        tenant,
+        rate.client,
+        rate.event_type,
+        rate.valid_from,
+        rate.valid_until,
+        hourly_rate,
+        rate.evening_surcharge,
+        rate.weekend_surcharge
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(id)
+}
+
+pub async fn delete_rate(conn: &mut SqliteConnection, tenant: i64, rate_id: i64) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM rate WHERE tenant = ? AND id = ?",
+        tenant,
+        rate_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_schedule_sources(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Vec<ScheduleSource>> {
+    let rows = sqlx::query!(
+        "SELECT id, name, kind, subdomain, employee, client FROM schedule_source WHERE tenant = ? ORDER BY name",
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(ScheduleSource {
+                id: row.id,
+                name: row.name,
+                kind: row.kind.parse()?,
+                subdomain: row.subdomain,
+                employee: row.employee,
+                client: row.client,
+            })
+        })
+        .collect()
+}
+
+pub async fn get_schedule_source(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+) -> Result<ScheduleSource> {
+    get_schedule_sources(conn, tenant)
+        .await?
+        .into_iter()
+        .find(|source| source.id == id)
+        .ok_or_else(|| anyhow!("no schedule source with id {id}"))
+}
+
+pub async fn add_schedule_source(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    source: &ScheduleSource,
+) -> Result<i64> {
+    let kind = source.kind.key();
+
+    let id = sqlx::query!(
+        r#"
+INSERT INTO schedule_source ( tenant, name, kind, subdomain, employee, client )
+VALUES ( ?, ?, ?, ?, ?, ? )
+        "#,
+        tenant,
+        source.name,
+        kind,
+        source.subdomain,
+        source.employee,
+        source.client
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(id)
+}
+
+pub async fn delete_schedule_source(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    source_id: i64,
+) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM schedule_source WHERE tenant = ? AND id = ?",
+        tenant,
+        source_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_tenants(conn: &mut SqliteConnection) -> Result<Vec<Tenant>> {
+    sqlx::query_as!(Tenant, "SELECT id, name FROM tenant ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|err| anyhow!(err))
+}
+
+/// Adds a business to this deployment, whose invoices start out with the
+/// default layout. Returns the id of the new tenant.
+pub async fn add_tenant(conn: &mut SqliteConnection, name: &str) -> Result<i64> {
+    let mut tx = conn.begin().await?;
+    let id = insert_tenant(&mut tx, name).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+async fn insert_tenant(conn: &mut SqliteConnection, name: &str) -> Result<i64> {
+    let id = sqlx::query!("INSERT INTO tenant ( name ) VALUES ( ? )", name)
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+
+    sqlx::query!(
+        "INSERT INTO settings ( tenant, business_name ) VALUES ( ?, ? )",
+        id,
+        name
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+pub async fn get_user(conn: &mut SqliteConnection, id: i64) -> Result<Option<User>> {
+    let row = sqlx::query!(
+        "SELECT id, tenant, username, password_hash, role FROM user WHERE id = ?",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    row.map(|row| {
+        Ok(User {
+            id: row.id as usize,
+            tenant: row.tenant,
+            username: row.username,
+            password_hash: row.password_hash,
+            role: row.role.parse()?,
+        })
+    })
+    .transpose()
+}
+
+pub async fn get_user_by_name(conn: &mut SqliteConnection, username: &str) -> Result<Option<User>> {
+    let row = sqlx::query!(
+        "SELECT id, tenant, username, password_hash, role FROM user WHERE username = ?",
+        username
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    row.map(|row| {
+        Ok(User {
+            id: row.id as usize,
+            tenant: row.tenant,
+            username: row.username,
+            password_hash: row.password_hash,
+            role: row.role.parse()?,
+        })
+    })
+    .transpose()
+}
+
+/// The users who can log in to `tenant`, by name.
+pub async fn get_users(conn: &mut SqliteConnection, tenant: i64) -> Result<Vec<User>> {
+    let rows = sqlx::query!(
+        "SELECT id, tenant, username, password_hash, role FROM user WHERE tenant = ? ORDER BY username",
+        tenant
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(User {
+                id: row.id as usize,
+                tenant: row.tenant,
+                username: row.username,
+                password_hash: row.password_hash,
+                role: row.role.parse()?,
+            })
+        })
+        .collect()
+}
+
+/// Adds a user to `tenant`, with an Argon2 `password_hash`. Returns the id
+/// of the new user.
+pub async fn add_user(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    username: &str,
+    password_hash: &str,
+    role: Role,
+) -> Result<i64> {
+    let role = role.key();
+    let id = sqlx::query!(
+        "INSERT INTO user ( tenant, username, password_hash, role ) VALUES ( ?, ?, ?, ? )",
+        tenant,
+        username,
+        password_hash,
+        role
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(id)
+}
+
+pub async fn set_role(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    user_id: i64,
+    role: Role,
+) -> Result<()> {
+    let role = role.key();
+    let res = sqlx::query!(
+        "UPDATE user SET role = ? WHERE tenant = ? AND id = ?",
+        role,
+        tenant,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(anyhow!("no user with id {user_id}"));
+    }
+
+    Ok(())
+}
+
+/// Removes a user of `tenant`, who is logged out on their next request.
+pub async fn delete_user(conn: &mut SqliteConnection, tenant: i64, user_id: i64) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM user WHERE tenant = ? AND id = ?",
+        tenant,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_password_hash(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    password_hash: &str,
+) -> Result<()> {
+    let res = sqlx::query!(
+        "UPDATE user SET password_hash = ? WHERE id = ?",
+        password_hash,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(anyhow!("no user with id {user_id}"));
+    }
+
     Ok(())
 }
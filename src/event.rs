@@ -1,3 +1,7 @@
+use chrono::format::ParseError;
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+};
 use serde::Deserialize;
 
 #[derive(Clone, Debug, Deserialize)]
@@ -15,6 +19,74 @@ pub struct Event {
     pub end: String,
 }
 
+impl Event {
+    /// When the shift starts and ends. L1NDA gives the times without an
+    /// offset, in the time in Amsterdam, and a shift that ends at or before
+    /// its start ends the next day.
+    pub fn local_times(&self) -> Result<(NaiveDateTime, NaiveDateTime), ParseError> {
+        let start: NaiveDateTime = self.start.parse()?;
+        let mut end: NaiveDateTime = self.end.parse()?;
+        if end <= start {
+            end = end + Days::new(1);
+        }
+
+        Ok((start, end))
+    }
+
+    /// The minutes worked.
+    pub fn minutes(&self) -> Result<i64, ParseError> {
+        let (start, end) = self.local_times()?;
+        Ok(minutes_between(start, end))
+    }
+}
+
+/// The minutes that pass between two times in Amsterdam, which is an hour
+/// less or more than the clock says on the nights that summer time starts
+/// or ends.
+pub fn minutes_between(start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+    (amsterdam(end) - amsterdam(start)).num_minutes()
+}
+
+/// A time in Amsterdam, where summer time starts at 02:00 on the last Sunday
+/// of March and ends at 03:00 on the last Sunday of October. The hour that
+/// is skipped in March counts as winter time, so 02:30 is 03:30 summer
+/// time, and the hour that happens twice in October is the first one, in
+/// summer time.
+fn amsterdam(t: NaiveDateTime) -> DateTime<FixedOffset> {
+    let summer_start = last_sunday(t.year(), 3).and_time(NaiveTime::from_hms_opt(3, 0, 0).unwrap());
+    let summer_end = last_sunday(t.year(), 10).and_time(NaiveTime::from_hms_opt(3, 0, 0).unwrap());
+    let hours = if summer_start <= t && t < summer_end {
+        2
+    } else {
+        1
+    };
+
+    FixedOffset::east_opt(hours * 60 * 60)
+        .unwrap()
+        .from_local_datetime(&t)
+        .unwrap()
+}
+
+/// The time on the clock in Amsterdam at `t` in UTC, where summer time runs
+/// from 01:00 UTC on the last Sunday of March to 01:00 UTC on the last
+/// Sunday of October.
+pub fn from_utc(t: NaiveDateTime) -> NaiveDateTime {
+    let summer_start = last_sunday(t.year(), 3).and_time(NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+    let summer_end = last_sunday(t.year(), 10).and_time(NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+    let hours = if summer_start <= t && t < summer_end {
+        2
+    } else {
+        1
+    };
+
+    t + chrono::Duration::hours(hours)
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let last = NaiveDate::from_ymd_opt(year, month, 31).unwrap();
+    last - Days::new(last.weekday().num_days_from_sunday() as u64)
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Week {
     pub start_date: String,
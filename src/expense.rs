@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::btw::BtwCategorie;
+use crate::money::Money;
+
+/// A purchase made for the business, i.e. kosten.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Expense {
+    pub date: NaiveDate,
+    pub supplier: String,
+    pub category: String,
+    pub btw_categorie: BtwCategorie,
+    /// Amount excl. BTW
+    pub excl: Money,
+    /// BTW as charged by the supplier
+    pub btw: Money,
+}
+
+impl Expense {
+    pub fn incl(&self) -> Money {
+        self.excl + self.btw
+    }
+
+    /// BTW over a purchase where the heffing is verlegd to us. It has to be
+    /// paid in the aangifte and can be deducted as voorbelasting right away.
+    pub fn verlegde_btw(&self) -> Money {
+        match self.btw_categorie {
+            BtwCategorie::Verlegd => self.excl.percentage(BtwCategorie::Hoog.rate()),
+            _ => Money::ZERO,
+        }
+    }
+
+    /// BTW on this purchase that can be deducted as voorbelasting.
+    pub fn voorbelasting(&self) -> Money {
+        self.btw + self.verlegde_btw()
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExpenseForm {
+    pub date: String,
+    pub supplier: String,
+    pub category: String,
+    pub btw: String,
+    /// Amount incl. BTW as printed on the receipt
+    pub incl: String,
+    /// BTW as printed on the receipt, calculated from `incl` when empty
+    pub btw_bedrag: String,
+}
+
+impl TryFrom<ExpenseForm> for Expense {
+    type Error = anyhow::Error;
+
+    fn try_from(form: ExpenseForm) -> Result<Self> {
+        let date = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d")
+            .map_err(|err| anyhow!("invalid date {:?}: {err}", form.date))?;
+        let btw_categorie: BtwCategorie = form.btw.parse()?;
+        let incl: Money = form.incl.parse()?;
+
+        let btw = match form.btw_bedrag.trim() {
+            "" => {
+                // incl = excl * (1 + rate), so btw = incl * rate / (1 + rate)
+                let rate = btw_categorie.rate();
+                incl.mul_div(rate, 10_000 + rate)
+            }
+            bedrag => bedrag.parse()?,
+        };
+
+        if form.supplier.trim().is_empty() {
+            anyhow::bail!("supplier is required");
+        }
+
+        Ok(Expense {
+            date,
+            supplier: form.supplier.trim().to_owned(),
+            category: form.category.trim().to_owned(),
+            btw_categorie,
+            excl: incl - btw,
+            btw,
+        })
+    }
+}
+
+/// A scanned receipt or purchase invoice belonging to an expense.
+#[derive(Clone, Debug)]
+pub struct Receipt {
+    pub filename: String,
+    /// As sent by the browser that uploaded it
+    pub content_type: String,
+    pub file: Vec<u8>,
+}
+
+impl Receipt {
+    /// Pictures and PDFs are shown in the browser. Anything else, like an
+    /// HTML or SVG file that could run scripts, is only downloaded.
+    pub fn shown_inline(&self) -> bool {
+        let content_type = self.content_type.trim().to_lowercase();
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        essence == "application/pdf" || (essence.starts_with("image/") && !essence.contains("svg"))
+    }
+
+    /// The content type and disposition to serve the receipt with.
+    pub fn headers(&self) -> (String, String) {
+        // Quotes, backslashes and newlines would end the filename early
+        let filename: String = self
+            .filename
+            .chars()
+            .filter(|c| !matches!(c, '"' | '\\') && !c.is_control())
+            .collect();
+        match self.shown_inline() {
+            true => (
+                self.content_type.clone(),
+                format!("inline; filename=\"{filename}\""),
+            ),
+            false => (
+                "application/octet-stream".to_owned(),
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        }
+    }
+}
@@ -1,22 +1,23 @@
+use crate::btw::{self, BtwCategorie, BtwRegel};
 use crate::event;
+use crate::money::Money;
+use crate::payment::{Payment, PaymentStatus, PAYMENT_TERM_DAYS};
+use crate::rate::Rates;
+use crate::reminder::Reminder;
+use crate::render::PdfRenderer;
+use crate::settings::Settings;
 
 use askama::Template;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use tempfile::NamedTempFile;
 
 use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::io::{self, Write};
+use std::io;
 use std::iter::zip;
-use std::path::{Path, PathBuf};
-use std::process::Command;
-
-const BTW_RATE: f64 = 0.21;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct FactuurForm {
-    pub factuur_nummer: usize,
     pub client_name: String,
     pub client_address: String,
     pub client_zip: String,
@@ -24,42 +25,78 @@ pub struct FactuurForm {
     pub tasks: Vec<String>,
     #[serde(rename = "price")]
     pub prices: Vec<String>,
+    #[serde(rename = "btw")]
+    pub btw_categorieen: Vec<String>,
+    /// Set when this is a credit note for the invoice with this nummer
+    #[serde(default)]
+    pub credit_for: Option<usize>,
+    /// Set when the form was filled in from the draft with this id
+    #[serde(default)]
+    pub concept: Option<i64>,
 }
 
 #[derive(Template)]
 #[template(path = "invoice/details.yml")]
 pub struct FactuurTemplate<'a> {
     pub factuur: &'a Factuur,
+    pub settings: &'a Settings,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Factuur {
+    /// Internal sequence number, which also identifies the invoice in URLs
     pub nummer: usize,
+    /// The invoice number as printed, see `Nummering`
+    pub kenmerk: String,
     pub client: Client,
     pub work_items: Vec<WorkItem>,
-    pub subtotal: f64,
-    pub btw: f64,
-    pub total: f64,
+    pub subtotal: Money,
+    pub btw: Vec<BtwRegel>,
+    pub total: Money,
     pub date: DateTime<Utc>,
+    /// Days the client has to pay, as agreed when the invoice was issued
+    #[serde(default = "default_payment_term")]
+    pub payment_term: u64,
+    #[serde(default)]
+    pub payments: Vec<Payment>,
+    #[serde(default)]
+    pub reminders: Vec<Reminder>,
+    /// The invoice this credit note corrects
+    #[serde(default)]
+    pub credit_for: Option<FactuurRef>,
+    /// Date and amount of the credit notes that correct this invoice
+    #[serde(default)]
+    pub credits: Vec<(NaiveDate, Money)>,
 }
 
-impl From<FactuurForm> for Factuur {
-    fn from(value: FactuurForm) -> Self {
-        let work_items: Vec<WorkItem> = zip(value.tasks, value.prices)
-            .filter(|(desc, euro)| !desc.is_empty() && !euro.is_empty())
-            .filter_map(|(desc, euro)| match euro.parse::<f64>() {
-                Err(_) => None,
-                Ok(euro) => Some((desc, euro)),
-            })
-            .map(|(desc, euro)| WorkItem { desc, euro })
-            .collect();
+impl TryFrom<FactuurForm> for Factuur {
+    type Error = anyhow::Error;
+
+    /// Lines without a description or price are left out, while a line with
+    /// a price or BTW category that can't be read makes the whole form fail.
+    fn try_from(value: FactuurForm) -> anyhow::Result<Self> {
+        let mut btw_categorieen = value.btw_categorieen.into_iter();
+        let mut work_items = vec![];
+        for (desc, euro) in zip(value.tasks, value.prices) {
+            let btw = btw_categorieen.next();
+            if desc.is_empty() || euro.is_empty() {
+                continue;
+            }
+            let euro: Money = euro.parse()?;
+            let btw: BtwCategorie = btw
+                .ok_or_else(|| anyhow::anyhow!("no BTW category for {desc:?}"))?
+                .parse()?;
+            work_items.push(WorkItem { desc, euro, btw });
+        }
 
-        let subtotal = work_items.iter().map(|i| i.euro).sum::<f64>();
-        let btw = BTW_RATE * subtotal;
-        let total = subtotal + btw;
+        let subtotal = work_items.iter().map(|i| i.euro).sum::<Money>();
+        let btw = btw::specificatie(&work_items);
+        let total = subtotal + btw.iter().map(|r| r.btw).sum();
 
-        Factuur {
-            nummer: value.factuur_nummer,
+        // The number is assigned when the invoice is stored
+        Ok(Factuur {
+            nummer: 0,
+            kenmerk: String::new(),
             client: Client {
                 name: value.client_name,
                 address: value.client_address,
@@ -70,10 +107,51 @@ impl From<FactuurForm> for Factuur {
             btw,
             total,
             date: chrono::offset::Utc::now(),
-        }
+            payment_term: PAYMENT_TERM_DAYS,
+            payments: vec![],
+            reminders: vec![],
+            credit_for: None,
+            credits: vec![],
+        })
+    }
+}
+
+fn default_payment_term() -> u64 {
+    PAYMENT_TERM_DAYS
+}
+
+/// An invoice that is still being worked on. It gets a number and a PDF
+/// only once it is finalized.
+#[derive(Clone, Debug)]
+pub struct Concept {
+    pub id: i64,
+    pub client: Client,
+    pub work_items: Vec<WorkItem>,
+    /// The nummer of the invoice this credit note corrects
+    pub credit_for: Option<usize>,
+    /// The recurring invoice this draft was made for
+    pub recurring: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Concept {
+    pub fn total(&self) -> Money {
+        let subtotal: Money = self.work_items.iter().map(|i| i.euro).sum();
+        subtotal
+            + btw::specificatie(&self.work_items)
+                .iter()
+                .map(|r| r.btw)
+                .sum()
     }
 }
 
+/// Reference to another invoice by both its nummer and kenmerk.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FactuurRef {
+    pub nummer: usize,
+    pub kenmerk: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Client {
     pub name: String,
@@ -84,82 +162,127 @@ pub struct Client {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WorkItem {
     pub desc: String,
-    pub euro: f64,
+    pub euro: Money,
+    /// Invoices from before per-line BTW were all at the 21% rate.
+    #[serde(default)]
+    pub btw: BtwCategorie,
 }
 
-impl TryFrom<event::Event> for WorkItem {
-    type Error = FactuurError;
+/// Hourly rate for clients that do not have one of their own.
+pub const DEFAULT_HOURLY_RATE: Money = Money::from_cents(2200);
 
-    fn try_from(e: event::Event) -> Result<Self, FactuurError> {
+impl WorkItem {
+    /// The hours worked in `e`, priced by `rates`.
+    pub fn from_event(e: event::Event, rates: &Rates) -> Result<Self, FactuurError> {
         let desc = format!("{} {} ({})", e.event_type, e.date, e.start_to_end);
-        let start = DateTime::parse_from_rfc3339(&format!("{}+01:00", e.start)).map_err(|err| {
-            FactuurError {
-                kind: FactuurErrorKind::ParseDate(err),
-            }
+        let total = rates.price(&e).map_err(|err| FactuurError {
+            kind: FactuurErrorKind::ParseDate(err),
         })?;
-        let end = DateTime::parse_from_rfc3339(&format!("{}+01:00", e.end)).map_err(|err| {
-            FactuurError {
-                kind: FactuurErrorKind::ParseDate(err),
-            }
-        })?;
-        let hours = (end - start).num_minutes() as f64 / 60.0;
-        let tarief = 22.0;
-        let total = hours * tarief;
 
-        Ok(Self { desc, euro: total })
+        Ok(Self {
+            desc,
+            euro: total,
+            btw: BtwCategorie::Hoog,
+        })
     }
 }
 
-const TEX_TEMPLATE: &[u8] = include_bytes!("../templates/invoice/template.tex");
-
 impl Factuur {
-    pub fn generate_pdf(&self) -> Result<FactuurFile, FactuurError> {
+    pub fn btw_totaal(&self) -> Money {
+        self.btw.iter().map(|r| r.btw).sum()
+    }
+
+    pub fn due_date(&self) -> NaiveDate {
+        self.date.date_naive() + Days::new(self.payment_term)
+    }
+
+    /// The reference that `credit_note` gets when it corrects this invoice.
+    /// A credit note has to be negative, can not correct another credit
+    /// note, and all credit notes together can not credit more than this
+    /// invoice was for.
+    pub fn check_credit_note(&self, credit_note: &Factuur) -> anyhow::Result<FactuurRef> {
+        if self.credit_for.is_some() {
+            anyhow::bail!("factuur {} is zelf een creditfactuur", self.kenmerk);
+        }
+        if credit_note.total >= Money::ZERO {
+            anyhow::bail!("het totaal moet negatief zijn");
+        }
+        if self.credited(NaiveDate::MAX) - credit_note.total > self.total {
+            anyhow::bail!(
+                "er zou meer gecrediteerd worden dan de €{} van factuur {}",
+                self.total,
+                self.kenmerk
+            );
+        }
+        Ok(FactuurRef {
+            nummer: self.nummer,
+            kenmerk: self.kenmerk.clone(),
+        })
+    }
+
+    /// Amount credited up to and including `on`.
+    pub fn credited(&self, on: NaiveDate) -> Money {
+        self.credits
+            .iter()
+            .filter(|(date, _)| *date <= on)
+            .map(|(_, amount)| *amount)
+            .sum()
+    }
+
+    /// Amount still to be paid, counting payments up to and including `on`.
+    ///
+    /// A credit note is settled against the invoice it corrects, so it is
+    /// never outstanding itself. Instead it lowers what is outstanding on the
+    /// original invoice, below zero if that was paid already.
+    pub fn outstanding(&self, on: NaiveDate) -> Money {
+        if self.credit_for.is_some() {
+            return Money::ZERO;
+        }
+
+        self.total
+            - self.credited(on)
+            - self
+                .payments
+                .iter()
+                .filter(|p| p.date <= on)
+                .map(|p| p.amount)
+                .sum()
+    }
+
+    pub fn status(&self, today: NaiveDate) -> PaymentStatus {
+        let outstanding = self.outstanding(today);
+        if outstanding <= Money::ZERO {
+            PaymentStatus::Paid
+        } else if today > self.due_date() {
+            PaymentStatus::Overdue
+        } else if outstanding < self.total - self.credited(today) {
+            PaymentStatus::PartiallyPaid
+        } else {
+            PaymentStatus::Open
+        }
+    }
+
+    /// Heading of the invoice document.
+    pub fn title(&self) -> &'static str {
+        match self.credit_for {
+            Some(_) => "Creditfactuur",
+            None => "Factuur",
+        }
+    }
+
+    pub fn generate_pdf(
+        &self,
+        renderer: &dyn PdfRenderer,
+        settings: &Settings,
+    ) -> Result<Vec<u8>, FactuurError> {
         // Generate details from YAML template
-        let factuur_details = FactuurTemplate { factuur: self }.render().unwrap();
-        let mut details_file = NamedTempFile::new().map_err(|err| FactuurError {
-            kind: FactuurErrorKind::ReadFile(err),
-        })?;
-        details_file
-            .write_all(factuur_details.as_bytes())
-            .map_err(|err| FactuurError {
-                kind: FactuurErrorKind::ReadFile(err),
-            })?;
-
-        // Write the latex template to a temporary file so we can use it in our command
-        let mut tex_file = NamedTempFile::new().map_err(|err| FactuurError {
-            kind: FactuurErrorKind::ReadFile(err),
-        })?;
-        tex_file
-            .write_all(TEX_TEMPLATE)
-            .map_err(|err| FactuurError {
-                kind: FactuurErrorKind::ReadFile(err),
-            })?;
-
-        // Call pandoc with details.yml and template.tex and save to /tmp
-        let output_path = PathBuf::from(format!(
-            "/tmp/Factuur {} {}.pdf",
-            self.client.name, self.nummer
-        ));
-        let output = Command::new("pandoc")
-            .arg(details_file.path())
-            .arg("-o")
-            .arg(format!("{}", output_path.to_string_lossy()))
-            .arg(format!("--template={}", tex_file.path().to_string_lossy()))
-            .arg("--pdf-engine=xelatex")
-            .output()
-            .map_err(|err| FactuurError {
-                kind: FactuurErrorKind::PandocCommand(err),
-            })?;
-
-        match output.status.success() {
-            true => Ok(FactuurFile(output_path)),
-            false => Err(FactuurError {
-                kind: FactuurErrorKind::PandocCommand(io::Error::new(
-                    io::ErrorKind::Other,
-                    String::from_utf8_lossy(&output.stderr).to_string(),
-                )),
-            }),
+        let factuur_details = FactuurTemplate {
+            factuur: self,
+            settings,
         }
+        .render()
+        .unwrap();
+        renderer.render_pdf(&factuur_details)
     }
 }
 
@@ -180,6 +303,7 @@ impl Error for FactuurError {
             FactuurErrorKind::ParseDate(err) => Some(err),
             FactuurErrorKind::PandocCommand(err) => Some(err),
             FactuurErrorKind::ReadFile(err) => Some(err),
+            FactuurErrorKind::TypstCompile(_) => None,
         }
     }
 }
@@ -189,21 +313,5 @@ pub enum FactuurErrorKind {
     ParseDate(chrono::format::ParseError),
     PandocCommand(io::Error),
     ReadFile(io::Error),
-}
-
-/// Represents the invoice as a temporary PDF file on disk. Should be
-/// deleted from disk on Drop to prevent disk space from filling up.
-pub struct FactuurFile(PathBuf);
-
-impl AsRef<Path> for FactuurFile {
-    #[inline]
-    fn as_ref(&self) -> &Path {
-        &self.0
-    }
-}
-
-impl Drop for FactuurFile {
-    fn drop(&mut self) {
-        _ = std::fs::remove_file(&self.0);
-    }
+    TypstCompile(String),
 }
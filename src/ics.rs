@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{Datelike, Days, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::event::{self, Event};
+use crate::schedule::{Schedule, Shifts};
+
+/// Which events in a calendar are work. A filter that is `None` matches
+/// every event.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// The name of the calendar, as in `X-WR-CALNAME`
+    pub calendar: Option<String>,
+    pub category: Option<String>,
+    /// Matches the whole title, where `*` stands for any text, or else any
+    /// part of the title
+    pub title: Option<String>,
+}
+
+impl Filter {
+    fn matches(&self, event: &CalendarEvent) -> bool {
+        self.calendar
+            .as_ref()
+            .is_none_or(|c| c.trim().eq_ignore_ascii_case(&event.calendar))
+            && self.category.as_ref().is_none_or(|c| {
+                event
+                    .categories
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(c.trim()))
+            })
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|t| title_matches(t.trim(), &event.summary))
+    }
+}
+
+/// The work in an iCalendar file, as exported from a calendar app. Times
+/// are converted to the time in Amsterdam, and events that take whole days
+/// are left out, as they have no hours to bill. Events in a time zone that
+/// we can't convert are left out too, but mentioned.
+#[derive(Clone, Debug)]
+pub struct Ics {
+    contents: String,
+    filter: Filter,
+}
+
+#[async_trait]
+impl Schedule for Ics {
+    async fn shifts(&self, from: NaiveDate, until: NaiveDate) -> Result<Shifts> {
+        let events = parse(&self.contents)?;
+
+        // Occurrences of a repeating event that were moved or cancelled, by
+        // the start they had
+        let mut moved: HashMap<&str, Vec<NaiveDateTime>> = HashMap::new();
+        for e in &events {
+            if let Some(recurrence_id) = e.recurrence_id {
+                moved.entry(e.uid.as_str()).or_default().push(recurrence_id);
+            }
+        }
+
+        let mut shifts = vec![];
+        let mut skipped = vec![];
+        for e in events
+            .iter()
+            .filter(|e| !e.cancelled && self.filter.matches(e))
+        {
+            let (Some(start), Some(end)) = (e.start, e.end) else {
+                continue;
+            };
+            let length = end - start;
+            if length <= Duration::zero() {
+                continue;
+            }
+            if let Some(tzid) = &e.unknown_zone {
+                let in_period = match e.rule {
+                    None => from <= start.date() && start.date() <= until,
+                    Some(_) => start.date() <= until,
+                };
+                if in_period {
+                    let op = if e.rule.is_some() { "vanaf" } else { "op" };
+                    skipped.push(format!("{} {op} {} ({tzid})", e.summary, start.date()));
+                }
+                continue;
+            }
+
+            let starts = match &e.rule {
+                None => vec![start],
+                Some(rule) => match Rule::parse(rule) {
+                    Some(rule) => rule
+                        .starts(start, until)
+                        .into_iter()
+                        .filter(|t| !e.exdates.contains(t))
+                        .filter(|t| !moved.get(e.uid.as_str()).is_some_and(|m| m.contains(t)))
+                        .collect(),
+                    None if start.date() <= until => {
+                        skipped.push(format!("{} vanaf {}", e.summary, start.date()));
+                        continue;
+                    }
+                    None => continue,
+                },
+            };
+
+            shifts.extend(
+                starts
+                    .into_iter()
+                    .filter(|t| from <= t.date() && t.date() <= until)
+                    .map(|t| (t, t + length, e)),
+            );
+        }
+        shifts.sort_by_key(|(start, _, _)| *start);
+
+        Ok(Shifts {
+            events: shifts
+                .into_iter()
+                .map(|(start, end, e)| Event {
+                    person: e.calendar.clone(),
+                    event_type: e.summary.clone(),
+                    date: start.date().to_string(),
+                    start_to_end: format!("{} - {}", start.format("%H:%M"), end.format("%H:%M")),
+                    start: start.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                    end: end.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                })
+                .collect(),
+            missing_weeks: vec![],
+            skipped,
+        })
+    }
+}
+
+impl Ics {
+    pub fn new(contents: String, filter: Filter) -> Self {
+        Ics { contents, filter }
+    }
+}
+
+/// An event as in the calendar, with its times in Amsterdam.
+#[derive(Clone, Debug, Default)]
+struct CalendarEvent {
+    calendar: String,
+    uid: String,
+    summary: String,
+    categories: Vec<String>,
+    /// `None` for events that take whole days
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    cancelled: bool,
+    rule: Option<String>,
+    /// Starts of occurrences of a repeating event that were left out
+    exdates: Vec<NaiveDateTime>,
+    /// The start of the occurrence of a repeating event that this event
+    /// replaces
+    recurrence_id: Option<NaiveDateTime>,
+    /// The time zone of the start, when we can't convert it to Amsterdam
+    unknown_zone: Option<String>,
+}
+
+/// Reads the events in all calendars in `ics`.
+fn parse(ics: &str) -> Result<Vec<CalendarEvent>> {
+    let ics = ics.trim_start_matches('\u{feff}').trim_start();
+    if !ics
+        .get(..15)
+        .is_some_and(|s| s.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        bail!("not an iCalendar file");
+    }
+
+    // Long lines are folded onto lines that start with whitespace
+    let mut lines: Vec<String> = vec![];
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end().to_owned()),
+        }
+    }
+
+    let mut events = vec![];
+    let mut calendar = String::new();
+    let mut event: Option<CalendarEvent> = None;
+    // Components inside events, like alarms, whose properties are not the
+    // event's
+    let mut nested = 0;
+    let mut duration = None;
+    for line in lines {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(CalendarEvent {
+                    calendar: calendar.clone(),
+                    ..Default::default()
+                });
+                duration = None;
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) => {
+                let mut e = event.take().unwrap();
+                if let (Some(start), None, Some(duration)) = (e.start, e.end, duration) {
+                    e.end = Some(start + duration);
+                }
+                events.push(e);
+            }
+            ("END", None) if value.eq_ignore_ascii_case("VCALENDAR") => calendar.clear(),
+            ("X-WR-CALNAME", None) => calendar = unescape(value),
+            (_, Some(_)) if nested > 0 => (),
+            ("UID", Some(e)) => e.uid = value.to_owned(),
+            ("SUMMARY", Some(e)) => e.summary = unescape(value),
+            ("CATEGORIES", Some(e)) => e.categories.extend(
+                split_escaped(value, ',')
+                    .into_iter()
+                    .map(|c| c.trim().to_owned())
+                    .filter(|c| !c.is_empty()),
+            ),
+            ("STATUS", Some(e)) => e.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            ("DTSTART", Some(e)) => {
+                e.start = parse_time(&params, value)?;
+                e.unknown_zone = tzid(&params).filter(|tz| zone(tz).is_none());
+            }
+            ("DTEND", Some(e)) => e.end = parse_time(&params, value)?,
+            ("DURATION", Some(_)) => duration = parse_duration(value),
+            ("RRULE", Some(e)) => e.rule = Some(value.to_owned()),
+            ("EXDATE", Some(e)) => {
+                for value in value.split(',') {
+                    e.exdates.extend(parse_time(&params, value)?);
+                }
+            }
+            ("RECURRENCE-ID", Some(e)) => e.recurrence_id = parse_time(&params, value)?,
+            _ => (),
+        }
+    }
+
+    Ok(events)
+}
+
+/// The name in upper case, the parameters and the value of a content line
+/// like `DTSTART;TZID=Europe/Amsterdam:20261003T100000`. Parameters can be
+/// quoted, with colons in them.
+fn split_property(line: &str) -> Option<(String, String, &str)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let (name, params) = line[..colon]
+        .split_once(';')
+        .unwrap_or((&line[..colon], ""));
+    Some((name.to_uppercase(), params.to_owned(), &line[colon + 1..]))
+}
+
+/// A time in Amsterdam, from a time in UTC like `20261003T080000Z`, or one
+/// on the clock like `20261003T100000` in the time zone in `params`, if any.
+/// Dates without a time, of events that take whole days, are `None`. Times
+/// in a zone that `zone` doesn't know are left as they are.
+fn parse_time(params: &str, value: &str) -> Result<Option<NaiveDateTime>> {
+    let value = value.trim();
+    if params
+        .split(';')
+        .any(|p| p.eq_ignore_ascii_case("VALUE=DATE"))
+        || value.len() == 8
+    {
+        return Ok(None);
+    }
+
+    let invalid = || anyhow!("invalid time in calendar: {value}");
+    match value.strip_suffix(['Z', 'z']) {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|t| Some(event::from_utc(t)))
+            .map_err(|_| invalid()),
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map(|t| match tzid(params).and_then(|tz| zone(&tz)) {
+                Some(Zone::Utc) => Some(event::from_utc(t)),
+                Some(Zone::Behind(hours)) => Some(t + Duration::hours(hours)),
+                None => Some(t),
+            })
+            .map_err(|_| invalid()),
+    }
+}
+
+/// The `TZID` parameter, like `Europe/London`.
+fn tzid(params: &str) -> Option<String> {
+    params.split(';').find_map(|p| {
+        let (name, value) = p.split_once('=')?;
+        name.eq_ignore_ascii_case("TZID")
+            .then(|| value.trim_matches('"').to_owned())
+    })
+}
+
+/// How to get from the clock in a time zone to the clock in Amsterdam.
+enum Zone {
+    Utc,
+    /// The number of hours the zone is behind Amsterdam, all year round
+    Behind(i64),
+}
+
+/// The time zones that calendars in Europe use, by their IANA name or the
+/// name Outlook gives them. Apart from UTC, these change to summer time at
+/// the same moment as Amsterdam does.
+fn zone(tzid: &str) -> Option<Zone> {
+    match tzid.to_uppercase().as_str() {
+        "UTC" | "ETC/UTC" | "GMT" | "ETC/GMT" | "Z" => Some(Zone::Utc),
+        "EUROPE/LONDON" | "EUROPE/DUBLIN" | "EUROPE/LISBON" | "GMT STANDARD TIME" => {
+            Some(Zone::Behind(1))
+        }
+        "EUROPE/AMSTERDAM"
+        | "EUROPE/BERLIN"
+        | "EUROPE/BRUSSELS"
+        | "EUROPE/PARIS"
+        | "EUROPE/LUXEMBOURG"
+        | "EUROPE/MADRID"
+        | "EUROPE/ROME"
+        | "EUROPE/VIENNA"
+        | "EUROPE/ZURICH"
+        | "EUROPE/COPENHAGEN"
+        | "EUROPE/OSLO"
+        | "EUROPE/STOCKHOLM"
+        | "EUROPE/PRAGUE"
+        | "EUROPE/WARSAW"
+        | "CET"
+        | "W. EUROPE STANDARD TIME"
+        | "ROMANCE STANDARD TIME"
+        | "CENTRAL EUROPE STANDARD TIME" => Some(Zone::Behind(0)),
+        "EUROPE/HELSINKI" | "EUROPE/ATHENS" | "EUROPE/BUCHAREST" | "EUROPE/SOFIA"
+        | "EUROPE/RIGA" | "EUROPE/TALLINN" | "EUROPE/VILNIUS" | "FLE STANDARD TIME"
+        | "GTB STANDARD TIME" => Some(Zone::Behind(-1)),
+        _ => None,
+    }
+}
+
+/// A duration like `PT2H30M` or `P1DT4H`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in value.trim().strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => (),
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = std::mem::take(&mut number).parse().ok()?;
+                duration += match c {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    _ => Duration::seconds(n),
+                };
+            }
+            _ => return None,
+        }
+    }
+    Some(duration)
+}
+
+/// How an event repeats, for the rules that a calendar app makes for work
+/// every day or on some days of the week.
+#[derive(Clone, Debug)]
+struct Rule {
+    weekly: bool,
+    interval: u64,
+    count: Option<usize>,
+    /// The last start, in Amsterdam
+    until: Option<NaiveDateTime>,
+    by_day: Vec<Weekday>,
+}
+
+impl Rule {
+    /// `None` for rules that we can't follow, like monthly ones.
+    fn parse(rule: &str) -> Option<Rule> {
+        let mut parsed = Rule {
+            weekly: false,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+        };
+        for part in rule.split(';') {
+            let (key, value) = part.split_once('=')?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => match value.to_uppercase().as_str() {
+                    "DAILY" => parsed.weekly = false,
+                    "WEEKLY" => parsed.weekly = true,
+                    _ => return None,
+                },
+                "INTERVAL" => parsed.interval = value.parse().ok().filter(|i| *i > 0)?,
+                "COUNT" => parsed.count = Some(value.parse().ok()?),
+                "UNTIL" => {
+                    parsed.until = match parse_time("", value).ok()? {
+                        Some(until) => Some(until),
+                        // The whole last day
+                        None => NaiveDate::parse_from_str(value, "%Y%m%d")
+                            .ok()?
+                            .and_hms_opt(23, 59, 59),
+                    }
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        parsed.by_day.push(weekday(day)?);
+                    }
+                }
+                "WKST" => (),
+                _ => return None,
+            }
+        }
+        Some(parsed)
+    }
+
+    /// The starts of the occurrences from `first` on, up to and including
+    /// the day `last`. Occurrences keep their time on the clock, also after
+    /// summer time starts or ends.
+    fn starts(&self, first: NaiveDateTime, last: NaiveDate) -> Vec<NaiveDateTime> {
+        let mut days = if self.by_day.is_empty() || !self.weekly {
+            vec![first.weekday()]
+        } else {
+            self.by_day.clone()
+        };
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+
+        // Daily rules step by days, weekly ones by weeks from the Monday
+        let (step, base) = match self.weekly {
+            false => (self.interval, first.date()),
+            true => (
+                self.interval * 7,
+                first.date() - Days::new(first.weekday().num_days_from_monday() as u64),
+            ),
+        };
+
+        let mut starts = vec![];
+        for n in 0.. {
+            let period = base + Days::new(n * step);
+            for day in &days {
+                let date = match self.weekly {
+                    false => period,
+                    true => period + Days::new(day.num_days_from_monday() as u64),
+                };
+                let t = date.and_time(first.time());
+                if t < first {
+                    continue;
+                }
+                if date > last
+                    || self.until.is_some_and(|until| t > until)
+                    || self.count.is_some_and(|count| starts.len() >= count)
+                {
+                    return starts;
+                }
+                starts.push(t);
+            }
+        }
+        starts
+    }
+}
+
+fn weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Whether `title` matches `pattern`, without minding case.
+fn title_matches(pattern: &str, title: &str) -> bool {
+    let (pattern, title) = (pattern.to_lowercase(), title.to_lowercase());
+    if !pattern.contains('*') {
+        return title.contains(&pattern);
+    }
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().unwrap();
+    let Some(mut title) = title.strip_prefix(first) else {
+        return false;
+    };
+    let (last, middle) = rest.split_last().unwrap();
+    for part in middle {
+        match title.find(part) {
+            Some(i) => title = &title[i + part.len()..],
+            None => return false,
+        }
+    }
+    title.ends_with(last)
+}
+
+/// Splits a text value on the separators that are not escaped.
+fn split_escaped(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => parts.last_mut().unwrap().push('\n'),
+                Some(c) => parts.last_mut().unwrap().push(c),
+                None => (),
+            },
+            c if c == separator => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    split_escaped(value, ';').join(";").trim().to_owned()
+}
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{Datelike, Days, IsoWeek, NaiveDate};
+use futures::stream::{self, StreamExt};
+use reqwest::Url;
+
+use std::env;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::event::Week;
+use crate::schedule::{self, Schedule, Shifts};
+
+/// Weeks that are fetched from L1NDA at the same time.
+const PARALLEL_WEEKS: usize = 4;
+
+/// Tries per week, before its shifts are left out of the import.
+const ATTEMPTS: u32 = 3;
+
+/// The schedule of one employee in the L1NDA of an employer.
+#[derive(Clone, Debug)]
+pub struct L1nda {
+    /// Whose session key is used
+    tenant: i64,
+    /// Like `denieuweanita` for `denieuweanita.l1nda.nl`
+    subdomain: String,
+    employee: String,
+}
+
+#[async_trait]
+impl Schedule for L1nda {
+    async fn shifts(&self, from: NaiveDate, until: NaiveDate) -> Result<Shifts> {
+        let weeks = weeks_between(from, until);
+        let auth_cookie = self.auth_cookie()?;
+        let client = reqwest::Client::new();
+
+        let fetched = fetch_weeks(&weeks, |week| {
+            fetch_week(&client, &self.subdomain, &auth_cookie, week)
+        })
+        .await;
+        Ok(self.shifts_between(from, until, fetched))
+    }
+}
+
+impl L1nda {
+    pub fn new(tenant: i64, subdomain: &str, employee: &str) -> Result<Self> {
+        if !valid_subdomain(subdomain) {
+            bail!("invalid L1NDA subdomain {subdomain:?}");
+        }
+        Ok(L1nda {
+            tenant,
+            subdomain: subdomain.to_owned(),
+            employee: employee.to_owned(),
+        })
+    }
+
+    /// The session key of the tenant for the subdomain, like
+    /// `LINDA_AUTH_1_DENIEUWEANITA`. The first tenant can also use the one in
+    /// `LINDA_AUTH`, which is from before there were tenants.
+    fn auth_cookie(&self) -> Result<String> {
+        let key = format!(
+            "LINDA_AUTH_{}_{}",
+            self.tenant,
+            self.subdomain.to_uppercase().replace('-', "_")
+        );
+        env::var(&key)
+            .or_else(|err| match self.tenant {
+                1 => env::var("LINDA_AUTH"),
+                _ => Err(err),
+            })
+            .map_err(|_| anyhow!("no session key for L1NDA in {key}"))
+    }
+
+    /// The shifts of the employee on the days from `from` up to and
+    /// including `until`, from the weeks that cover them. `fetched` holds
+    /// the weeks that could not be fetched as errors.
+    pub fn shifts_between(
+        &self,
+        from: NaiveDate,
+        until: NaiveDate,
+        fetched: Vec<(IsoWeek, Result<Week>)>,
+    ) -> Shifts {
+        let in_period = |date: &str| {
+            date.parse::<NaiveDate>()
+                .is_ok_and(|date| from <= date && date <= until)
+        };
+        let mut events = vec![];
+        let mut missing_weeks = vec![];
+
+        for (week, res) in fetched {
+            match res {
+                Ok(v) => events.extend(
+                    v.schedule
+                        .into_iter()
+                        .flat_map(|r| r.days)
+                        .filter(|d| in_period(&d.date))
+                        .flat_map(|d| d.events)
+                        .filter(|e| e.person == self.employee),
+                ),
+                Err(err) => {
+                    println!(
+                        "Failed to fetch week {} of {} from L1NDA: {err:#}",
+                        week.week(),
+                        week.year()
+                    );
+                    missing_weeks.push(week);
+                }
+            }
+        }
+
+        Shifts {
+            events,
+            missing_weeks,
+            skipped: vec![],
+        }
+    }
+}
+
+/// Whether `subdomain` is only the first part of a host in `l1nda.nl`, like
+/// `denieuweanita`.
+pub fn valid_subdomain(subdomain: &str) -> bool {
+    !subdomain.is_empty()
+        && subdomain
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// The ISO weeks with days in the month, in order. The first and last week
+/// can belong to the ISO year before or after, like week 53 of 2026 that
+/// ends on the 3rd of January 2027.
+pub fn weeks_of_month(year: i32, month: u32) -> Result<Vec<IsoWeek>> {
+    let (first, last) = schedule::month(&format!("{year}-{month:02}"))?;
+    Ok(weeks_between(first, last))
+}
+
+/// The ISO weeks with days from `from` up to and including `until`, in order.
+pub fn weeks_between(from: NaiveDate, until: NaiveDate) -> Vec<IsoWeek> {
+    let mut weeks: Vec<IsoWeek> = vec![];
+    let mut day = from;
+    while day <= until {
+        if weeks.last() != Some(&day.iso_week()) {
+            weeks.push(day.iso_week());
+        }
+        day = day + Days::new(1);
+    }
+
+    weeks
+}
+
+/// Fetches a few weeks at the same time, and tries each of them again when
+/// it fails. The results are in the order of `weeks`.
+pub async fn fetch_weeks<F, Fut>(weeks: &[IsoWeek], fetch: F) -> Vec<(IsoWeek, Result<Week>)>
+where
+    F: Fn(IsoWeek) -> Fut,
+    Fut: Future<Output = Result<Week>>,
+{
+    stream::iter(weeks.iter().copied())
+        .map(|week| {
+            let fetch = &fetch;
+            async move {
+                let mut attempt = 1;
+                loop {
+                    match fetch(week).await {
+                        Ok(v) => return (week, Ok(v)),
+                        Err(err) if attempt >= ATTEMPTS => return (week, Err(err)),
+                        Err(err) => {
+                            println!(
+                                "Retrying week {} of {} after: {err:#}",
+                                week.week(),
+                                week.year()
+                            );
+                            tokio::time::sleep(Duration::from_millis(500) * attempt).await;
+                            attempt += 1;
+                        }
+                    }
+                }
+            }
+        })
+        .buffered(PARALLEL_WEEKS)
+        .collect()
+        .await
+}
+
+async fn fetch_week(
+    client: &reqwest::Client,
+    subdomain: &str,
+    auth_cookie: &str,
+    week: IsoWeek,
+) -> Result<Week> {
+    let url = format!(
+        "https://{}.l1nda.nl/week/{}/{}?xhr=true",
+        subdomain,
+        week.year(),
+        week.week()
+    )
+    .parse::<Url>()?;
+    // The session key must not be sent anywhere else
+    if !url
+        .host_str()
+        .is_some_and(|host| host.ends_with(".l1nda.nl"))
+    {
+        bail!("{url} is not in L1NDA");
+    }
+
+    let body = client
+        .get(url)
+        .header("Cookie", auth_cookie)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let v: Week = serde_json::from_str(&body)?;
+
+    println!("Got data from {} to {}", v.start_date, v.end_date);
+
+    Ok(v)
+}
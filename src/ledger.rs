@@ -0,0 +1,217 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use crate::aangifte::Quarter;
+use crate::expense::Expense;
+use crate::factuur::Factuur;
+use crate::money::Money;
+use crate::payment::Payment;
+
+/// The accounts of the grootboek. Everything that is not on the balance
+/// sheet ends up in either omzet or kosten.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rekening {
+    Bank,
+    Debiteuren,
+    /// BTW that still has to be paid to the Belastingdienst
+    Btw,
+    EigenVermogen,
+    Omzet,
+    Kosten,
+}
+
+/// A single line of a journal entry. Debit amounts are positive and credit
+/// amounts are negative, so the lines of every entry add up to zero.
+#[derive(Clone, Debug)]
+pub struct Mutatie {
+    pub datum: NaiveDate,
+    pub rekening: Rekening,
+    pub bedrag: Money,
+}
+
+/// Balances as they were on the first day of a year, i.e. the beginbalans.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OpeningBalance {
+    pub year: i32,
+    pub bank: Money,
+    pub debiteuren: Money,
+    /// BTW over the last quarter of the previous year, still to be paid
+    pub btw: Money,
+}
+
+impl OpeningBalance {
+    pub fn eigen_vermogen(&self) -> Money {
+        self.bank + self.debiteuren - self.btw
+    }
+
+    fn saldo(&self, rekening: Rekening) -> Money {
+        match rekening {
+            Rekening::Bank => self.bank,
+            Rekening::Debiteuren => self.debiteuren,
+            Rekening::Btw => -self.btw,
+            Rekening::EigenVermogen => -self.eigen_vermogen(),
+            Rekening::Omzet | Rekening::Kosten => Money::ZERO,
+        }
+    }
+}
+
+/// The mutaties of a single bookyear, derived from the administration and
+/// starting at the opening balance of that year.
+#[derive(Clone, Debug, Default)]
+pub struct Grootboek {
+    opening: OpeningBalance,
+    mutaties: Vec<Mutatie>,
+}
+
+impl Grootboek {
+    pub fn new(opening: OpeningBalance) -> Self {
+        Grootboek {
+            opening,
+            mutaties: vec![],
+        }
+    }
+
+    fn boek(&mut self, datum: NaiveDate, regels: &[(Rekening, Money)]) {
+        debug_assert!(regels.iter().map(|(_, b)| *b).sum::<Money>().is_zero());
+        self.mutaties
+            .extend(regels.iter().map(|(rekening, bedrag)| Mutatie {
+                datum,
+                rekening: *rekening,
+                bedrag: *bedrag,
+            }));
+    }
+
+    /// An issued invoice is owed to us until it is paid.
+    pub fn factuur(&mut self, factuur: &Factuur) {
+        let btw = factuur.btw_totaal();
+        self.boek(
+            factuur.date.date_naive(),
+            &[
+                (Rekening::Debiteuren, factuur.total),
+                (Rekening::Omzet, -(factuur.total - btw)),
+                (Rekening::Btw, -btw),
+            ],
+        );
+    }
+
+    /// A payment received from a client settles (part of) an invoice.
+    pub fn betaling(&mut self, payment: &Payment) {
+        self.boek(
+            payment.date,
+            &[
+                (Rekening::Bank, payment.amount),
+                (Rekening::Debiteuren, -payment.amount),
+            ],
+        );
+    }
+
+    /// Expenses are taken to be paid from the bank on the day of purchase.
+    pub fn kosten(&mut self, expense: &Expense) {
+        self.boek(
+            expense.date,
+            &[
+                (Rekening::Kosten, expense.excl),
+                (Rekening::Btw, expense.btw),
+                (Rekening::Bank, -expense.incl()),
+            ],
+        );
+    }
+
+    /// Voorbelasting recorded by hand for purchases that are not in the
+    /// kosten, booked on the last day of the quarter.
+    pub fn voorbelasting(&mut self, quarter: &Quarter, bedrag: Money) {
+        self.boek(
+            quarter.last_day(),
+            &[(Rekening::Btw, bedrag), (Rekening::Bank, -bedrag)],
+        );
+    }
+
+    /// Balance of a single account on `datum`, debit positive.
+    pub fn saldo(&self, rekening: Rekening, datum: NaiveDate) -> Money {
+        self.opening.saldo(rekening)
+            + self
+                .mutaties
+                .iter()
+                .filter(|m| m.rekening == rekening && m.datum <= datum)
+                .map(|m| m.bedrag)
+                .sum()
+    }
+
+    /// BTW to be paid per quarter from the mutaties up to `datum`. The BTW in
+    /// the opening balance belongs to the last quarter of the year before.
+    pub fn btw_per_kwartaal(&self, datum: NaiveDate) -> BTreeMap<Quarter, Money> {
+        let mut kwartalen: BTreeMap<Quarter, Money> = BTreeMap::new();
+        if !self.opening.btw.is_zero() {
+            let vorig_jaar = NaiveDate::from_ymd_opt(self.opening.year - 1, 12, 31);
+            if let Some(vorig_jaar) = vorig_jaar {
+                kwartalen.insert(Quarter::of(&vorig_jaar), self.opening.btw);
+            }
+        }
+        for m in self.mutaties.iter() {
+            if m.rekening == Rekening::Btw && m.datum <= datum {
+                *kwartalen.entry(Quarter::of(&m.datum)).or_default() -= m.bedrag;
+            }
+        }
+        kwartalen
+    }
+
+    /// The balance sheet on `datum`.
+    ///
+    /// We have no record of paying the Belastingdienst, so the BTW of every
+    /// quarter is taken to be paid from the bank on its deadline.
+    pub fn balans(&self, datum: NaiveDate) -> Balans {
+        let btw_per_kwartaal = self.btw_per_kwartaal(datum);
+        let afgedragen: Money = btw_per_kwartaal
+            .iter()
+            .filter(|(q, _)| q.deadline() <= datum)
+            .map(|(_, bedrag)| *bedrag)
+            .sum();
+
+        let opening_eigen_vermogen = -self.saldo(Rekening::EigenVermogen, datum);
+        let resultaat = -(self.saldo(Rekening::Omzet, datum) + self.saldo(Rekening::Kosten, datum));
+
+        Balans {
+            datum,
+            bank: self.saldo(Rekening::Bank, datum) - afgedragen,
+            debiteuren: self.saldo(Rekening::Debiteuren, datum),
+            btw: -self.saldo(Rekening::Btw, datum) - afgedragen,
+            opening_eigen_vermogen,
+            resultaat,
+            btw_per_kwartaal: btw_per_kwartaal
+                .into_iter()
+                .map(|(q, bedrag)| (q, bedrag, q.deadline() <= datum))
+                .collect(),
+        }
+    }
+}
+
+/// The balance sheet on a given day.
+#[derive(Clone, Debug)]
+pub struct Balans {
+    pub datum: NaiveDate,
+    pub bank: Money,
+    pub debiteuren: Money,
+    /// BTW still to be paid to the Belastingdienst
+    pub btw: Money,
+    pub opening_eigen_vermogen: Money,
+    /// Omzet minus kosten since the opening balance
+    pub resultaat: Money,
+    /// BTW per quarter and whether its deadline has passed
+    pub btw_per_kwartaal: Vec<(Quarter, Money, bool)>,
+}
+
+impl Balans {
+    pub fn activa(&self) -> Money {
+        self.bank + self.debiteuren
+    }
+
+    pub fn eigen_vermogen(&self) -> Money {
+        self.opening_eigen_vermogen + self.resultaat
+    }
+
+    pub fn passiva(&self) -> Money {
+        self.btw + self.eigen_vermogen()
+    }
+}
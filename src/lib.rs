@@ -1,17 +1,42 @@
-pub mod anita;
+pub mod aangifte;
+pub mod bank;
+pub mod btw;
 pub mod cli;
+pub mod client;
 pub mod db;
 pub mod event;
+pub mod expense;
 pub mod factuur;
+pub mod ics;
+pub mod l1nda;
+pub mod ledger;
+pub mod money;
+pub mod nummering;
+pub mod payment;
+pub mod rate;
+pub mod recurring;
+pub mod reminder;
+pub mod render;
 pub mod routes;
+pub mod schedule;
 pub mod server;
+pub mod settings;
+pub mod tenant;
+pub mod user;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Page {
     Dashboard,
     Facturen,
     Btw,
-    Anita,
+    Kosten,
+    Balans,
+    Bank,
+    Klanten,
+    Instellingen,
+    Account,
+    Agenda,
+    Rooster,
     Factuur,
     Auth,
 }
@@ -1,29 +1,118 @@
 use anyhow::Result;
-use chrono::DateTime;
+use chrono::{NaiveDate, Utc};
 use clap::Parser;
 
-use creatief_vakvrouw::anita;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
 use creatief_vakvrouw::cli;
+use creatief_vakvrouw::client;
+use creatief_vakvrouw::db;
 use creatief_vakvrouw::event;
+use creatief_vakvrouw::factuur::WorkItem;
+use creatief_vakvrouw::ics;
+use creatief_vakvrouw::nummering::Nummering;
+use creatief_vakvrouw::rate::Rates;
+use creatief_vakvrouw::render::{self, Formaat};
+use creatief_vakvrouw::schedule::{self, Schedule};
 use creatief_vakvrouw::server;
+use creatief_vakvrouw::settings::Settings;
+use creatief_vakvrouw::user;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let arg = cli::Cli::parse();
 
     match arg.command {
-        cli::Commands::Anita { month, name } => get_anita(month, name).await,
+        cli::Commands::Agenda {
+            tenant,
+            file,
+            from,
+            until,
+            client,
+            calendar,
+            category,
+            title,
+        } => {
+            let filter = ics::Filter {
+                calendar,
+                category,
+                title,
+            };
+            get_calendar_events(tenant, file, from, until, client, filter).await
+        }
+        cli::Commands::Clients { tenant, command } => match command {
+            cli::ClientsCommands::Import { file, apply } => {
+                import_clients(tenant, file, apply).await
+            }
+        },
+        cli::Commands::Factuur {
+            tenant,
+            nummer,
+            formaat,
+        } => render_invoice(tenant, nummer, formaat).await,
+        cli::Commands::Herhaal => draft_recurring_invoices().await,
+        cli::Commands::Rooster {
+            tenant,
+            month,
+            bron,
+        } => get_shifts(tenant, month, bron).await,
         cli::Commands::Server => server::run().await,
+        cli::Commands::Settings { tenant, command } => match command {
+            cli::SettingsCommands::Import { file } => import_settings(tenant, file).await,
+        },
+        cli::Commands::Tenants { command } => match command {
+            cli::TenantsCommands::Add { name } => add_tenant(name).await,
+            cli::TenantsCommands::List => list_tenants().await,
+        },
+        cli::Commands::Users { command } => match command {
+            cli::UsersCommands::Add {
+                username,
+                tenant,
+                role,
+            } => add_user(username, tenant, role).await,
+        },
     }
 }
 
-async fn get_anita(month: String, name: String) -> Result<()> {
-    let (year, month) = month.split_once('-').unwrap();
+async fn get_shifts(tenant: i64, month: String, bron: Option<String>) -> Result<()> {
+    let (from, until) = schedule::month(&month)?;
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let bronnen = db::get_schedule_sources(&mut conn, tenant).await?;
+    let bron = match bron {
+        Some(bron) => bronnen
+            .into_iter()
+            .find(|b| b.name == bron || b.id.to_string() == bron)
+            .ok_or_else(|| anyhow::anyhow!("No schedule {bron:?} in tenant {tenant}"))?,
+        None if bronnen.len() == 1 => bronnen.into_iter().next().unwrap(),
+        None => {
+            let namen: Vec<String> = bronnen.into_iter().map(|b| b.name).collect();
+            return Err(anyhow::anyhow!(
+                "Pick a schedule with --bron, one of: {}",
+                namen.join(", ")
+            ));
+        }
+    };
 
-    let rooster_noemi = anita::Anita::new(name);
-    let events = rooster_noemi
-        .get_events_from_month(month.to_owned(), year.to_owned())
-        .await?;
+    // Priced just like on the website
+    let client = match bron.client {
+        Some(id) => Some(db::get_client_by_id(&mut conn, tenant, id).await?),
+        None => None,
+    };
+    let rates = Rates::new(db::get_rates(&mut conn, tenant).await?, client.as_ref());
+
+    let shifts = bron.schedule(tenant)?.shifts(from, until).await?;
+    let events = shifts.events;
+
+    for week in &shifts.missing_weeks {
+        eprintln!(
+            "Week {} of {} is missing, add its shifts by hand",
+            week.week(),
+            week.year()
+        );
+    }
 
     for e in &events {
         println!("{} works at {} from {}", e.person, e.date, e.start_to_end);
@@ -34,26 +123,174 @@ async fn get_anita(month: String, name: String) -> Result<()> {
     }
 
     println!();
-    invoice(&events);
+    invoice(&bron.name, &events, &rates)
+}
+
+async fn get_calendar_events(
+    tenant: i64,
+    file: PathBuf,
+    from: NaiveDate,
+    until: NaiveDate,
+    client: Option<String>,
+    filter: ics::Filter,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(&file)?;
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let client = match client {
+        Some(name) => Some(
+            db::get_client(&mut conn, tenant, &name)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No client {name:?} in tenant {tenant}"))?,
+        ),
+        None => None,
+    };
+    let rates = Rates::new(db::get_rates(&mut conn, tenant).await?, client.as_ref());
+
+    let shifts = ics::Ics::new(contents, filter).shifts(from, until).await?;
+    for skipped in &shifts.skipped {
+        eprintln!("{skipped} repeats in a way that can't be read, add it by hand");
+    }
+    let events = shifts.events;
+    if events.is_empty() {
+        return Err(anyhow::anyhow!("No matching events"));
+    }
+
+    let name = client.map_or_else(|| file.display().to_string(), |c| c.name);
+    invoice(&name, &events, &rates)
+}
+
+async fn import_clients(tenant: i64, file: PathBuf, apply: bool) -> Result<()> {
+    let contents = std::fs::read_to_string(&file)?;
+    let import = client::parse(&contents)?;
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let bestaand = db::get_client_details(&mut conn, tenant).await?;
+    let wijzigingen = client::vergelijk(&bestaand, import);
+
+    for wijziging in &wijzigingen {
+        println!("{wijziging}");
+    }
+
+    let changes = wijzigingen.iter().filter(|w| w.is_change()).count();
+    if changes == 0 {
+        println!("Nothing to import");
+    } else if apply {
+        let stored = db::import_clients(&mut conn, tenant, &wijzigingen).await?;
+        println!("Imported {stored} client(s)");
+    } else {
+        println!("Would import {changes} client(s), run again with --apply to do so");
+    }
+
+    Ok(())
+}
+
+async fn render_invoice(tenant: i64, nummer: usize, formaat: String) -> Result<()> {
+    let formaat: Formaat = formaat.parse()?;
+    let renderer = formaat.renderer(&render::from_env()?);
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let factuur = db::get_invoices(&mut conn, tenant, db::InvoiceStatus::Active)
+        .await?
+        .into_iter()
+        .find(|i| i.nummer == nummer)
+        .ok_or_else(|| anyhow::anyhow!("No invoice with number {nummer}"))?;
+
+    let settings = db::get_settings(&mut conn, tenant).await?;
+    let document = renderer.render(&factuur, &settings)?;
+    std::io::stdout().write_all(&document)?;
+
+    Ok(())
+}
+
+async fn draft_recurring_invoices() -> Result<()> {
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let created = db::create_due_drafts(&mut conn, Utc::now().date_naive()).await?;
+    println!("Drafted {created} recurring invoice(s)");
+
+    Ok(())
+}
+
+async fn add_tenant(name: String) -> Result<()> {
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let id = db::add_tenant(&mut conn, name.trim()).await?;
+    println!("Added tenant {id}, add a user with `users add --tenant {id} <username>`");
+
+    Ok(())
+}
+
+async fn import_settings(tenant: i64, file: PathBuf) -> Result<()> {
+    let fields: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let mut settings = match serde_json::to_value(db::get_settings(&mut conn, tenant).await?)? {
+        serde_json::Value::Object(settings) => settings,
+        _ => unreachable!("settings serialize to an object"),
+    };
+    for (field, value) in fields {
+        if !settings.contains_key(&field) {
+            return Err(anyhow::anyhow!("Unknown setting {field:?}"));
+        }
+        println!("{field}: {value}");
+        settings.insert(field, value);
+    }
+    let settings: Settings = serde_json::from_value(serde_json::Value::Object(settings))?;
+    settings.nummering(&Nummering::from_env()?)?;
+
+    db::set_settings(&mut conn, tenant, &settings).await?;
+    println!("Imported the settings of tenant {tenant}");
 
     Ok(())
 }
 
-fn invoice(events: &[event::Event]) {
+async fn list_tenants() -> Result<()> {
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    for tenant in db::get_tenants(&mut conn).await? {
+        println!("{}\t{}", tenant.id, tenant.name);
+    }
+
+    Ok(())
+}
+
+async fn add_user(username: String, tenant: i64, role: String) -> Result<()> {
+    let role: user::Role = role.parse()?;
+    let mut password = String::new();
+    io::stdin().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err(anyhow::anyhow!("No password given on stdin"));
+    }
+
+    let db = server::connect().await?;
+    let mut conn = db.acquire().await?;
+    let password_hash = user::hash_password(password)?;
+    let id = db::add_user(&mut conn, tenant, username.trim(), &password_hash, role).await?;
+    println!("Added user {id} to tenant {tenant} as {}", role.key());
+
+    Ok(())
+}
+
+fn invoice(name: &str, events: &[event::Event], rates: &Rates) -> Result<()> {
     println!(
-        "Factuur De Nieuwe Anita ({} t/m {})",
+        "Factuur {} ({} t/m {})",
+        name,
         events[0].date,
         events.last().unwrap().date
     );
     println!("service:");
     for e in events {
-        let desc = format!("{} {} ({})", e.event_type, e.date, e.start_to_end);
-        let start = DateTime::parse_from_rfc3339(&format!("{}+01:00", e.start)).unwrap();
-        let end = DateTime::parse_from_rfc3339(&format!("{}+01:00", e.end)).unwrap();
-        let hours = (end - start).num_minutes() as f64 / 60.0;
-        let tarief = 18.0;
-        let total = hours * tarief;
-        println!("- description: {}", desc);
-        println!("  price: {}", total);
+        let item = WorkItem::from_event(e.clone(), rates)?;
+        println!("- description: {}", item.desc);
+        println!("  price: {}", item.euro);
     }
+
+    Ok(())
 }
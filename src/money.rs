@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// An amount of money in whole euro cents.
+///
+/// All bookkeeping is done in integer cents so that sums over many invoices
+/// never drift away from what is printed on the individual PDFs. Fractions
+/// of a cent only appear while multiplying (hours times a rate, BTW over a
+/// subtotal) and are rounded half away from zero right there.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    pub const fn cents(self) -> i64 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+
+    /// Multiplies by `numerator / denominator`, rounding half away from zero
+    /// to whole cents.
+    pub fn mul_div(self, numerator: i64, denominator: i64) -> Self {
+        let product = self.0 as i128 * numerator as i128;
+        let denominator = denominator as i128;
+        let half = denominator.abs() / 2;
+        let rounded = if (product < 0) != (denominator < 0) {
+            (product - half) / denominator
+        } else {
+            (product + half) / denominator
+        };
+        Money(rounded as i64)
+    }
+
+    /// Applies a percentage expressed in basis points (2100 is 21%).
+    pub fn percentage(self, basis_points: i64) -> Self {
+        self.mul_div(basis_points, 10_000)
+    }
+
+    /// Rounds down to whole euros, towards negative infinity.
+    pub fn floor_euros(self) -> Self {
+        Money(self.0.div_euclid(100) * 100)
+    }
+
+    /// Rounds up to whole euros, towards positive infinity.
+    pub fn ceil_euros(self) -> Self {
+        Money(-(-self.0).div_euclid(100) * 100)
+    }
+}
+
+impl Display for Money {
+    /// Formats as euros with two decimals and a dot separator, e.g. `-12.05`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+}
+
+/// Serializes as a euro string like `12.05` instead of in cents, for exports
+/// that are read by people rather than by this application.
+pub fn serialize_euros<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(money)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoneyError(String);
+
+impl Display for ParseMoneyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid amount: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// Parses euro amounts as typed into a form: `12`, `12.5`, `-12,50`.
+    /// Anything beyond the second decimal is rounded half away from zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseMoneyError(s.to_owned());
+        let trimmed = s.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = match unsigned.split_once(['.', ',']) {
+            Some((w, f)) => (w, f),
+            None => (unsigned, ""),
+        };
+
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(err());
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(err());
+        }
+
+        let whole: i64 = match whole {
+            "" => 0,
+            w => w.parse().map_err(|_| err())?,
+        };
+        let mut digits = fraction.bytes().map(|b| (b - b'0') as i64);
+        let mut cents = digits.next().unwrap_or(0) * 10 + digits.next().unwrap_or(0);
+        if digits.next().unwrap_or(0) >= 5 {
+            cents += 1;
+        }
+
+        let total = whole
+            .checked_mul(100)
+            .and_then(|w| w.checked_add(cents))
+            .ok_or_else(err)?;
+
+        Ok(Money(if negative { -total } else { total }))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::{bail, Result};
+
+use std::env;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// How invoice numbers are printed, e.g. `{jaar}-{nummer:4}` for
+/// `2026-0042`. The `{nummer}` is the volgnummer, which starts over every
+/// year when the pattern contains `{jaar}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nummering {
+    pattern: String,
+}
+
+impl Default for Nummering {
+    /// Plain numbers that keep counting up, as we always had.
+    fn default() -> Self {
+        Nummering {
+            pattern: "{nummer}".to_owned(),
+        }
+    }
+}
+
+impl Nummering {
+    /// Reads the pattern from `FACTUUR_NUMMERING`, if set.
+    pub fn from_env() -> Result<Self> {
+        match env::var("FACTUUR_NUMMERING") {
+            Ok(pattern) => pattern.parse(),
+            Err(_) => Ok(Nummering::default()),
+        }
+    }
+
+    pub fn resets_yearly(&self) -> bool {
+        self.pattern.contains("{jaar}")
+    }
+
+    /// The printed number of the `volgnummer`th invoice in `jaar`.
+    pub fn format(&self, jaar: i32, volgnummer: u32) -> String {
+        let mut res = String::new();
+        let mut rest = self.pattern.as_str();
+        while let Some(start) = rest.find('{') {
+            res.push_str(&rest[..start]);
+            // Placeholders were checked when parsing the pattern
+            let end = start + rest[start..].find('}').unwrap();
+            match &rest[start + 1..end] {
+                "jaar" => res.push_str(&jaar.to_string()),
+                "nummer" => res.push_str(&volgnummer.to_string()),
+                placeholder => {
+                    let width: usize = placeholder["nummer:".len()..].parse().unwrap();
+                    res.push_str(&format!("{volgnummer:0width$}"));
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        res.push_str(rest);
+        res
+    }
+}
+
+impl FromStr for Nummering {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut has_nummer = false;
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                bail!("unclosed placeholder in invoice number pattern {s:?}");
+            };
+            match &rest[start + 1..end] {
+                "jaar" => (),
+                "nummer" => has_nummer = true,
+                p if p
+                    .strip_prefix("nummer:")
+                    .is_some_and(|w| w.parse::<usize>().is_ok()) =>
+                {
+                    has_nummer = true
+                }
+                p => bail!("unknown placeholder {{{p}}} in invoice number pattern {s:?}"),
+            }
+            rest = &rest[end + 1..];
+        }
+
+        if !has_nummer {
+            bail!("invoice number pattern {s:?} needs a {{nummer}}");
+        }
+
+        Ok(Nummering {
+            pattern: s.to_owned(),
+        })
+    }
+}
+
+/// Ranges of volgnummers missing from a period, i.e. between the lowest and
+/// the highest number that was issued in it.
+pub fn gaps(mut volgnummers: Vec<u32>) -> Vec<RangeInclusive<u32>> {
+    volgnummers.sort();
+    volgnummers.dedup();
+    volgnummers
+        .windows(2)
+        .filter(|w| w[1] > w[0] + 1)
+        .map(|w| w[0] + 1..=w[1] - 1)
+        .collect()
+}
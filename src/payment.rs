@@ -0,0 +1,116 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::money::Money;
+
+/// Payment term of invoices that do not say otherwise. New invoices take
+/// theirs from the client or from the settings.
+pub const PAYMENT_TERM_DAYS: u64 = 14;
+
+/// A (partial) payment received for an invoice.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Payment {
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub method: PaymentMethod,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentMethod {
+    #[default]
+    Overboeking,
+    Contant,
+    Pin,
+    Anders,
+}
+
+impl PaymentMethod {
+    pub const ALL: [PaymentMethod; 4] = [
+        PaymentMethod::Overboeking,
+        PaymentMethod::Contant,
+        PaymentMethod::Pin,
+        PaymentMethod::Anders,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            PaymentMethod::Overboeking => "overboeking",
+            PaymentMethod::Contant => "contant",
+            PaymentMethod::Pin => "pin",
+            PaymentMethod::Anders => "anders",
+        }
+    }
+}
+
+impl Display for PaymentMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+impl FromStr for PaymentMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaymentMethod::ALL
+            .into_iter()
+            .find(|m| m.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown payment method: {s}"))
+    }
+}
+
+/// Whether an invoice has been paid, derived from its payments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaymentStatus {
+    Open,
+    PartiallyPaid,
+    Paid,
+    /// Not fully paid after the payment term
+    Overdue,
+}
+
+impl PaymentStatus {
+    pub const ALL: [PaymentStatus; 4] = [
+        PaymentStatus::Open,
+        PaymentStatus::PartiallyPaid,
+        PaymentStatus::Paid,
+        PaymentStatus::Overdue,
+    ];
+
+    /// Key used in the `/facturen?status=` filter.
+    pub fn key(&self) -> &'static str {
+        match self {
+            PaymentStatus::Open => "open",
+            PaymentStatus::PartiallyPaid => "deels",
+            PaymentStatus::Paid => "betaald",
+            PaymentStatus::Overdue => "verlopen",
+        }
+    }
+}
+
+impl Display for PaymentStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            PaymentStatus::Open => "open",
+            PaymentStatus::PartiallyPaid => "deels betaald",
+            PaymentStatus::Paid => "betaald",
+            PaymentStatus::Overdue => "verlopen",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for PaymentStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaymentStatus::ALL
+            .into_iter()
+            .find(|m| m.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown payment status: {s}"))
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::format::ParseError;
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+use crate::client::ClientDetails;
+use crate::event::{self, Event};
+use crate::factuur::DEFAULT_HOURLY_RATE;
+use crate::money::Money;
+
+/// Hours from 20:00 up to 06:00 are evening hours.
+pub const EVENING_START: u32 = 20;
+pub const EVENING_END: u32 = 6;
+
+/// An hourly rate from a date on, as set on the rates page.
+#[derive(Clone, Debug)]
+pub struct Rate {
+    pub id: i64,
+    /// For all clients when `None`
+    pub client: Option<i64>,
+    /// Like `Bar`, as in the schedule, or `None` for all work
+    pub event_type: Option<String>,
+    pub valid_from: NaiveDate,
+    /// The last day the rate applies, or `None` until a newer rate starts
+    pub valid_until: Option<NaiveDate>,
+    pub hourly_rate: Money,
+    /// In percent on top of the hourly rate, for evening hours
+    pub evening_surcharge: i64,
+    /// In percent on top of the hourly rate, for hours on Saturday and Sunday
+    pub weekend_surcharge: i64,
+}
+
+impl Rate {
+    fn applies(&self, client: Option<i64>, event_type: &str, date: NaiveDate) -> bool {
+        self.client.is_none_or(|c| Some(c) == client)
+            && self.event_type.as_deref().is_none_or(|t| t == event_type)
+            && self.valid_from <= date
+            && self.valid_until.is_none_or(|until| date <= until)
+    }
+
+    /// Rates for both a client and a kind of work go before rates for a
+    /// client, which go before rates for a kind of work.
+    fn specificity(&self) -> u8 {
+        2 * self.client.is_some() as u8 + self.event_type.is_some() as u8
+    }
+
+    /// The surcharge in percent for work at `t`. Evening hours in the weekend
+    /// get the highest of both surcharges, not the sum.
+    fn surcharge(&self, t: NaiveDateTime) -> i64 {
+        let evening = t.hour() >= EVENING_START || t.hour() < EVENING_END;
+        let weekend = matches!(t.weekday(), Weekday::Sat | Weekday::Sun);
+
+        let mut surcharge = 0;
+        if evening {
+            surcharge = surcharge.max(self.evening_surcharge);
+        }
+        if weekend {
+            surcharge = surcharge.max(self.weekend_surcharge);
+        }
+        surcharge
+    }
+}
+
+/// Prices the work for one client, for the web and the command line alike.
+#[derive(Clone, Debug)]
+pub struct Rates {
+    client: Option<i64>,
+    rates: Vec<Rate>,
+    /// The hourly rate of the client, for work that no rate applies to
+    fallback: Money,
+}
+
+impl Rates {
+    pub fn new(rates: Vec<Rate>, client: Option<&ClientDetails>) -> Self {
+        Rates {
+            client: client.map(|c| c.id),
+            rates,
+            fallback: client.map_or(DEFAULT_HOURLY_RATE, ClientDetails::hourly_rate),
+        }
+    }
+
+    /// The rate for work of `event_type` on `date`. The most specific rate
+    /// wins, and of those the one that started last, so that a raise only
+    /// needs a new rate from the day it applies.
+    pub fn find(&self, event_type: &str, date: NaiveDate) -> Option<&Rate> {
+        self.rates
+            .iter()
+            .filter(|r| r.applies(self.client, event_type, date))
+            .max_by_key(|r| (r.specificity(), r.valid_from))
+    }
+
+    /// The price of a shift, by the rate on the day that it starts.
+    pub fn price(&self, event: &Event) -> Result<Money, ParseError> {
+        let (start, end) = event.local_times()?;
+        let Some(rate) = self.find(&event.event_type, start.date()) else {
+            return Ok(self
+                .fallback
+                .mul_div(event::minutes_between(start, end), 60));
+        };
+
+        // Minutes times the percentage they are billed at, summed up before
+        // rounding to cents
+        let mut weighted = 0;
+        let mut from = start;
+        while from < end {
+            let until = next_boundary(from).min(end);
+            weighted += event::minutes_between(from, until) * (100 + rate.surcharge(from));
+            from = until;
+        }
+
+        Ok(rate.hourly_rate.mul_div(weighted, 60 * 100))
+    }
+}
+
+/// The first time after `t` where a surcharge can start or end.
+fn next_boundary(t: NaiveDateTime) -> NaiveDateTime {
+    let date = t.date();
+    [
+        date.and_hms_opt(EVENING_END, 0, 0).unwrap(),
+        date.and_hms_opt(EVENING_START, 0, 0).unwrap(),
+        (date + Days::new(1)).and_hms_opt(0, 0, 0).unwrap(),
+    ]
+    .into_iter()
+    .find(|boundary| *boundary > t)
+    .unwrap()
+}
@@ -0,0 +1,127 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::factuur::{Client, WorkItem};
+use crate::money::Money;
+use crate::render::MAANDEN;
+
+/// How often a recurring invoice is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Wekelijks,
+    #[default]
+    Maandelijks,
+    Kwartaal,
+    Jaarlijks,
+}
+
+impl Frequency {
+    pub const ALL: [Frequency; 4] = [
+        Frequency::Wekelijks,
+        Frequency::Maandelijks,
+        Frequency::Kwartaal,
+        Frequency::Jaarlijks,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Frequency::Wekelijks => "wekelijks",
+            Frequency::Maandelijks => "maandelijks",
+            Frequency::Kwartaal => "kwartaal",
+            Frequency::Jaarlijks => "jaarlijks",
+        }
+    }
+
+    /// The run after the one on `date`. A monthly run on the 31st moves to
+    /// the last day of a shorter month, and stays on that day from then on.
+    pub fn next(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Frequency::Wekelijks => date + Days::new(7),
+            Frequency::Maandelijks => date + Months::new(1),
+            Frequency::Kwartaal => date + Months::new(3),
+            Frequency::Jaarlijks => date + Months::new(12),
+        }
+    }
+}
+
+impl Display for Frequency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Frequency::Wekelijks => "elke week",
+            Frequency::Maandelijks => "elke maand",
+            Frequency::Kwartaal => "elk kwartaal",
+            Frequency::Jaarlijks => "elk jaar",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Frequency::ALL
+            .into_iter()
+            .find(|f| f.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown frequency: {s}"))
+    }
+}
+
+/// An invoice that is drafted again every period.
+#[derive(Clone, Debug)]
+pub struct RecurringInvoice {
+    pub id: i64,
+    pub client: Client,
+    /// Descriptions may contain placeholders, see `fill_placeholders`
+    pub work_items: Vec<WorkItem>,
+    pub frequency: Frequency,
+    /// The day the next draft is made
+    pub next_run: NaiveDate,
+}
+
+impl RecurringInvoice {
+    /// The runs that are due on `today` and have not been made yet, oldest
+    /// first. Missed periods are all caught up on.
+    pub fn due_runs(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let mut runs = vec![];
+        let mut run = self.next_run;
+        while run <= today {
+            runs.push(run);
+            run = self.frequency.next(run);
+        }
+        runs
+    }
+
+    /// The work items of the run on `date`, with their placeholders filled in.
+    pub fn work_items_for(&self, date: NaiveDate) -> Vec<WorkItem> {
+        self.work_items
+            .iter()
+            .map(|i| WorkItem {
+                desc: fill_placeholders(&i.desc, date),
+                ..i.clone()
+            })
+            .collect()
+    }
+
+    /// Total excl. BTW of a single run.
+    pub fn subtotal(&self) -> Money {
+        self.work_items.iter().map(|i| i.euro).sum()
+    }
+}
+
+/// Fills in `{month}` and `{year}` with the month and year of `date`, e.g.
+/// "Lessen {month} {year}" becomes "Lessen oktober 2026". The Dutch `{maand}`
+/// and `{jaar}` work too.
+pub fn fill_placeholders(template: &str, date: NaiveDate) -> String {
+    let maand = MAANDEN[date.month0() as usize];
+    let jaar = date.year().to_string();
+    template
+        .replace("{month}", maand)
+        .replace("{maand}", maand)
+        .replace("{year}", &jaar)
+        .replace("{jaar}", &jaar)
+}
@@ -0,0 +1,145 @@
+use askama::Template;
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::factuur::{Factuur, FactuurError};
+use crate::money::Money;
+use crate::payment::PaymentStatus;
+use crate::render::PdfRenderer;
+use crate::settings::Settings;
+
+/// Number of days a client gets to pay after each reminder, before the
+/// next one is due.
+pub const REMINDER_TERM_DAYS: u64 = 7;
+
+/// The steps of the dunning workflow, in the order they are sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderKind {
+    Eerste,
+    Tweede,
+    /// The final notice before the claim is handed over
+    Aanmaning,
+}
+
+impl ReminderKind {
+    pub const ALL: [ReminderKind; 3] = [
+        ReminderKind::Eerste,
+        ReminderKind::Tweede,
+        ReminderKind::Aanmaning,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ReminderKind::Eerste => "eerste",
+            ReminderKind::Tweede => "tweede",
+            ReminderKind::Aanmaning => "aanmaning",
+        }
+    }
+
+    /// Heading of the document, in place of "Factuur".
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReminderKind::Eerste => "Herinnering",
+            ReminderKind::Tweede => "Tweede herinnering",
+            ReminderKind::Aanmaning => "Aanmaning",
+        }
+    }
+
+    fn next(&self) -> Option<ReminderKind> {
+        match self {
+            ReminderKind::Eerste => Some(ReminderKind::Tweede),
+            ReminderKind::Tweede => Some(ReminderKind::Aanmaning),
+            ReminderKind::Aanmaning => None,
+        }
+    }
+}
+
+impl Display for ReminderKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ReminderKind::Eerste => "1e herinnering",
+            ReminderKind::Tweede => "2e herinnering",
+            ReminderKind::Aanmaning => "aanmaning",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for ReminderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ReminderKind::ALL
+            .into_iter()
+            .find(|k| k.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown reminder kind: {s}"))
+    }
+}
+
+/// A reminder that was sent for an invoice.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Reminder {
+    pub kind: ReminderKind,
+    pub date: NaiveDate,
+}
+
+impl Reminder {
+    /// The day the client has to have paid after this reminder.
+    pub fn deadline(&self) -> NaiveDate {
+        self.date + Days::new(REMINDER_TERM_DAYS)
+    }
+}
+
+impl Factuur {
+    /// The reminder that should be sent on `today`, if any. The first one is
+    /// due once the invoice is overdue, every next one once the term of the
+    /// previous reminder has passed.
+    pub fn reminder_due(&self, today: NaiveDate) -> Option<ReminderKind> {
+        if self.status(today) != PaymentStatus::Overdue {
+            return None;
+        }
+
+        match self.reminders.iter().max_by_key(|r| r.kind) {
+            None => Some(ReminderKind::Eerste),
+            Some(last) if today > last.deadline() => last.kind.next(),
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "invoice/reminder.yml")]
+pub struct ReminderTemplate<'a> {
+    pub factuur: &'a Factuur,
+    pub reminder: &'a Reminder,
+    pub outstanding: Money,
+    pub settings: &'a Settings,
+}
+
+/// Renders the reminder with the same template as the invoice itself, so it
+/// shows the original invoice with a different heading.
+pub fn generate_pdf(
+    factuur: &Factuur,
+    reminder: &Reminder,
+    renderer: &dyn PdfRenderer,
+    settings: &Settings,
+) -> Result<Vec<u8>, FactuurError> {
+    let details = ReminderTemplate {
+        factuur,
+        reminder,
+        outstanding: factuur.outstanding(reminder.date),
+        settings,
+    }
+    .render()
+    .unwrap();
+
+    renderer.render_pdf(&details)
+}
+
+pub fn filename(kind: ReminderKind, client_name: &str, kenmerk: &str) -> String {
+    format!("{} {} {}.pdf", kind.title(), client_name, kenmerk)
+}
@@ -0,0 +1,380 @@
+use askama::Template;
+use chrono::{Datelike, NaiveDate, Utc};
+use comemo::Prehashed;
+use tempfile::NamedTempFile;
+use typst::diag::{FileError, FileResult};
+use typst::eval::Tracer;
+use typst::foundations::{Bytes, Datetime, Smart};
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::{Library, World};
+
+use std::env;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use crate::factuur::{Factuur, FactuurError, FactuurErrorKind};
+use crate::settings::Settings;
+
+const TEX_TEMPLATE: &[u8] = include_bytes!("../templates/invoice/template.tex");
+const TYPST_TEMPLATE: &str = include_str!("../templates/invoice/template.typ");
+
+/// Directories searched for fonts like Liberation, besides the fonts that
+/// are bundled with Typst.
+const FONT_DIRS: [&str; 2] = ["/usr/share/fonts", "/usr/local/share/fonts"];
+
+/// Month names, as written in dates on invoices.
+pub const MAANDEN: [&str; 12] = [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+
+/// Turns an invoice into a document to send to the client.
+pub trait InvoiceRenderer: Debug + Send + Sync {
+    fn content_type(&self) -> &'static str;
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError>;
+}
+
+/// Turns the details YAML of an invoice or reminder into a PDF.
+pub trait PdfRenderer: InvoiceRenderer {
+    fn render_pdf(&self, details: &str) -> Result<Vec<u8>, FactuurError>;
+}
+
+/// The formats an invoice can be rendered in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Formaat {
+    Pdf,
+    /// A standalone page, to preview or print from the browser
+    Html,
+    /// Plain text, for in the body of an email
+    Tekst,
+}
+
+impl Formaat {
+    pub const ALL: [Formaat; 3] = [Formaat::Pdf, Formaat::Html, Formaat::Tekst];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Formaat::Pdf => "pdf",
+            Formaat::Html => "html",
+            Formaat::Tekst => "tekst",
+        }
+    }
+
+    /// The renderer for this format, where PDFs are made by `pdf`.
+    pub fn renderer(&self, pdf: &Arc<dyn PdfRenderer>) -> Arc<dyn InvoiceRenderer> {
+        match self {
+            Formaat::Pdf => pdf.clone(),
+            Formaat::Html => Arc::new(Html),
+            Formaat::Tekst => Arc::new(Tekst),
+        }
+    }
+}
+
+impl Display for Formaat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Formaat::Pdf => "PDF",
+            Formaat::Html => "HTML",
+            Formaat::Tekst => "tekst",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for Formaat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Formaat::ALL
+            .into_iter()
+            .find(|f| f.key() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown format: {s}"))
+    }
+}
+
+/// A date written out in Dutch, e.g. `18 oktober 2026`.
+pub fn datum(date: NaiveDate) -> String {
+    format!(
+        "{} {} {}",
+        date.day(),
+        MAANDEN[date.month0() as usize],
+        date.year()
+    )
+}
+
+/// Picks the renderer from `FACTUUR_RENDERER`, either `pandoc` (the default)
+/// or `typst`.
+pub fn from_env() -> anyhow::Result<Arc<dyn PdfRenderer>> {
+    match env::var("FACTUUR_RENDERER").as_deref() {
+        Err(_) | Ok("pandoc") => Ok(Arc::new(Pandoc)),
+        Ok("typst") => Ok(Arc::new(Typst)),
+        Ok(other) => anyhow::bail!("unknown FACTUUR_RENDERER: {other:?}"),
+    }
+}
+
+/// Renders with our LaTeX template through pandoc and xelatex.
+#[derive(Debug)]
+pub struct Pandoc;
+
+impl InvoiceRenderer for Pandoc {
+    fn content_type(&self) -> &'static str {
+        "application/pdf"
+    }
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError> {
+        factuur.generate_pdf(self, settings)
+    }
+}
+
+impl PdfRenderer for Pandoc {
+    fn render_pdf(&self, details: &str) -> Result<Vec<u8>, FactuurError> {
+        let read_file = |err| FactuurError {
+            kind: FactuurErrorKind::ReadFile(err),
+        };
+
+        let mut details_file = NamedTempFile::new().map_err(read_file)?;
+        details_file
+            .write_all(details.as_bytes())
+            .map_err(read_file)?;
+
+        // Write the latex template to a temporary file so we can use it in our command
+        let mut tex_file = NamedTempFile::new().map_err(read_file)?;
+        tex_file.write_all(TEX_TEMPLATE).map_err(read_file)?;
+
+        // pandoc picks the output format from the extension
+        let output_file = tempfile::Builder::new()
+            .suffix(".pdf")
+            .tempfile()
+            .map_err(read_file)?;
+
+        let output = Command::new("pandoc")
+            .arg(details_file.path())
+            .arg("-o")
+            .arg(output_file.path())
+            .arg(format!("--template={}", tex_file.path().to_string_lossy()))
+            .arg("--pdf-engine=xelatex")
+            .output()
+            .map_err(|err| FactuurError {
+                kind: FactuurErrorKind::PandocCommand(err),
+            })?;
+
+        if !output.status.success() {
+            return Err(FactuurError {
+                kind: FactuurErrorKind::PandocCommand(io::Error::other(
+                    String::from_utf8_lossy(&output.stderr).to_string(),
+                )),
+            });
+        }
+
+        fs::read(output_file.path()).map_err(read_file)
+    }
+}
+
+/// Renders in process with `template.typ`, so no LaTeX installation is
+/// needed.
+#[derive(Debug)]
+pub struct Typst;
+
+impl InvoiceRenderer for Typst {
+    fn content_type(&self) -> &'static str {
+        "application/pdf"
+    }
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError> {
+        factuur.generate_pdf(self, settings)
+    }
+}
+
+impl PdfRenderer for Typst {
+    fn render_pdf(&self, details: &str) -> Result<Vec<u8>, FactuurError> {
+        let world = InvoiceWorld::new(details);
+        let mut tracer = Tracer::new();
+        let document = typst::compile(&world, &mut tracer).map_err(|diagnostics| {
+            let messages: Vec<String> = diagnostics.iter().map(|d| d.message.to_string()).collect();
+            FactuurError {
+                kind: FactuurErrorKind::TypstCompile(messages.join("\n")),
+            }
+        })?;
+
+        Ok(typst_pdf::pdf(&document, Smart::Auto, world.today(None)))
+    }
+}
+
+mod filters {
+    use crate::money::Money;
+
+    /// An amount with a decimal comma, as on the PDF.
+    pub fn bedrag(money: &Money) -> ::askama::Result<String> {
+        Ok(money.to_string().replace('.', ","))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "invoice/factuur.html")]
+struct HtmlTemplate<'a> {
+    factuur: &'a Factuur,
+    settings: &'a Settings,
+}
+
+/// Renders a standalone HTML page laid out like the PDF.
+#[derive(Debug)]
+pub struct Html;
+
+impl InvoiceRenderer for Html {
+    fn content_type(&self) -> &'static str {
+        "text/html; charset=utf-8"
+    }
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError> {
+        Ok(HtmlTemplate { factuur, settings }
+            .render()
+            .unwrap()
+            .into_bytes())
+    }
+}
+
+#[derive(Template)]
+#[template(path = "invoice/factuur.txt")]
+struct TekstTemplate<'a> {
+    factuur: &'a Factuur,
+    settings: &'a Settings,
+}
+
+/// Renders the invoice as plain text, for in the body of an email.
+#[derive(Debug)]
+pub struct Tekst;
+
+impl InvoiceRenderer for Tekst {
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError> {
+        Ok(TekstTemplate { factuur, settings }
+            .render()
+            .unwrap()
+            .into_bytes())
+    }
+}
+
+struct Fonts {
+    book: Prehashed<FontBook>,
+    fonts: Vec<Font>,
+}
+
+/// The bundled and system fonts, loaded on first use.
+fn fonts() -> &'static Fonts {
+    static FONTS: OnceLock<Fonts> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut fonts: Vec<Font> = typst_assets::fonts()
+            .flat_map(|data| Font::iter(Bytes::from_static(data)))
+            .collect();
+        for dir in FONT_DIRS {
+            load_fonts(Path::new(dir), &mut fonts);
+        }
+        Fonts {
+            book: Prehashed::new(FontBook::from_fonts(&fonts)),
+            fonts,
+        }
+    })
+}
+
+fn load_fonts(dir: &Path, fonts: &mut Vec<Font>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            load_fonts(&path, fonts);
+        } else if path.extension().is_some_and(|ext| {
+            ["ttf", "otf", "ttc", "otc"]
+                .iter()
+                .any(|f| ext.eq_ignore_ascii_case(f))
+        }) && let Ok(data) = fs::read(&path)
+        {
+            fonts.extend(Font::iter(Bytes::from(data)));
+        }
+    }
+}
+
+/// Everything the Typst compiler can see: the template, and the details it
+/// reads as `details.yml`.
+struct InvoiceWorld {
+    library: Prehashed<Library>,
+    main: Source,
+    details: Bytes,
+}
+
+impl InvoiceWorld {
+    fn new(details: &str) -> Self {
+        let main = FileId::new(None, VirtualPath::new("/template.typ"));
+        InvoiceWorld {
+            library: Prehashed::new(Library::default()),
+            main: Source::new(main, TYPST_TEMPLATE.to_owned()),
+            details: Bytes::from(yaml_document(details).as_bytes().to_vec()),
+        }
+    }
+}
+
+/// The details are a pandoc metadata block between `---` lines, which the
+/// YAML parser would read as two documents.
+fn yaml_document(details: &str) -> &str {
+    details
+        .trim()
+        .trim_start_matches("---")
+        .trim_end_matches("---")
+}
+
+impl World for InvoiceWorld {
+    fn library(&self) -> &Prehashed<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        &fonts().book
+    }
+
+    fn main(&self) -> Source {
+        self.main.clone()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        match id == self.main.id() {
+            true => Ok(self.main.clone()),
+            false => Err(FileError::NotFound(id.vpath().as_rootless_path().into())),
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        match id.vpath().as_rootless_path() == Path::new("details.yml") {
+            true => Ok(self.details.clone()),
+            false => Err(FileError::NotFound(id.vpath().as_rootless_path().into())),
+        }
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        fonts().fonts.get(index).cloned()
+    }
+
+    fn today(&self, _offset: Option<i64>) -> Option<Datetime> {
+        let today = Utc::now().date_naive();
+        Datetime::from_ymd(today.year(), today.month() as u8, today.day() as u8)
+    }
+}
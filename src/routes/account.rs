@@ -0,0 +1,222 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+    Form,
+};
+use axum_login::extractors::AuthContext;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    db,
+    server::AppState,
+    user::{self, Role, User, Users},
+    Page,
+};
+
+#[derive(Template)]
+#[template(path = "account.html")]
+pub struct AccountTemplate {
+    page: Page,
+    user: User,
+    /// Everyone who can log in to this tenant, for owners only
+    gebruikers: Vec<User>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> AccountTemplate {
+    let gebruikers = if user.role == Role::Eigenaar {
+        let mut conn = state.db.acquire().await.unwrap();
+        match db::get_users(&mut conn, user.tenant).await {
+            Ok(gebruikers) => gebruikers,
+            Err(err) => {
+                println!("Failed to fetch users from DB: {:?}", err);
+                vec![]
+            }
+        }
+    } else {
+        vec![]
+    };
+
+    AccountTemplate {
+        page: Page::Account,
+        user,
+        gebruikers,
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WachtwoordForm {
+    huidig: String,
+    nieuw: String,
+}
+
+pub async fn password_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    mut auth: AuthContext<usize, User, Users, Role>,
+    Form(form): Form<WachtwoordForm>,
+) -> impl IntoResponse {
+    if user.verify_password(&form.huidig).is_err() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, je huidige wachtwoord klopt niet.".to_owned(),
+        ));
+    }
+    if form.nieuw.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, je nieuwe wachtwoord is leeg.".to_owned(),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let res = async {
+        let password_hash = user::hash_password(&form.nieuw)?;
+        db::set_password_hash(&mut conn, user.id as i64, &password_hash).await?;
+        // The session remembers the old hash, so log in again with the new one
+        let user = User {
+            password_hash,
+            ..user
+        };
+        auth.login(&user).await?;
+        anyhow::Ok(())
+    };
+
+    match res.await {
+        Ok(_) => Ok(Redirect::to("/account")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het veranderen van je wachtwoord. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GebruikerForm {
+    username: String,
+    password: String,
+    role: String,
+}
+
+/// Adds someone who can log in to the same tenant.
+pub async fn user_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<GebruikerForm>,
+) -> impl IntoResponse {
+    let role: Role = match form.role.parse() {
+        Ok(role) => role,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+    let username = form.username.trim();
+    if username.is_empty() || form.password.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, vul een gebruikersnaam en wachtwoord in.".to_owned(),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+
+    // Usernames decide the tenant at login, so they are unique over all tenants
+    if let Ok(Some(_)) = db::get_user_by_name(&mut conn, username).await {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, die gebruikersnaam is al in gebruik.".to_owned(),
+        ));
+    }
+
+    let res = async {
+        let password_hash = user::hash_password(&form.password)?;
+        db::add_user(&mut conn, user.tenant, username, &password_hash, role).await
+    };
+
+    match res.await {
+        Ok(_) => Ok(Redirect::to("/account")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het toevoegen van de gebruiker. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RolForm {
+    id: i64,
+    role: String,
+}
+
+pub async fn role_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<RolForm>,
+) -> impl IntoResponse {
+    let role: Role = match form.role.parse() {
+        Ok(role) => role,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+    // Which leaves at least one owner who can manage the others
+    if form.id == user.id as i64 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, je eigen rol kun je niet veranderen.".to_owned(),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::set_role(&mut conn, user.tenant, form.id, role).await {
+        Ok(_) => Ok(Redirect::to("/account")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het veranderen van de rol. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerwijderParams {
+    id: i64,
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<VerwijderParams>,
+) -> impl IntoResponse {
+    if params.id == user.id as i64 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, jezelf kun je niet verwijderen.".to_owned(),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_user(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/account")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het verwijderen van de gebruiker. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
@@ -0,0 +1,126 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::extract::{Extension, Multipart, State};
+use chrono::NaiveDate;
+use reqwest::StatusCode;
+
+use crate::{
+    client::ClientDetails,
+    db,
+    ics::{Filter, Ics},
+    schedule::Schedule,
+    server::AppState,
+    user::User,
+    Page,
+};
+
+use super::rooster::factuur_met_diensten;
+
+/// Calendars of a few years are well below this.
+pub const MAX_CALENDAR_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Template)]
+#[template(path = "agenda.html")]
+pub struct AgendaTemplate {
+    page: Page,
+    klanten: Vec<ClientDetails>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> AgendaTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let klanten = db::get_client_details(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default();
+
+    AgendaTemplate {
+        page: Page::Agenda,
+        klanten,
+    }
+}
+
+/// Makes an invoice of the events in the uploaded calendar that match the
+/// filters, from `van` up to and including `tot`.
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut agenda = None;
+    let (mut van, mut tot, mut klant) = (String::new(), String::new(), String::new());
+    let mut filter = Filter::default();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        };
+        let name = field.name().unwrap_or_default().to_owned();
+        let value = match field.bytes().await {
+            Ok(bytes) => bytes,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        };
+        let text = || String::from_utf8_lossy(&value).trim().to_owned();
+        let filled = || Some(text()).filter(|s| !s.is_empty());
+        match name.as_str() {
+            "agenda" => agenda = Some(value.clone()),
+            "van" => van = text(),
+            "tot" => tot = text(),
+            "klant" => klant = text(),
+            "kalender" => filter.calendar = filled(),
+            "categorie" => filter.category = filled(),
+            "titel" => filter.title = filled(),
+            _ => (),
+        }
+    }
+
+    let inhoud = match agenda
+        .ok_or_else(|| anyhow::anyhow!("no calendar uploaded"))
+        .and_then(|b| Ok(String::from_utf8(b.to_vec())?))
+    {
+        Ok(inhoud) => inhoud,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die agenda kan ik niet lezen: {}", err),
+            ))
+        }
+    };
+    let (from, until) = match (van.parse::<NaiveDate>(), tot.parse::<NaiveDate>()) {
+        (Ok(from), Ok(until)) if from <= until => (from, until),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Hey, kies een periode met een begin voor het eind.".to_owned(),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let klant = match klant.parse::<i64>() {
+        Ok(id) => match db::get_client_by_id(&mut conn, user.tenant, id).await {
+            Ok(klant) => Some(klant),
+            Err(err) => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!("Hey, deze klant kunnen we niet vinden: {}", err),
+                ))
+            }
+        },
+        Err(_) => None,
+    };
+
+    let shifts = match Ics::new(inhoud, filter).shifts(from, until).await {
+        Ok(shifts) => shifts,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die agenda kan ik niet lezen: {}", err),
+            ))
+        }
+    };
+
+    Ok(factuur_met_diensten(&state, &mut conn, user.tenant, klant, Ok(shifts)).await)
+}
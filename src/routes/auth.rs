@@ -1,12 +1,13 @@
-use argon2::{PasswordHash, PasswordVerifier};
 use askama::Template;
 use askama_axum::IntoResponse;
 use axum::{extract::State, response::Redirect, Form};
-use axum_login::{extractors::AuthContext, memory_store::MemoryStore as AuthMemoryStore};
+use axum_login::extractors::AuthContext;
 use serde::Deserialize;
 
 use crate::{
-    server::{AppState, User},
+    db,
+    server::AppState,
+    user::{Role, User, Users},
     Page,
 };
 
@@ -22,27 +23,33 @@ pub async fn login_get() -> LoginTemplate {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct LoginForm {
+    username: String,
     password: String,
 }
 
 pub async fn login_post(
     State(state): State<AppState>,
-    mut auth: AuthContext<usize, User, AuthMemoryStore<usize, User>>,
+    mut auth: AuthContext<usize, User, Users, Role>,
     Form(login_form): Form<LoginForm>,
 ) -> axum::response::Result<impl IntoResponse> {
-    let parsed_hash = match PasswordHash::new(&state.user.password_hash) {
-        Ok(hash) => hash,
-        Err(err) => return Err(format!("Failed to parse user's password hash: {err:#?}").into()),
+    let mut conn = state.db.acquire().await.unwrap();
+    let user = match db::get_user_by_name(&mut conn, login_form.username.trim()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            println!("Authentication error: no user {:?}", login_form.username);
+            return Err("Authentication failure".into());
+        }
+        Err(err) => return Err(format!("Failed to fetch user: {err:#?}").into()),
     };
 
-    match argon2::Argon2::default().verify_password(login_form.password.as_bytes(), &parsed_hash) {
-        Ok(_) => match auth.login(&state.user).await {
+    match user.verify_password(&login_form.password) {
+        Ok(_) => match auth.login(&user).await {
             Ok(_) => Ok(Redirect::to("/")),
             Err(err) => Err(format!("Failed to login: {err:#?}").into()),
         },
         Err(err) => {
             println!("Authentication error: {err:#?}");
-            Err(format!("Authentication failure: {err:#?}").into())
+            Err("Authentication failure".into())
         }
     }
 }
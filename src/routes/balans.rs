@@ -0,0 +1,154 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::{Datelike, NaiveDate, Utc};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sqlx::SqliteConnection;
+
+use crate::{
+    db,
+    factuur::Factuur,
+    ledger::{Balans, Grootboek, OpeningBalance},
+    money::Money,
+    server::AppState,
+    user::User,
+    Page,
+};
+
+#[derive(Template)]
+#[template(path = "balans.html")]
+pub struct BalansTemplate {
+    page: Page,
+    balans: Balans,
+    opening: Option<OpeningBalance>,
+    openstaand: Vec<Factuur>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalansParams {
+    datum: Option<NaiveDate>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<BalansParams>,
+) -> impl IntoResponse {
+    let datum = params.datum.unwrap_or_else(|| Utc::now().date_naive());
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match balans_op(&mut conn, user.tenant, datum).await {
+        Ok((balans, opening, openstaand)) => Ok(BalansTemplate {
+            page: Page::Balans,
+            balans,
+            opening,
+            openstaand,
+        }),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, er ging iets mis bij het opstellen van de balans. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+/// Builds the grootboek from the most recent opening balance up to `datum`,
+/// and returns the balance sheet together with the invoices that make up
+/// the debiteuren.
+async fn balans_op(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    datum: NaiveDate,
+) -> anyhow::Result<(Balans, Option<OpeningBalance>, Vec<Factuur>)> {
+    let opening = db::get_opening_balance(&mut *conn, tenant, datum.year()).await?;
+    // Without an opening balance we start at the very first invoice
+    let begin = match &opening {
+        Some(o) => NaiveDate::from_ymd_opt(o.year, 1, 1).unwrap(),
+        None => NaiveDate::MIN,
+    };
+    let in_range = |d: NaiveDate| begin <= d && d <= datum;
+
+    let mut grootboek = Grootboek::new(opening.clone().unwrap_or_default());
+
+    let mut invoices = db::get_invoices(&mut *conn, tenant, db::InvoiceStatus::Active).await?;
+    invoices.sort_by_key(|i| i.nummer);
+    for i in invoices.iter() {
+        if in_range(i.date.date_naive()) {
+            grootboek.factuur(i);
+        }
+        // Invoices from before the opening balance can still be paid later on
+        for p in i.payments.iter().filter(|p| in_range(p.date)) {
+            grootboek.betaling(p);
+        }
+    }
+
+    for (_, e, _) in db::get_expenses(&mut *conn, tenant).await? {
+        if in_range(e.date) {
+            grootboek.kosten(&e);
+        }
+    }
+
+    for (q, bedrag) in db::get_all_voorbelasting(&mut *conn, tenant).await? {
+        if in_range(q.last_day()) {
+            grootboek.voorbelasting(&q, bedrag);
+        }
+    }
+
+    // Only the invoices that have not been paid in full make up the debiteuren
+    invoices.retain(|i| in_range(i.date.date_naive()) && i.outstanding(datum) > Money::ZERO);
+
+    Ok((grootboek.balans(datum), opening, invoices))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpeningBalanceForm {
+    jaar: i32,
+    bank: String,
+    debiteuren: String,
+    btw: String,
+}
+
+pub async fn opening_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<OpeningBalanceForm>,
+) -> impl IntoResponse {
+    let parse = |s: &str| match s.trim() {
+        "" => Ok(Money::ZERO),
+        s => s.parse::<Money>(),
+    };
+    let opening = match (parse(&form.bank), parse(&form.debiteuren), parse(&form.btw)) {
+        (Ok(bank), Ok(debiteuren), Ok(btw)) => OpeningBalance {
+            year: form.jaar,
+            bank,
+            debiteuren,
+            btw,
+        },
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            return Err((StatusCode::BAD_REQUEST, format!("{err}")))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::set_opening_balance(&mut conn, user.tenant, &opening).await {
+        Ok(_) => Ok(Redirect::to(
+            format!("/balans?datum={}-01-01", opening.year).as_str(),
+        )),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de beginbalans. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
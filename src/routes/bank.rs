@@ -0,0 +1,238 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Multipart, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sqlx::SqliteConnection;
+
+use crate::{
+    bank::{self, Transaction},
+    db::{self, BankLink},
+    factuur::Factuur,
+    payment::{Payment, PaymentMethod, PaymentStatus},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+/// Statements of a year are well below this, also as CAMT.053.
+pub const MAX_STATEMENT_SIZE: usize = 20 * 1024 * 1024;
+
+#[derive(Template)]
+#[template(path = "bank.html")]
+pub struct BankTemplate {
+    page: Page,
+    transactions: Vec<(i64, Transaction)>,
+    invoices: Vec<Factuur>,
+    import: Option<BankParams>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankParams {
+    /// Number of new transactions in the last import
+    nieuw: usize,
+    /// Number of those that were matched to an invoice
+    gekoppeld: usize,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    params: Option<Query<BankParams>>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    let transactions = match db::get_open_bank_transactions(&mut conn, user.tenant).await {
+        Ok(transactions) => transactions,
+        Err(err) => {
+            println!("Failed to fetch bank transactions from DB: {:?}", err);
+            vec![]
+        }
+    };
+    let invoices = match open_invoices(&mut conn, user.tenant).await {
+        Ok(invoices) => invoices,
+        Err(err) => {
+            println!("Failed to fetch invoices from DB: {:?}", err);
+            vec![]
+        }
+    };
+
+    BankTemplate {
+        page: Page::Bank,
+        transactions,
+        invoices,
+        import: params.map(|Query(params)| params),
+    }
+}
+
+/// Invoices that have not been paid in full, most recent first.
+async fn open_invoices(conn: &mut SqliteConnection, tenant: i64) -> anyhow::Result<Vec<Factuur>> {
+    let today = Utc::now().date_naive();
+    let mut invoices = db::get_invoices(&mut *conn, tenant, db::InvoiceStatus::Active).await?;
+    invoices.retain(|i| i.status(today) != PaymentStatus::Paid);
+    invoices.sort_by_key(|i| i.nummer);
+    invoices.reverse();
+    Ok(invoices)
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut statement = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("afschrift") => match field.bytes().await {
+                Ok(bytes) => statement = Some(bytes),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+            },
+            Ok(Some(_)) => (),
+            Ok(None) => break,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        }
+    }
+
+    let transactions = match statement
+        .ok_or_else(|| anyhow::anyhow!("no statement uploaded"))
+        .and_then(|s| Ok(String::from_utf8(s.to_vec())?))
+        .and_then(|s| bank::parse(&s))
+    {
+        Ok(transactions) => transactions,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat afschrift kan ik niet lezen: {}", err),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match import(&mut conn, user.tenant, &transactions).await {
+        Ok((nieuw, gekoppeld)) => Ok(Redirect::to(
+            format!("/bank?nieuw={nieuw}&gekoppeld={gekoppeld}").as_str(),
+        )),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het importeren van het afschrift. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+/// Stores the transactions that were not imported before and books the
+/// credits that match an open invoice as its payment. Returns the number of
+/// new and of matched transactions.
+async fn import(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    transactions: &[Transaction],
+) -> anyhow::Result<(usize, usize)> {
+    let mut invoices = open_invoices(&mut *conn, tenant).await?;
+    let (mut nieuw, mut gekoppeld) = (0, 0);
+
+    for t in transactions {
+        let Some(id) = db::add_bank_transaction(&mut *conn, tenant, t).await? else {
+            continue;
+        };
+        nieuw += 1;
+
+        let Some(nummer) = t.match_invoice(&invoices).map(|i| i.nummer) else {
+            continue;
+        };
+        let payment = Payment {
+            date: t.date,
+            amount: t.amount,
+            method: PaymentMethod::Overboeking,
+        };
+        let payment_id = db::add_payment(&mut *conn, tenant, nummer as u32, &payment).await?;
+        db::link_bank_transaction(&mut *conn, tenant, id, BankLink::Payment(payment_id)).await?;
+        gekoppeld += 1;
+
+        // The same invoice should not be matched twice within one statement
+        if let Some(i) = invoices.iter_mut().find(|i| i.nummer == nummer) {
+            i.payments.push(payment);
+        }
+    }
+
+    Ok((nieuw, gekoppeld))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KoppelForm {
+    id: i64,
+    factuur: usize,
+}
+
+/// Books a transaction from the review queue as payment of an invoice.
+pub async fn koppel_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<KoppelForm>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    let res = async {
+        let t = db::get_bank_transaction(&mut conn, user.tenant, form.id).await?;
+        if !t.is_credit() {
+            anyhow::bail!("only incoming transactions can pay an invoice");
+        }
+        let payment = Payment {
+            date: t.date,
+            amount: t.amount,
+            method: PaymentMethod::Overboeking,
+        };
+        let payment_id =
+            db::add_payment(&mut conn, user.tenant, form.factuur as u32, &payment).await?;
+        db::link_bank_transaction(
+            &mut conn,
+            user.tenant,
+            form.id,
+            BankLink::Payment(payment_id),
+        )
+        .await
+    };
+
+    match res.await {
+        Ok(_) => Ok(Redirect::to("/bank")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het koppelen van de betaling. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankActionParams {
+    id: i64,
+}
+
+/// Removes a transaction from the review queue, e.g. a private transfer.
+pub async fn negeer(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<BankActionParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::link_bank_transaction(&mut conn, user.tenant, params.id, BankLink::Ignored).await {
+        Ok(_) => Ok(Redirect::to("/bank")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, er ging iets mis bij het negeren van de transactie. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
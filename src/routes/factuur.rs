@@ -3,47 +3,154 @@ use std::{fmt::Display, str::FromStr};
 use askama::Template;
 use askama_axum::IntoResponse;
 use axum::{
-    extract::{Query, State},
+    extract::{Extension, Query, State},
     response::Redirect,
 };
 use axum_extra::extract::Form;
+use chrono::{Datelike, NaiveDate, TimeDelta, Utc};
+use rand::Rng;
 use reqwest::{header, StatusCode};
 use serde::{Deserialize, Deserializer};
+use sqlx::SqliteConnection;
 
 use crate::{
+    btw::BtwCategorie,
     db::{self, SoftDeleteAction},
     factuur::{self, Factuur, FactuurForm},
+    money::Money,
+    payment::Payment,
+    render::Formaat,
     server::AppState,
+    settings::Settings,
+    user::User,
     Page,
 };
 
+use super::instellingen;
+
 #[derive(Template)]
 #[template(path = "factuur.html")]
 pub struct FactuurTemplate {
     pub page: Page,
     pub client: Option<factuur::Client>,
     pub items: Vec<factuur::WorkItem>,
-    pub most_recent_invoice_id: Option<usize>,
+    /// Kenmerk the new invoice will get, unless someone else is quicker
+    pub volgende: Option<String>,
+    /// The invoice to credit, for a credit note
+    pub credit_for: Option<factuur::FactuurRef>,
+    /// The draft being worked on, if any
+    pub concept: Option<i64>,
+    /// Shown above the form, e.g. when not all shifts could be fetched
+    pub waarschuwing: Option<String>,
 }
 
 pub async fn get(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Query(params): Query<FactuurParams>,
 ) -> FactuurTemplate {
     let mut conn = state.db.acquire().await.unwrap();
 
-    let client = match params.client {
+    let mut client = match params.client {
         None => None,
-        Some(key) => db::get_client(&mut conn, &key).await.unwrap_or_default(),
+        Some(key) => db::get_client(&mut conn, user.tenant, &key)
+            .await
+            .unwrap_or_default()
+            .map(|c| c.client()),
     };
 
-    let most_recent_invoice_id = db::most_recent_invoice(&mut conn).await.unwrap_or_default();
+    // A credit note starts out as the full original invoice, negated
+    let mut items = vec![];
+    let mut credit_for = None;
+    if let Some(nummer) = params.credit {
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+            Ok(invoices) => {
+                if let Some(original) = invoices.into_iter().find(|i| i.nummer == nummer) {
+                    credit_for = Some(factuur::FactuurRef {
+                        nummer,
+                        kenmerk: original.kenmerk,
+                    });
+                    client = Some(original.client);
+                    items = original
+                        .work_items
+                        .into_iter()
+                        .map(|i| factuur::WorkItem { euro: -i.euro, ..i })
+                        .collect();
+                }
+            }
+            Err(err) => println!("Failed to fetch invoice to credit: {err}"),
+        }
+    }
+
+    // A draft is picked up where it was left
+    let mut concept = None;
+    if let Some(id) = params.concept {
+        match db::get_draft(&mut conn, user.tenant, id).await {
+            Ok(draft) => {
+                if let Some(nummer) = draft.credit_for {
+                    credit_for = factuur_ref(&mut conn, user.tenant, nummer).await;
+                }
+                concept = Some(draft.id);
+                client = Some(draft.client);
+                items = draft.work_items;
+            }
+            Err(err) => println!("Failed to fetch draft: {err}"),
+        }
+    }
+
+    let volgende = next_kenmerk(&mut conn, &state, user.tenant).await;
 
     FactuurTemplate {
         page: Page::Factuur,
         client,
-        items: vec![],
-        most_recent_invoice_id,
+        items,
+        volgende,
+        credit_for,
+        concept,
+        waarschuwing: None,
+    }
+}
+
+async fn factuur_ref(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    nummer: usize,
+) -> Option<factuur::FactuurRef> {
+    match db::get_invoices(&mut *conn, tenant, db::InvoiceStatus::Active).await {
+        Ok(invoices) => {
+            invoices
+                .into_iter()
+                .find(|i| i.nummer == nummer)
+                .map(|i| factuur::FactuurRef {
+                    nummer,
+                    kenmerk: i.kenmerk,
+                })
+        }
+        Err(err) => {
+            println!("Failed to fetch invoice {nummer}: {err}");
+            None
+        }
+    }
+}
+
+/// The kenmerk a new invoice of `tenant` created today would get.
+pub async fn next_kenmerk(
+    conn: &mut SqliteConnection,
+    state: &AppState,
+    tenant: i64,
+) -> Option<String> {
+    let jaar = Utc::now().year();
+    let kenmerk = async {
+        let settings = db::get_settings(&mut *conn, tenant).await?;
+        let nummering = settings.nummering(&state.nummering)?;
+        db::next_kenmerk(&mut *conn, tenant, &nummering, jaar).await
+    };
+    match kenmerk.await {
+        Ok(kenmerk) => Some(kenmerk),
+        Err(err) => {
+            println!("Failed to determine the next invoice number: {err}");
+            None
+        }
     }
 }
 
@@ -51,9 +158,13 @@ pub async fn get(
 pub struct FactuurParams {
     #[serde(default, deserialize_with = "empty_string_as_none")]
     client: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    credit: Option<usize>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    concept: Option<i64>,
 }
 
-fn empty_string_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+pub(crate) fn empty_string_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: FromStr,
@@ -70,17 +181,36 @@ where
 
 pub async fn post(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Form(factuur_form): Form<FactuurForm>,
 ) -> impl IntoResponse {
-    let factuur = Factuur::from(factuur_form);
+    let concept = factuur_form.concept;
+    let mut conn = state.db.acquire().await.unwrap();
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    instellingen::check_details(&settings)?;
+    let nummering = instellingen::nummering(&state, &settings)?;
+    let mut factuur = from_form(&mut conn, user.tenant, factuur_form, &settings).await?;
 
-    let factuur_file = match factuur.generate_pdf() {
-        Ok(f) => f,
+    // Generate the PDF once the number is assigned, and persist both
+    let render = |factuur: &Factuur| -> anyhow::Result<Vec<u8>> {
+        Ok(factuur.generate_pdf(state.renderer.as_ref(), &settings)?)
+    };
+    match db::add_invoice(
+        &mut conn,
+        user.tenant,
+        &mut factuur,
+        concept,
+        &nummering,
+        render,
+    )
+    .await
+    {
+        Ok(_) => (),
         Err(err) => {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!(
-                    "Hey er ging iets mis tijdens het genereren van de PDF. \
+                    "Hey er ging iets mis tijdens het maken van de factuur. \
                     Laat dit even zien aan Max:\n\n {}",
                     err
                 ),
@@ -88,13 +218,120 @@ pub async fn post(
         }
     };
 
-    let pdf = match tokio::fs::read(&factuur_file).await {
-        Ok(pdf) => pdf,
+    Ok(Redirect::to(
+        format!("/facturen?n={}#{}", factuur.nummer, factuur.nummer).as_str(),
+    ))
+}
+
+/// The invoice filled in on the form, with the invoice it corrects if it is
+/// a credit note.
+async fn from_form(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    factuur_form: FactuurForm,
+    settings: &Settings,
+) -> Result<Factuur, (StatusCode, String)> {
+    if let Some(id) = factuur_form.concept {
+        check_draft(&mut *conn, tenant, id).await?;
+    }
+
+    let credit_for = factuur_form.credit_for;
+    let mut factuur = match Factuur::try_from(factuur_form) {
+        Ok(factuur) => factuur,
         Err(err) => {
             return Err((
-                StatusCode::NOT_FOUND,
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die factuur klopt niet: {}", err),
+            ))
+        }
+    };
+
+    // Known clients can have a payment term of their own
+    factuur.payment_term = match db::get_client(&mut *conn, tenant, &factuur.client.name).await {
+        Ok(client) => client
+            .and_then(|c| c.payment_term)
+            .unwrap_or(settings.payment_term),
+        Err(err) => {
+            println!("Failed to fetch client {}: {err}", factuur.client.name);
+            settings.payment_term
+        }
+    };
+
+    if let Some(nummer) = credit_for {
+        match check_credit_note(&mut *conn, tenant, &factuur, nummer).await {
+            Ok(original) => factuur.credit_for = Some(original),
+            Err(err) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Hey, die creditfactuur klopt niet: {}", err),
+                ))
+            }
+        }
+    }
+
+    Ok(factuur)
+}
+
+/// A draft can only be finalized once.
+async fn check_draft(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    id: i64,
+) -> Result<(), (StatusCode, String)> {
+    match db::get_draft(conn, tenant, id).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err((
+            StatusCode::CONFLICT,
+            "Hey, dit concept is al een echte factuur geworden. Kijk even bij de facturen."
+                .to_owned(),
+        )),
+    }
+}
+
+/// An invoice rendered from the form, waiting to be confirmed.
+#[derive(Clone, Debug)]
+pub struct Voorbeeld {
+    /// With the kenmerk it was previewed with
+    pub factuur: Factuur,
+    pub pdf: Vec<u8>,
+    /// The draft it was made from
+    pub concept: Option<i64>,
+    /// Only the users of this tenant can see and confirm the preview
+    pub tenant: i64,
+}
+
+/// How long a preview can still be confirmed.
+const VOORBEELD_DAYS: i64 = 1;
+
+#[derive(Template)]
+#[template(path = "voorbeeld.html")]
+pub struct VoorbeeldTemplate {
+    page: Page,
+    id: String,
+    factuur: Factuur,
+}
+
+/// Renders the invoice on the form as it would be made, without storing it.
+pub async fn voorbeeld_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(factuur_form): Form<FactuurForm>,
+) -> impl IntoResponse {
+    let concept = factuur_form.concept;
+    let mut conn = state.db.acquire().await.unwrap();
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    instellingen::check_details(&settings)?;
+    let nummering = instellingen::nummering(&state, &settings)?;
+    let mut factuur = from_form(&mut conn, user.tenant, factuur_form, &settings).await?;
+
+    let jaar = factuur.date.year();
+    factuur.kenmerk = match db::next_kenmerk(&mut conn, user.tenant, &nummering, jaar).await {
+        Ok(kenmerk) => kenmerk,
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
                 format!(
-                    "Hey er ging iets mis tijdens het genereren van de PDF. \
+                    "Hey, er ging iets mis bij het bepalen van het factuurnummer. \
                     Laat dit even zien aan Max:\n\n {}",
                     err
                 ),
@@ -102,15 +339,13 @@ pub async fn post(
         }
     };
 
-    // Persist invoice details to the database
-    let mut conn = state.db.acquire().await.unwrap();
-    match db::add_invoice(&mut conn, &factuur, &pdf).await {
-        Ok(_) => (),
+    let pdf = match factuur.generate_pdf(state.renderer.as_ref(), &settings) {
+        Ok(pdf) => pdf,
         Err(err) => {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!(
-                    "Hey, foutje tijdens het updaten van de database. \
+                    "Hey er ging iets mis tijdens het genereren van de PDF. \
                     Laat dit even zien aan Max:\n\n {}",
                     err
                 ),
@@ -118,11 +353,165 @@ pub async fn post(
         }
     };
 
+    let id = format!("{:016x}", rand::thread_rng().r#gen::<u64>());
+    let mut voorbeelden = state.voorbeelden.write().await;
+    let verlopen = Utc::now() - TimeDelta::days(VOORBEELD_DAYS);
+    voorbeelden.retain(|_, v| v.factuur.date > verlopen);
+    voorbeelden.insert(
+        id.clone(),
+        Voorbeeld {
+            factuur: factuur.clone(),
+            pdf,
+            concept,
+            tenant: user.tenant,
+        },
+    );
+
+    Ok(VoorbeeldTemplate {
+        page: Page::Factuur,
+        id,
+        factuur,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoorbeeldParams {
+    id: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    formaat: Option<String>,
+}
+
+/// The previewed document, as PDF unless another format is asked for.
+pub async fn voorbeeld_get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<VoorbeeldParams>,
+) -> impl IntoResponse {
+    let formaat: Formaat = match params.formaat.as_deref().unwrap_or("pdf").parse() {
+        Ok(formaat) => formaat,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+    let Some(voorbeeld) = preview(&state, &user, &params.id).await else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Hey, dit voorbeeld is verlopen. Vul de factuur opnieuw in.".to_owned(),
+        ));
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    let renderer = formaat.renderer(&state.renderer);
+    let document = match formaat {
+        Formaat::Pdf => voorbeeld.pdf,
+        _ => match renderer.render(&voorbeeld.factuur, &settings) {
+            Ok(document) => document,
+            Err(err) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!(
+                        "Hey er ging iets mis tijdens het maken van de {}. \
+                        Laat dit even zien aan Max:\n\n {}",
+                        formaat, err
+                    ),
+                ))
+            }
+        },
+    };
+
+    Ok(([(header::CONTENT_TYPE, renderer.content_type())], document))
+}
+
+/// The preview with `id`, if it was made for the tenant of `user` and has not
+/// expired yet. Expired previews are only cleaned up when a new one is made.
+async fn preview(state: &AppState, user: &User, id: &str) -> Option<Voorbeeld> {
+    let verlopen = Utc::now() - TimeDelta::days(VOORBEELD_DAYS);
+    state
+        .voorbeelden
+        .read()
+        .await
+        .get(id)
+        .filter(|v| v.tenant == user.tenant && v.factuur.date > verlopen)
+        .cloned()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BevestigParams {
+    id: String,
+}
+
+/// Stores the previewed invoice with exactly the PDF that was shown, as
+/// long as it still gets the number it was previewed with.
+pub async fn bevestig(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(params): Form<BevestigParams>,
+) -> impl IntoResponse {
+    let Some(voorbeeld) = preview(&state, &user, &params.id).await else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Hey, dit voorbeeld is verlopen of de factuur is al gemaakt. \
+            Kijk even bij de facturen of vul hem opnieuw in."
+                .to_owned(),
+        ));
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    if let Some(id) = voorbeeld.concept {
+        check_draft(&mut conn, user.tenant, id).await?;
+    }
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    instellingen::check_details(&settings)?;
+    let nummering = instellingen::nummering(&state, &settings)?;
+
+    let mut factuur = voorbeeld.factuur.clone();
+    let render = |factuur: &Factuur| -> anyhow::Result<Vec<u8>> {
+        if factuur.kenmerk != voorbeeld.factuur.kenmerk {
+            anyhow::bail!(
+                "nummer {} is intussen aan een andere factuur gegeven, bekijk het \
+                voorbeeld opnieuw zodat deze factuur nummer {} krijgt",
+                voorbeeld.factuur.kenmerk,
+                factuur.kenmerk
+            );
+        }
+        Ok(voorbeeld.pdf.clone())
+    };
+    if let Err(err) = db::add_invoice(
+        &mut conn,
+        user.tenant,
+        &mut factuur,
+        voorbeeld.concept,
+        &nummering,
+        render,
+    )
+    .await
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Hey, de factuur is niet gemaakt: {}", err),
+        ));
+    }
+    state.voorbeelden.write().await.remove(&params.id);
+
     Ok(Redirect::to(
         format!("/facturen?n={}#{}", factuur.nummer, factuur.nummer).as_str(),
     ))
 }
 
+/// A credit note has to correct an existing invoice of the tenant, as far
+/// as `Factuur::check_credit_note` allows. Returns the invoice to credit.
+async fn check_credit_note(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    credit_note: &Factuur,
+    nummer: usize,
+) -> anyhow::Result<factuur::FactuurRef> {
+    let invoices = db::get_invoices(&mut *conn, tenant, db::InvoiceStatus::Active).await?;
+    let Some(original) = invoices.iter().find(|i| i.nummer == nummer) else {
+        anyhow::bail!("factuur {nummer} bestaat niet");
+    };
+    original.check_credit_note(credit_note)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FactuurActionParams {
     factuur: usize,
@@ -130,10 +519,11 @@ pub struct FactuurActionParams {
 
 pub async fn download(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Query(params): Query<FactuurActionParams>,
 ) -> impl IntoResponse {
     let mut conn = state.db.acquire().await.unwrap();
-    let (name, pdf) = match db::get_pdf(&mut conn, params.factuur as u32).await {
+    let (name, pdf) = match db::get_pdf(&mut conn, user.tenant, params.factuur as u32).await {
         Ok((n, p)) => (n, p),
         Err(err) => {
             return Err((
@@ -158,12 +548,137 @@ pub async fn download(
     Ok((headers, pdf))
 }
 
+/// Saves the form as a draft, to finish it later.
+pub async fn concept_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(factuur_form): Form<FactuurForm>,
+) -> impl IntoResponse {
+    let (id, credit_for) = (factuur_form.concept, factuur_form.credit_for);
+    let factuur = match Factuur::try_from(factuur_form) {
+        Ok(factuur) => factuur,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat concept klopt niet: {}", err),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::save_draft(
+        &mut conn,
+        user.tenant,
+        id,
+        &factuur.client,
+        &factuur.work_items,
+        credit_for,
+    )
+    .await
+    {
+        Ok(_) => Ok(Redirect::to("/")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van het concept. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConceptParams {
+    id: i64,
+}
+
+pub async fn concept_delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<ConceptParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_draft(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het verwijderen van het concept. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BekijkParams {
+    factuur: usize,
+    formaat: String,
+}
+
+/// Renders an invoice on the fly in the requested format, to view it in the
+/// browser or to copy it into an email.
+pub async fn bekijk(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<BekijkParams>,
+) -> impl IntoResponse {
+    let formaat: Formaat = match params.formaat.parse() {
+        Ok(formaat) => formaat,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let factuur = match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+        Ok(invoices) => invoices.into_iter().find(|i| i.nummer == params.factuur),
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "Hey, er gings iets mis bij het ophalen van de factuur uit de database. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+            ))
+        }
+    };
+    let Some(factuur) = factuur else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Hey, factuur {} bestaat niet.", params.factuur),
+        ));
+    };
+
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    let renderer = formaat.renderer(&state.renderer);
+    match renderer.render(&factuur, &settings) {
+        Ok(document) => Ok(([(header::CONTENT_TYPE, renderer.content_type())], document)),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey er ging iets mis tijdens het maken van de {}. \
+                Laat dit even zien aan Max:\n\n {}",
+                formaat, err
+            ),
+        )),
+    }
+}
+
 pub async fn delete(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Query(params): Query<FactuurActionParams>,
 ) -> impl IntoResponse {
     let mut conn = state.db.acquire().await.unwrap();
-    match db::soft_delete_invoice(&mut conn, params.factuur as u32, SoftDeleteAction::Delete).await
+    match db::soft_delete_invoice(
+        &mut conn,
+        user.tenant,
+        params.factuur as u32,
+        SoftDeleteAction::Delete,
+    )
+    .await
     {
         Ok(_) => Ok(Redirect::to("/facturen")),
         Err(err) => Err((
@@ -179,10 +694,17 @@ pub async fn delete(
 
 pub async fn restore(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Query(params): Query<FactuurActionParams>,
 ) -> impl IntoResponse {
     let mut conn = state.db.acquire().await.unwrap();
-    match db::soft_delete_invoice(&mut conn, params.factuur as u32, SoftDeleteAction::Restore).await
+    match db::soft_delete_invoice(
+        &mut conn,
+        user.tenant,
+        params.factuur as u32,
+        SoftDeleteAction::Restore,
+    )
+    .await
     {
         Ok(_) => Ok(Redirect::to(
             format!("/facturen?n={}#{}", params.factuur, params.factuur).as_str(),
@@ -197,3 +719,42 @@ pub async fn restore(
         )),
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentForm {
+    factuur: usize,
+    date: NaiveDate,
+    amount: String,
+    method: String,
+}
+
+pub async fn payment_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<PaymentForm>,
+) -> impl IntoResponse {
+    let payment = match (form.amount.parse::<Money>(), form.method.parse()) {
+        (Ok(amount), Ok(method)) => Payment {
+            date: form.date,
+            amount,
+            method,
+        },
+        (Err(err), _) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        (_, Err(err)) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::add_payment(&mut conn, user.tenant, form.factuur as u32, &payment).await {
+        Ok(_) => Ok(Redirect::to(
+            format!("/facturen?n={}#{}", form.factuur, form.factuur).as_str(),
+        )),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, er ging iets mis bij het opslaan van de betaling. \
+                    Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
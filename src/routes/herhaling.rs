@@ -0,0 +1,141 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::NaiveDate;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    btw::BtwCategorie,
+    db,
+    factuur::{Factuur, FactuurForm},
+    recurring::{Frequency, RecurringInvoice},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+#[derive(Template)]
+#[template(path = "herhalingen.html")]
+pub struct HerhalingenTemplate {
+    page: Page,
+    herhalingen: Vec<RecurringInvoice>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> HerhalingenTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let herhalingen = match db::get_recurring_invoices(&mut conn, user.tenant).await {
+        Ok(herhalingen) => herhalingen,
+        Err(err) => {
+            println!("Failed to fetch recurring invoices from DB: {:?}", err);
+            vec![]
+        }
+    };
+
+    HerhalingenTemplate {
+        page: Page::Facturen,
+        herhalingen,
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HerhalingForm {
+    client_name: String,
+    client_address: String,
+    client_zip: String,
+    task: Vec<String>,
+    price: Vec<String>,
+    btw: Vec<String>,
+    frequency: String,
+    next_run: NaiveDate,
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<HerhalingForm>,
+) -> impl IntoResponse {
+    let frequency: Frequency = match form.frequency.parse() {
+        Ok(frequency) => frequency,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+
+    // The work items are filled in just like on a normal invoice
+    let factuur = match Factuur::try_from(FactuurForm {
+        client_name: form.client_name,
+        client_address: form.client_address,
+        client_zip: form.client_zip,
+        tasks: form.task,
+        prices: form.price,
+        btw_categorieen: form.btw,
+        credit_for: None,
+        concept: None,
+    }) {
+        Ok(factuur) => factuur,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die herhalende factuur klopt niet: {}", err),
+            ))
+        }
+    };
+    if factuur.work_items.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, een herhalende factuur heeft minstens één regel nodig.".to_owned(),
+        ));
+    }
+
+    let recurring = RecurringInvoice {
+        id: 0,
+        client: factuur.client,
+        work_items: factuur.work_items,
+        frequency,
+        next_run: form.next_run,
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::add_recurring_invoice(&mut conn, user.tenant, &recurring).await {
+        Ok(_) => Ok(Redirect::to("/herhalingen")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de herhalende factuur. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HerhalingParams {
+    id: i64,
+}
+
+/// Stops a recurring invoice. Drafts that were already made are kept.
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<HerhalingParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_recurring_invoice(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/herhalingen")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het verwijderen van de herhalende factuur. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
@@ -0,0 +1,187 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::{NaiveDate, Utc};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+
+use crate::{
+    db,
+    factuur::Factuur,
+    reminder::{self, Reminder, ReminderKind},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+use super::instellingen;
+
+#[derive(Template)]
+#[template(path = "herinneringen.html")]
+pub struct HerinneringenTemplate {
+    page: Page,
+    today: NaiveDate,
+    /// Overdue invoices with the reminder that is due for them
+    te_versturen: Vec<(Factuur, ReminderKind)>,
+    /// Log of all reminders that were sent, most recent first
+    verstuurd: Vec<(Factuur, Reminder)>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> HerinneringenTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let mut invoices =
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+            Ok(invoices) => invoices,
+            Err(err) => {
+                println!("Failed to fetch invoices from DB: {:?}", err);
+                vec![]
+            }
+        };
+    invoices.sort_by_key(|i| i.nummer);
+
+    let today = Utc::now().date_naive();
+    let te_versturen = invoices
+        .iter()
+        .filter_map(|i| i.reminder_due(today).map(|kind| (i.clone(), kind)))
+        .collect();
+
+    let mut verstuurd: Vec<(Factuur, Reminder)> = invoices
+        .iter()
+        .flat_map(|i| i.reminders.iter().map(|r| (i.clone(), r.clone())))
+        .collect();
+    verstuurd.sort_by_key(|(i, r)| (r.date, r.kind, i.nummer));
+    verstuurd.reverse();
+
+    HerinneringenTemplate {
+        page: Page::Facturen,
+        today,
+        te_versturen,
+        verstuurd,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HerinneringParams {
+    factuur: usize,
+    soort: String,
+}
+
+/// Generates the reminder that is due for an invoice and logs it as sent.
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<HerinneringParams>,
+) -> impl IntoResponse {
+    let kind: ReminderKind = match form.soort.parse() {
+        Ok(kind) => kind,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let factuur = match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+        Ok(invoices) => invoices.into_iter().find(|i| i.nummer == form.factuur),
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "Hey, er gings iets mis bij het ophalen van de factuur uit de database. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+            ))
+        }
+    };
+
+    let today = Utc::now().date_naive();
+    let Some(factuur) = factuur.filter(|f| f.reminder_due(today) == Some(kind)) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Hey, voor factuur {} hoeft nu geen {} verstuurd te worden.",
+                form.factuur, kind
+            ),
+        ));
+    };
+
+    let settings = instellingen::load(&mut conn, user.tenant).await?;
+    let reminder = Reminder { kind, date: today };
+    let pdf = match reminder::generate_pdf(&factuur, &reminder, state.renderer.as_ref(), &settings)
+    {
+        Ok(pdf) => pdf,
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "Hey er ging iets mis tijdens het genereren van de PDF. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+            ))
+        }
+    };
+
+    match db::add_reminder(
+        &mut conn,
+        user.tenant,
+        factuur.nummer as u32,
+        &reminder,
+        &pdf,
+    )
+    .await
+    {
+        // Also when it was just sent from another tab
+        Ok(_) => Ok(Redirect::to("/herinneringen")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de herinnering. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+pub async fn download(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<HerinneringParams>,
+) -> impl IntoResponse {
+    let kind: ReminderKind = match params.soort.parse() {
+        Ok(kind) => kind,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let (name, pdf) =
+        match db::get_reminder_pdf(&mut conn, user.tenant, params.factuur as u32, kind).await {
+            Ok((n, p)) => (n, p),
+            Err(err) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!(
+                    "Hey, er gings iets mis bij het ophalen van de herinnering uit de database. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+                ))
+            }
+        };
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/pdf".to_owned()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", name),
+        ),
+    ];
+
+    Ok((headers, pdf))
+}
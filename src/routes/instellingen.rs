@@ -0,0 +1,105 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use reqwest::StatusCode;
+use sqlx::SqliteConnection;
+
+use crate::{db, nummering::Nummering, server::AppState, settings::Settings, user::User, Page};
+
+#[derive(Template)]
+#[template(path = "instellingen.html")]
+pub struct InstellingenTemplate {
+    page: Page,
+    settings: Settings,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    let settings = load(&mut conn, user.tenant).await?;
+
+    Ok::<_, (StatusCode, String)>(InstellingenTemplate {
+        page: Page::Instellingen,
+        settings,
+    })
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(settings): Form<Settings>,
+) -> impl IntoResponse {
+    if let Err(err) = settings.nummering(&state.nummering) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Hey, die factuurnummering klopt niet: {}", err),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::set_settings(&mut conn, user.tenant, &settings).await {
+        Ok(_) => Ok(Redirect::to("/instellingen")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de instellingen. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+/// The settings of `tenant`, which every invoice and reminder is rendered
+/// with.
+pub async fn load(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+) -> Result<Settings, (StatusCode, String)> {
+    db::get_settings(conn, tenant).await.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, de instellingen konden niet opgehaald worden. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )
+    })
+}
+
+/// Invoices can only be made once the details of the business are filled in.
+pub fn check_details(settings: &Settings) -> Result<(), (StatusCode, String)> {
+    let missing = settings.missing_details();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err((
+        StatusCode::CONFLICT,
+        format!(
+            "Hey, vul eerst de gegevens van je bedrijf in op /instellingen, \
+            die komen op elke factuur. Nog leeg: {}.",
+            missing.join(", ")
+        ),
+    ))
+}
+
+/// How the new invoices of a tenant with `settings` are numbered.
+pub fn nummering(state: &AppState, settings: &Settings) -> Result<Nummering, (StatusCode, String)> {
+    settings.nummering(&state.nummering).map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, de factuurnummering in de instellingen klopt niet. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )
+    })
+}
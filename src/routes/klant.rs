@@ -0,0 +1,376 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Multipart, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sqlx::SqliteConnection;
+
+use crate::{
+    client::{self, ClientDetails, Wijziging},
+    db,
+    money::Money,
+    server::AppState,
+    user::User,
+    Page,
+};
+
+use super::factuur::empty_string_as_none;
+use super::instellingen;
+
+#[derive(Template)]
+#[template(path = "klanten.html")]
+pub struct KlantenTemplate {
+    page: Page,
+    klanten: Vec<ClientDetails>,
+    archief: Vec<ClientDetails>,
+    import: Option<KlantenParams>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KlantenParams {
+    /// Number of clients added or changed by the last import
+    geimporteerd: usize,
+}
+
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    params: Option<Query<KlantenParams>>,
+) -> KlantenTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let klanten = match db::get_client_details(&mut conn, user.tenant).await {
+        Ok(klanten) => klanten,
+        Err(err) => {
+            println!("Failed to fetch clients from DB: {:?}", err);
+            vec![]
+        }
+    };
+
+    let (archief, klanten) = klanten.into_iter().partition(|k| k.archived);
+
+    KlantenTemplate {
+        page: Page::Klanten,
+        klanten,
+        archief,
+        import: params.map(|Query(params)| params),
+    }
+}
+
+#[derive(Template)]
+#[template(path = "klant.html")]
+pub struct KlantTemplate {
+    page: Page,
+    klant: ClientDetails,
+    /// The clients that could be duplicates of this one
+    anderen: Vec<ClientDetails>,
+    /// Payment term for clients without one of their own
+    standaard_termijn: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KlantParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    id: Option<i64>,
+}
+
+/// The form for a new client, or to edit the client with `id`.
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<KlantParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+
+    let klant = match params.id {
+        None => ClientDetails {
+            country: "Nederland".to_owned(),
+            ..Default::default()
+        },
+        Some(id) => match db::get_client_by_id(&mut conn, user.tenant, id).await {
+            Ok(klant) => klant,
+            Err(err) => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!("Hey, deze klant kunnen we niet vinden: {}", err),
+                ))
+            }
+        },
+    };
+
+    let anderen = match params.id {
+        None => vec![],
+        Some(id) => db::get_client_details(&mut conn, user.tenant)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| c.id != id)
+            .collect(),
+    };
+
+    let standaard_termijn = instellingen::load(&mut conn, user.tenant)
+        .await?
+        .payment_term;
+
+    Ok(KlantTemplate {
+        page: Page::Klanten,
+        klant,
+        anderen,
+        standaard_termijn,
+    })
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct KlantForm {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    id: Option<i64>,
+    name: String,
+    address: String,
+    zip: String,
+    kvk: String,
+    btw_id: String,
+    email: String,
+    contact: String,
+    country: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    payment_term: Option<u64>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    hourly_rate: Option<Money>,
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<KlantForm>,
+) -> impl IntoResponse {
+    let id = form.id.unwrap_or_default();
+    let klant = ClientDetails {
+        id,
+        name: form.name.trim().to_owned(),
+        address: form.address,
+        zip: form.zip,
+        kvk: form.kvk,
+        btw_id: form.btw_id,
+        email: form.email,
+        contact: form.contact,
+        country: form.country,
+        payment_term: form.payment_term,
+        hourly_rate: form.hourly_rate,
+        archived: false,
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+
+    // Names identify clients on the invoice form, so they have to be unique
+    match db::get_client(&mut conn, user.tenant, &klant.name).await {
+        Ok(Some(other)) if other.id != id => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Hey, er is al een klant met deze naam. \
+                Is het dezelfde klant? Voeg ze dan samen."
+                    .to_owned(),
+            ))
+        }
+        _ => (),
+    }
+
+    match db::save_client(&mut conn, user.tenant, &klant).await {
+        Ok(_) => Ok(Redirect::to("/klanten")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de klant. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveerParams {
+    id: i64,
+}
+
+pub async fn archive(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<ArchiveerParams>,
+) -> impl IntoResponse {
+    set_archived(state, user, params.id, true).await
+}
+
+pub async fn restore(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<ArchiveerParams>,
+) -> impl IntoResponse {
+    set_archived(state, user, params.id, false).await
+}
+
+async fn set_archived(
+    state: AppState,
+    user: User,
+    id: i64,
+    archived: bool,
+) -> Result<Redirect, (StatusCode, String)> {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::set_client_archived(&mut conn, user.tenant, id, archived).await {
+        Ok(_) => Ok(Redirect::to("/klanten")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het archiveren van de klant. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SamenvoegenForm {
+    /// The client that is kept
+    id: i64,
+    /// The duplicate that is merged into it
+    duplicaat: i64,
+}
+
+pub async fn merge(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<SamenvoegenForm>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::merge_clients(&mut conn, user.tenant, form.id, form.duplicaat).await {
+        Ok(_) => Ok(Redirect::to(format!("/klant?id={}", form.id).as_str())),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het samenvoegen van de klanten. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+/// Address books are small, also as vCards with photos.
+pub const MAX_IMPORT_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Template)]
+#[template(path = "klantimport.html")]
+pub struct KlantImportTemplate {
+    page: Page,
+    wijzigingen: Vec<Wijziging>,
+    /// The uploaded file, to import once confirmed
+    inhoud: String,
+}
+
+impl KlantImportTemplate {
+    fn changes(&self) -> usize {
+        self.wijzigingen.iter().filter(|w| w.is_change()).count()
+    }
+}
+
+/// Shows what importing the uploaded clients would change, without writing
+/// anything yet.
+pub async fn import_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut bestand = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("bestand") => match field.bytes().await {
+                Ok(bytes) => bestand = Some(bytes),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+            },
+            Ok(Some(_)) => (),
+            Ok(None) => break,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        }
+    }
+
+    let inhoud = match bestand
+        .ok_or_else(|| anyhow::anyhow!("no file uploaded"))
+        .and_then(|b| Ok(String::from_utf8(b.to_vec())?))
+    {
+        Ok(inhoud) => inhoud,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat bestand kan ik niet lezen: {}", err),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let wijzigingen = compare(&mut conn, user.tenant, &inhoud).await?;
+
+    Ok(KlantImportTemplate {
+        page: Page::Klanten,
+        wijzigingen,
+        inhoud,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportForm {
+    inhoud: String,
+}
+
+/// Imports the clients that were shown by `import_post`. They are compared
+/// again, in case a client was changed in the meantime.
+pub async fn import_bevestig(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<ImportForm>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    let wijzigingen = compare(&mut conn, user.tenant, &form.inhoud).await?;
+
+    match db::import_clients(&mut conn, user.tenant, &wijzigingen).await {
+        Ok(n) => Ok(Redirect::to(format!("/klanten?geimporteerd={n}").as_str())),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het importeren van de klanten. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+async fn compare(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    inhoud: &str,
+) -> Result<Vec<Wijziging>, (StatusCode, String)> {
+    let import = match client::parse(inhoud) {
+        Ok(import) => import,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat bestand kan ik niet lezen: {}", err),
+            ))
+        }
+    };
+
+    match db::get_client_details(&mut *conn, tenant).await {
+        Ok(bestaand) => Ok(client::vergelijk(&bestaand, import)),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het ophalen van de klanten. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
@@ -0,0 +1,221 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Multipart, Query, State},
+    response::Redirect,
+};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+
+use crate::{
+    btw::BtwCategorie,
+    db::{self, BankLink},
+    expense::{Expense, ExpenseForm, Receipt},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+/// Receipts are mostly phone pictures, which easily exceed axum's default.
+pub const MAX_RECEIPT_SIZE: usize = 20 * 1024 * 1024;
+
+#[derive(Template)]
+#[template(path = "kosten.html")]
+pub struct KostenTemplate {
+    page: Page,
+    expenses: Vec<(i64, Expense, bool)>,
+    categories: Vec<String>,
+    /// Prefilled from a bank transaction in the review queue
+    form: ExpenseForm,
+    bank: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KostenParams {
+    bank: Option<i64>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<KostenParams>,
+) -> KostenTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let expenses = match db::get_expenses(&mut conn, user.tenant).await {
+        Ok(expenses) => expenses,
+        Err(err) => {
+            println!("Failed to fetch expenses from DB: {:?}", err);
+            vec![]
+        }
+    };
+
+    // Suggest categories that were used before
+    let mut categories: Vec<String> = expenses
+        .iter()
+        .map(|(_, e, _)| e.category.clone())
+        .filter(|c| !c.is_empty())
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    let form = match params.bank {
+        Some(id) => match db::get_bank_transaction(&mut conn, user.tenant, id).await {
+            Ok(t) => t.expense_suggestion(),
+            Err(err) => {
+                println!("Failed to fetch bank transaction from DB: {:?}", err);
+                ExpenseForm::default()
+            }
+        },
+        None => ExpenseForm::default(),
+    };
+
+    KostenTemplate {
+        page: Page::Kosten,
+        expenses,
+        categories,
+        form,
+        bank: params.bank,
+    }
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut form = ExpenseForm::default();
+    let mut receipt = None;
+    let mut bank = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        };
+
+        let name = field.name().unwrap_or_default().to_owned();
+        if name == "bon" {
+            let filename = field.file_name().unwrap_or_default().to_owned();
+            let content_type = field
+                .content_type()
+                .unwrap_or("application/octet-stream")
+                .to_owned();
+            let file = match field.bytes().await {
+                Ok(file) => file.to_vec(),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+            };
+
+            // Browsers send an empty file part when nothing was selected
+            if !file.is_empty() {
+                receipt = Some(Receipt {
+                    filename,
+                    content_type,
+                    file,
+                });
+            }
+            continue;
+        }
+
+        let value = match field.text().await {
+            Ok(value) => value,
+            Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+        };
+        match name.as_str() {
+            "date" => form.date = value,
+            "supplier" => form.supplier = value,
+            "category" => form.category = value,
+            "btw" => form.btw = value,
+            "incl" => form.incl = value,
+            "btw_bedrag" => form.btw_bedrag = value,
+            "bank" => bank = value.parse::<i64>().ok(),
+            _ => (),
+        }
+    }
+
+    let expense = match Expense::try_from(form) {
+        Ok(expense) => expense,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die kosten kloppen niet helemaal: {}", err),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let res = async {
+        let id = db::add_expense(&mut conn, user.tenant, &expense, receipt.as_ref()).await?;
+        if let Some(bank) = bank {
+            db::link_bank_transaction(&mut conn, user.tenant, bank, BankLink::Expense(id)).await?;
+        }
+        anyhow::Ok(bank)
+    };
+
+    match res.await {
+        Ok(Some(_)) => Ok(Redirect::to("/bank")),
+        Ok(None) => Ok(Redirect::to("/kosten")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het updaten van de database. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KostenActionParams {
+    id: i64,
+}
+
+pub async fn receipt(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<KostenActionParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    let receipt = match db::get_receipt(&mut conn, user.tenant, params.id).await {
+        Ok(receipt) => receipt,
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "Hey, er gings iets mis bij het ophalen van de bon uit de database. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+            ))
+        }
+    };
+
+    let (content_type, disposition) = receipt.headers();
+    let headers = [
+        (header::CONTENT_TYPE, content_type),
+        (header::CONTENT_DISPOSITION, disposition),
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_owned()),
+    ];
+
+    Ok((headers, receipt.file))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<KostenActionParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_expense(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/kosten")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, er ging iets mis bij het verwijderen van de kosten uit de database. \
+                    Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
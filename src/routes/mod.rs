@@ -1,4 +1,14 @@
-pub mod anita;
+pub mod account;
+pub mod agenda;
 pub mod auth;
+pub mod balans;
+pub mod bank;
 pub mod factuur;
+pub mod herhaling;
+pub mod herinnering;
+pub mod instellingen;
+pub mod klant;
+pub mod kosten;
 pub mod report;
+pub mod rooster;
+pub mod tarief;
@@ -1,14 +1,26 @@
 use std::collections::HashMap;
 
 use askama::Template;
-use axum::extract::{Query, State};
-use chrono::Datelike;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::{Datelike, NaiveDate, Utc};
+use reqwest::{header, StatusCode};
 use serde::Deserialize;
+use sqlx::SqliteConnection;
 
 use crate::{
+    aangifte::{Aangifte, Quarter},
     db,
+    expense::Expense,
     factuur::Factuur,
+    money::Money,
+    payment::{PaymentMethod, PaymentStatus},
     server::{filters, AppState},
+    user::User,
     Page,
 };
 
@@ -18,6 +30,10 @@ pub struct HistoryTemplate {
     page: Page,
     grouped_invoices: Vec<(YearMonth, Vec<Factuur>)>,
     focus: Option<usize>,
+    status: Option<String>,
+    today: NaiveDate,
+    /// Invoice numbers missing from the sequence
+    gaps: Vec<String>,
 }
 
 #[derive(Template)]
@@ -69,21 +85,52 @@ impl std::fmt::Display for YearMonth {
 #[derive(Debug, Deserialize)]
 pub struct HistoryParams {
     n: Option<usize>,
+    /// One of the `PaymentStatus` keys, or `openstaand` for everything that
+    /// has not been paid in full.
+    status: Option<String>,
 }
 
 pub async fn history_get(
     State(state): State<AppState>,
+    Extension(user): Extension<User>,
     Query(params): Query<HistoryParams>,
 ) -> HistoryTemplate {
     let mut conn = state.db.acquire().await.unwrap();
-    let mut invoices = match db::get_invoices(&mut conn, db::InvoiceStatus::Active).await {
-        Ok(invoices) => invoices,
+    let mut invoices =
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+            Ok(invoices) => invoices,
+            Err(err) => {
+                println!("Failed to fetch invoices from DB: {:?}", err);
+                vec![]
+            }
+        };
+
+    // Numbered like the invoices of the tenant
+    let nummering = db::get_settings(&mut conn, user.tenant)
+        .await
+        .and_then(|settings| settings.nummering(&state.nummering));
+    let gaps = match nummering {
+        Ok(nummering) => db::get_invoice_gaps(&mut conn, user.tenant, &nummering).await,
+        Err(err) => Err(err),
+    };
+    let gaps = match gaps {
+        Ok(gaps) => gaps,
         Err(err) => {
-            println!("Failed to fetch invoices from DB: {:?}", err);
+            println!("Failed to check invoice numbers for gaps: {:?}", err);
             vec![]
         }
     };
 
+    let today = Utc::now().date_naive();
+    match params.status.as_deref() {
+        None | Some("") => (),
+        Some("openstaand") => invoices.retain(|i| i.status(today) != PaymentStatus::Paid),
+        Some(key) => match key.parse::<PaymentStatus>() {
+            Ok(status) => invoices.retain(|i| i.status(today) == status),
+            Err(err) => println!("Ignoring invoice filter: {err}"),
+        },
+    }
+
     invoices.sort_by_key(|i| i.nummer);
     invoices.reverse();
 
@@ -108,46 +155,39 @@ pub async fn history_get(
         page: Page::Facturen,
         grouped_invoices,
         focus: params.n,
-    }
-}
-
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Quarter {
-    year: i32,
-    quarter: u32,
-}
-
-impl std::fmt::Display for Quarter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let quarters = HashMap::from([
-            (1, "jan–maa"),
-            (2, "apr–jun"),
-            (3, "jul–sep"),
-            (4, "okt–dec"),
-        ]);
-
-        let display_quarter = match quarters.get(&self.quarter) {
-            Some(q) => q,
-            None => "ooit",
-        };
-
-        write!(f, "{}Q{} ({})", self.year, self.quarter, display_quarter)
+        status: params.status.filter(|s| !s.is_empty()),
+        today,
+        gaps,
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Btw {
-    omzet: f64,
-    btw: f64,
+    omzet: Money,
+    btw: Money,
+    kosten: Money,
+    voorbelasting: Money,
+    aangifte: Aangifte,
     invoices: Vec<Factuur>,
 }
 
-pub async fn btw_get(State(state): State<AppState>) -> BtwTemplate {
+pub async fn btw_get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> BtwTemplate {
     let mut conn = state.db.acquire().await.unwrap();
-    let mut invoices = match db::get_invoices(&mut conn, db::InvoiceStatus::Active).await {
-        Ok(invoices) => invoices,
+    let mut invoices =
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+            Ok(invoices) => invoices,
+            Err(err) => {
+                println!("Failed to fetch invoices from DB: {:?}", err);
+                vec![]
+            }
+        };
+    let expenses = match db::get_expenses(&mut conn, user.tenant).await {
+        Ok(expenses) => expenses,
         Err(err) => {
-            println!("Failed to fetch invoices from DB: {:?}", err);
+            println!("Failed to fetch expenses from DB: {:?}", err);
             vec![]
         }
     };
@@ -155,53 +195,191 @@ pub async fn btw_get(State(state): State<AppState>) -> BtwTemplate {
     invoices.sort_by_key(|i| i.nummer);
     invoices.reverse();
 
-    let mut grouped_invoices = HashMap::new();
+    let mut grouped: HashMap<Quarter, (Vec<Factuur>, Vec<Expense>)> = HashMap::new();
 
     // Group by quarter
     for i in invoices {
-        let q = Quarter {
-            year: i.date.year(),
-            // Default division behavior on unsigned integers is to floor
-            quarter: ((i.date.month() as f32) / 3.0).ceil() as u32,
-        };
-        grouped_invoices.entry(q).or_insert(vec![]).push(i);
+        grouped.entry(Quarter::of(&i.date)).or_default().0.push(i);
+    }
+    for (_, e, _) in expenses {
+        grouped.entry(Quarter::of(&e.date)).or_default().1.push(e);
     }
 
-    let mut grouped_invoices: Vec<(Quarter, Btw)> = grouped_invoices
-        .into_iter()
-        .map(|(q, invoices)| {
-            let omzet = invoices.iter().map(|i| i.subtotal).sum();
-            let btw = invoices.iter().map(|i| i.btw).sum();
-            (
-                q,
-                Btw {
-                    omzet,
-                    btw,
-                    invoices,
-                },
-            )
-        })
-        .collect();
+    let mut quarters: Vec<(Quarter, Btw)> = vec![];
+    for (q, (invoices, expenses)) in grouped {
+        let voorbelasting = db::get_voorbelasting(&mut conn, user.tenant, &q)
+            .await
+            .unwrap_or_default();
+        let omzet = invoices.iter().map(|i| i.subtotal).sum();
+        let btw = invoices.iter().map(|i| i.btw_totaal()).sum();
+        let kosten = expenses.iter().map(|e| e.voorbelasting()).sum();
+        let aangifte = Aangifte::new(&invoices, &expenses, voorbelasting);
+        quarters.push((
+            q,
+            Btw {
+                omzet,
+                btw,
+                kosten,
+                voorbelasting,
+                aangifte,
+                invoices,
+            },
+        ));
+    }
 
-    grouped_invoices.sort_by(|(ym1, _), (ym2, _)| ym1.cmp(ym2));
-    grouped_invoices.reverse();
+    quarters.sort_by_key(|(q, _)| *q);
+    quarters.reverse();
 
     BtwTemplate {
         page: Page::Btw,
-        quarters: grouped_invoices,
+        quarters,
     }
 }
 
-pub async fn deleted_invoices(State(state): State<AppState>) -> DeletedInvoicesTemplate {
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormaat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AangifteParams {
+    jaar: i32,
+    kwartaal: u32,
+    formaat: ExportFormaat,
+}
+
+pub async fn aangifte_export(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<AangifteParams>,
+) -> impl IntoResponse {
+    let q = match Quarter::new(params.jaar, params.kwartaal) {
+        Ok(q) => q,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat kwartaal klopt niet: {}", err),
+            ))
+        }
+    };
+
     let mut conn = state.db.acquire().await.unwrap();
-    let mut invoices = match db::get_invoices(&mut conn, db::InvoiceStatus::Deleted).await {
-        Ok(invoices) => invoices,
+    let aangifte = match aangifte_for_quarter(&mut conn, user.tenant, &q).await {
+        Ok(aangifte) => aangifte,
         Err(err) => {
-            println!("Failed to fetch deleted invoices from DB: {:?}", err);
-            vec![]
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "Hey, er ging iets mis bij het opstellen van de BTW-aangifte. \
+                    Laat dit even zien aan Max:\n\n {}",
+                    err
+                ),
+            ))
+        }
+    };
+
+    let (content_type, extension, body) = match params.formaat {
+        ExportFormaat::Csv => ("text/csv; charset=utf-8", "csv", aangifte.to_csv()),
+        ExportFormaat::Json => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&aangifte).unwrap(),
+        ),
+    };
+
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_owned()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"BTW-aangifte {}Q{}.{}\"",
+                q.year(),
+                q.quarter(),
+                extension
+            ),
+        ),
+    ];
+
+    Ok((headers, body))
+}
+
+async fn aangifte_for_quarter(
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    q: &Quarter,
+) -> anyhow::Result<Aangifte> {
+    let invoices: Vec<Factuur> = db::get_invoices(&mut *conn, tenant, db::InvoiceStatus::Active)
+        .await?
+        .into_iter()
+        .filter(|i| Quarter::of(&i.date) == *q)
+        .collect();
+    let expenses: Vec<Expense> = db::get_expenses(&mut *conn, tenant)
+        .await?
+        .into_iter()
+        .map(|(_, e, _)| e)
+        .filter(|e| Quarter::of(&e.date) == *q)
+        .collect();
+    let voorbelasting = db::get_voorbelasting(&mut *conn, tenant, q).await?;
+
+    Ok(Aangifte::new(&invoices, &expenses, voorbelasting))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoorbelastingForm {
+    jaar: i32,
+    kwartaal: u32,
+    bedrag: String,
+}
+
+pub async fn voorbelasting_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<VoorbelastingForm>,
+) -> impl IntoResponse {
+    let bedrag = match form.bedrag.parse::<Money>() {
+        Ok(bedrag) => bedrag,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("{err}"))),
+    };
+    let q = match Quarter::new(form.jaar, form.kwartaal) {
+        Ok(q) => q,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat kwartaal klopt niet: {}", err),
+            ))
         }
     };
 
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::set_voorbelasting(&mut conn, user.tenant, &q, bedrag).await {
+        Ok(_) => Ok(Redirect::to("/btw")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van de voorbelasting. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+pub async fn deleted_invoices(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> DeletedInvoicesTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let mut invoices =
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Deleted).await {
+            Ok(invoices) => invoices,
+            Err(err) => {
+                println!("Failed to fetch deleted invoices from DB: {:?}", err);
+                vec![]
+            }
+        };
+
     invoices.sort_by_key(|i| i.nummer);
     invoices.reverse();
 
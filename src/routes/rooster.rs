@@ -0,0 +1,294 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::IsoWeek;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sqlx::SqliteConnection;
+
+use crate::{
+    client::ClientDetails,
+    db, factuur, l1nda,
+    rate::Rates,
+    schedule::{self, ScheduleSource, Shifts, SourceKind},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+use super::factuur::{empty_string_as_none, next_kenmerk, FactuurTemplate};
+
+#[derive(Template)]
+#[template(path = "rooster.html")]
+pub struct RoosterTemplate {
+    page: Page,
+    bronnen: Vec<ScheduleSource>,
+    klanten: Vec<ClientDetails>,
+    /// The schedule that is selected when the page opens
+    gekozen: Option<i64>,
+}
+
+impl RoosterTemplate {
+    fn klant(&self, source: &ScheduleSource) -> &str {
+        match source.client {
+            None => "Geen klant",
+            Some(id) => self
+                .klanten
+                .iter()
+                .find(|k| k.id == id)
+                .map_or("Onbekende klant", |k| k.name.as_str()),
+        }
+    }
+
+    fn is_gekozen(&self, source: &ScheduleSource) -> bool {
+        self.gekozen == Some(source.id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoosterParams {
+    bron: Option<i64>,
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<RoosterParams>,
+) -> RoosterTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let bronnen = match db::get_schedule_sources(&mut conn, user.tenant).await {
+        Ok(bronnen) => bronnen,
+        Err(err) => {
+            println!("Failed to fetch schedule sources from DB: {:?}", err);
+            vec![]
+        }
+    };
+    let klanten = db::get_client_details(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default();
+
+    RoosterTemplate {
+        page: Page::Rooster,
+        bronnen,
+        klanten,
+        gekozen: params.bron,
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoosterForm {
+    bron: i64,
+    /// Of the form YYYY-MM
+    maand: String,
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<RoosterForm>,
+) -> impl IntoResponse {
+    let (from, until) = match schedule::month(&form.maand) {
+        Ok(period) => period,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, die maand klopt niet: {}", err),
+            ))
+        }
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    let bron = match db::get_schedule_source(&mut conn, user.tenant, form.bron).await {
+        Ok(bron) => bron,
+        Err(err) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Hey, dit rooster kunnen we niet vinden: {}", err),
+            ))
+        }
+    };
+
+    let klant = match bron.client {
+        Some(id) => db::get_client_by_id(&mut conn, user.tenant, id).await.ok(),
+        None => None,
+    };
+    let shifts = match bron.schedule(user.tenant) {
+        Ok(schedule) => schedule.shifts(from, until).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = &shifts {
+        println!("Failed to fetch data from {}: {}", bron.kind, err);
+    }
+
+    Ok(factuur_met_diensten(&state, &mut conn, user.tenant, klant, shifts).await)
+}
+
+/// A new invoice to `klant` with a line per shift, priced by the rates of
+/// the tenant. Shifts that are missing are mentioned above the invoice.
+pub(crate) async fn factuur_met_diensten(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    tenant: i64,
+    klant: Option<ClientDetails>,
+    shifts: anyhow::Result<Shifts>,
+) -> FactuurTemplate {
+    let rates = match db::get_rates(&mut *conn, tenant).await {
+        Ok(rates) => rates,
+        Err(err) => {
+            println!("Failed to fetch rates from DB: {:?}", err);
+            vec![]
+        }
+    };
+    let rates = Rates::new(rates, klant.as_ref());
+
+    let (items, waarschuwing) = match shifts {
+        Ok(shifts) => (
+            shifts
+                .events
+                .into_iter()
+                .filter_map(|e| factuur::WorkItem::from_event(e, &rates).ok())
+                .collect::<Vec<factuur::WorkItem>>(),
+            missing(&shifts.missing_weeks, &shifts.skipped),
+        ),
+        Err(err) => (
+            vec![],
+            Some(format!("Hey, het rooster kon niet opgehaald worden: {err}")),
+        ),
+    };
+
+    let volgende = next_kenmerk(&mut *conn, state, tenant).await;
+
+    FactuurTemplate {
+        page: Page::Factuur,
+        client: klant.map(|k| k.client()),
+        items,
+        volgende,
+        credit_for: None,
+        concept: None,
+        waarschuwing,
+    }
+}
+
+fn missing(weeks: &[IsoWeek], skipped: &[String]) -> Option<String> {
+    let mut missing = vec![];
+    if !weeks.is_empty() {
+        let weeks = weeks
+            .iter()
+            .map(|w| format!("week {} van {}", w.week(), w.year()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        missing.push(format!(
+            "Hey, niet alle weken konden uit het rooster gehaald worden. \
+            Vul de diensten van {weeks} zelf aan."
+        ));
+    }
+    if !skipped.is_empty() {
+        missing.push(format!(
+            "Hey, deze afspraken herhalen op een manier of staan in een tijdzone \
+            die we niet kunnen lezen. Vul ze zelf aan: {}.",
+            skipped.join(", ")
+        ));
+    }
+
+    (!missing.is_empty()).then(|| missing.join(" "))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BronForm {
+    name: String,
+    kind: String,
+    subdomain: String,
+    employee: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    client: Option<i64>,
+}
+
+pub async fn bron_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<BronForm>,
+) -> impl IntoResponse {
+    let kind: SourceKind = match form.kind.parse() {
+        Ok(kind) => kind,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, format!("Hey, {}", err))),
+    };
+    let bron = ScheduleSource {
+        id: 0,
+        name: form.name.trim().to_owned(),
+        kind,
+        subdomain: form.subdomain.trim().to_lowercase(),
+        employee: form.employee.trim().to_owned(),
+        client: form.client,
+    };
+    if bron.name.is_empty() || bron.subdomain.is_empty() || bron.employee.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, een rooster heeft een naam, subdomein en medewerker nodig.".to_owned(),
+        ));
+    }
+    if !l1nda::valid_subdomain(&bron.subdomain) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Hey, dat subdomein klopt niet: {}. Het is het eerste stuk van \
+                het adres, zoals denieuweanita in denieuweanita.l1nda.nl.",
+                bron.subdomain
+            ),
+        ));
+    }
+
+    let mut conn = state.db.acquire().await.unwrap();
+    // Schedules can only be linked to clients of the same tenant
+    let klant = match bron.client {
+        Some(id) => db::get_client_by_id(&mut conn, user.tenant, id)
+            .await
+            .map(|_| ()),
+        None => Ok(()),
+    };
+    if let Err(err) = klant {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Hey, deze klant kunnen we niet vinden: {}", err),
+        ));
+    }
+
+    match db::add_schedule_source(&mut conn, user.tenant, &bron).await {
+        Ok(_) => Ok(Redirect::to("/rooster")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van het rooster. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerwijderParams {
+    id: i64,
+}
+
+pub async fn bron_delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<VerwijderParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_schedule_source(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/rooster")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het verwijderen van het rooster. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
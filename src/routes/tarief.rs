@@ -0,0 +1,180 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    extract::{Extension, Query, State},
+    response::Redirect,
+};
+use axum_extra::extract::Form;
+use chrono::NaiveDate;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    client::ClientDetails,
+    db,
+    factuur::DEFAULT_HOURLY_RATE,
+    money::Money,
+    rate::{Rate, EVENING_END, EVENING_START},
+    server::AppState,
+    user::User,
+    Page,
+};
+
+use super::factuur::empty_string_as_none;
+
+#[derive(Template)]
+#[template(path = "tarieven.html")]
+pub struct TarievenTemplate {
+    page: Page,
+    tarieven: Vec<Rate>,
+    klanten: Vec<ClientDetails>,
+}
+
+impl TarievenTemplate {
+    fn klant(&self, rate: &Rate) -> &str {
+        match rate.client {
+            None => "Alle klanten",
+            Some(id) => self
+                .klanten
+                .iter()
+                .find(|k| k.id == id)
+                .map_or("Onbekende klant", |k| k.name.as_str()),
+        }
+    }
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> TarievenTemplate {
+    let mut conn = state.db.acquire().await.unwrap();
+    let tarieven = match db::get_rates(&mut conn, user.tenant).await {
+        Ok(tarieven) => tarieven,
+        Err(err) => {
+            println!("Failed to fetch rates from DB: {:?}", err);
+            vec![]
+        }
+    };
+    let klanten = db::get_client_details(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default();
+
+    TarievenTemplate {
+        page: Page::Klanten,
+        tarieven,
+        klanten,
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TariefForm {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    client: Option<i64>,
+    event_type: String,
+    valid_from: NaiveDate,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    valid_until: Option<NaiveDate>,
+    hourly_rate: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    evening_surcharge: Option<i64>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    weekend_surcharge: Option<i64>,
+}
+
+pub async fn post(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Form(form): Form<TariefForm>,
+) -> impl IntoResponse {
+    let hourly_rate: Money = match form.hourly_rate.parse() {
+        Ok(rate) => rate,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Hey, dat uurtarief klopt niet: {}", err),
+            ))
+        }
+    };
+    if form.evening_surcharge.is_some_and(|s| s < 0)
+        || form.weekend_surcharge.is_some_and(|s| s < 0)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, een toeslag kan niet negatief zijn.".to_owned(),
+        ));
+    }
+    if form
+        .valid_until
+        .is_some_and(|until| until < form.valid_from)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Hey, het tarief stopt voordat het begint.".to_owned(),
+        ));
+    }
+
+    let rate = Rate {
+        id: 0,
+        client: form.client,
+        event_type: match form.event_type.trim() {
+            "" => None,
+            event_type => Some(event_type.to_owned()),
+        },
+        valid_from: form.valid_from,
+        valid_until: form.valid_until,
+        hourly_rate,
+        evening_surcharge: form.evening_surcharge.unwrap_or_default(),
+        weekend_surcharge: form.weekend_surcharge.unwrap_or_default(),
+    };
+
+    let mut conn = state.db.acquire().await.unwrap();
+    // Rates can only be for clients of the same tenant
+    let klant = match rate.client {
+        Some(id) => db::get_client_by_id(&mut conn, user.tenant, id)
+            .await
+            .map(|_| ()),
+        None => Ok(()),
+    };
+    if let Err(err) = klant {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Hey, deze klant kunnen we niet vinden: {}", err),
+        ));
+    }
+
+    match db::add_rate(&mut conn, user.tenant, &rate).await {
+        Ok(_) => Ok(Redirect::to("/tarieven")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het opslaan van het tarief. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerwijderParams {
+    id: i64,
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<VerwijderParams>,
+) -> impl IntoResponse {
+    let mut conn = state.db.acquire().await.unwrap();
+    match db::delete_rate(&mut conn, user.tenant, params.id).await {
+        Ok(_) => Ok(Redirect::to("/tarieven")),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Hey, foutje tijdens het verwijderen van het tarief. \
+                Laat dit even zien aan Max:\n\n {}",
+                err
+            ),
+        )),
+    }
+}
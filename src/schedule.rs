@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{IsoWeek, Months, NaiveDate};
+
+use crate::event::Event;
+use crate::l1nda::L1nda;
+
+/// A rostering tool that the shifts of an employee come from.
+#[async_trait]
+pub trait Schedule: Send + Sync {
+    /// The shifts from `from` up to and including `until`.
+    async fn shifts(&self, from: NaiveDate, until: NaiveDate) -> Result<Shifts>;
+}
+
+/// The shifts of one employee in a period.
+#[derive(Clone, Debug)]
+pub struct Shifts {
+    pub events: Vec<Event>,
+    /// Weeks that could not be fetched, whose shifts have to be added by hand
+    pub missing_weeks: Vec<IsoWeek>,
+    /// Work that could not be read, like events that repeat in a way we can't
+    /// follow or are in a time zone we don't know, to be added by hand
+    pub skipped: Vec<String>,
+}
+
+/// The rostering tools that we can import shifts from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    L1nda,
+}
+
+impl SourceKind {
+    pub const ALL: [SourceKind; 1] = [SourceKind::L1nda];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SourceKind::L1nda => "l1nda",
+        }
+    }
+}
+
+impl Display for SourceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            SourceKind::L1nda => "L1NDA",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for SourceKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SourceKind::ALL
+            .into_iter()
+            .find(|k| k.key() == s)
+            .ok_or_else(|| anyhow!("unknown schedule source: {s}"))
+    }
+}
+
+/// The schedule of an employer, as set on the Rooster page.
+#[derive(Clone, Debug)]
+pub struct ScheduleSource {
+    pub id: i64,
+    /// Like `De Nieuwe Anita`
+    pub name: String,
+    pub kind: SourceKind,
+    /// Like `denieuweanita` for `denieuweanita.l1nda.nl`
+    pub subdomain: String,
+    /// The name of the employee in the schedule
+    pub employee: String,
+    /// The client that the shifts are invoiced to
+    pub client: Option<i64>,
+}
+
+impl ScheduleSource {
+    /// The schedule, read with the session of `tenant`.
+    pub fn schedule(&self, tenant: i64) -> Result<Box<dyn Schedule>> {
+        Ok(match self.kind {
+            SourceKind::L1nda => Box::new(L1nda::new(tenant, &self.subdomain, &self.employee)?),
+        })
+    }
+}
+
+/// The first and last day of a month of the form `YYYY-MM`.
+pub fn month(s: &str) -> Result<(NaiveDate, NaiveDate)> {
+    let invalid = || anyhow!("invalid month {s:?}: should be of form YYYY-MM");
+    let (year, month) = s.split_once('-').ok_or_else(invalid)?;
+    let first = NaiveDate::from_ymd_opt(
+        year.parse().map_err(|_| invalid())?,
+        month.parse().map_err(|_| invalid())?,
+        1,
+    )
+    .ok_or_else(invalid)?;
+    let last = (first + Months::new(1)).pred_opt().ok_or_else(invalid)?;
+
+    Ok((first, last))
+}
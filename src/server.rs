@@ -1,7 +1,15 @@
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::money::Money;
+use crate::nummering::Nummering;
+use crate::render::{self, PdfRenderer};
+use crate::routes::factuur::Voorbeeld;
+use crate::schedule::ScheduleSource;
+use crate::user::{Role, User, Users};
 use crate::{Page, factuur};
 use crate::{db, routes};
 
@@ -9,26 +17,26 @@ use anyhow::Result;
 use askama::Template;
 
 use axum::{
-    Router, Server,
-    extract::State,
+    Extension, Router, Server,
+    extract::{DefaultBodyLimit, State},
     routing::{get, post},
 };
 use axum_login::axum_sessions::SessionLayer;
 use axum_login::axum_sessions::async_session::MemoryStore as SessionMemoryStore;
-use axum_login::memory_store::MemoryStore as AuthMemoryStore;
-use axum_login::secrecy::SecretVec;
-use axum_login::{AuthLayer, AuthUser, RequireAuthorizationLayer};
+use axum_login::{AuthLayer, RequireAuthorizationLayer};
 
 use chrono::prelude::*;
 use rand::Rng;
 
-use sqlx::sqlite::SqlitePool;
+use sqlx::SqliteConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
 use tokio::sync::RwLock;
 
 pub mod filters {
     use chrono::NaiveDateTime;
 
     use crate::factuur::Factuur;
+    use crate::money::Money;
 
     pub fn date<T: std::fmt::Display>(s: T) -> ::askama::Result<String> {
         // Entries ingested from Rust are explicitly set to RFC 3339 format
@@ -45,7 +53,7 @@ pub mod filters {
         }
     }
 
-    pub fn sum_invoices(invoices: &[Factuur]) -> ::askama::Result<f64> {
+    pub fn sum_invoices(invoices: &[Factuur]) -> ::askama::Result<Money> {
         Ok(invoices.iter().map(|i| i.subtotal).sum())
     }
 }
@@ -53,43 +61,86 @@ pub mod filters {
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db: SqlitePool,
-    pub user: User,
+    /// For tenants that did not choose a numbering of their own
+    pub nummering: Nummering,
+    pub renderer: Arc<dyn PdfRenderer>,
+    /// Previewed invoices by id, until they are confirmed
+    pub voorbeelden: Arc<RwLock<HashMap<String, Voorbeeld>>>,
 }
 
-#[derive(Debug, Clone)]
-pub struct User {
-    pub id: usize,
-    pub password_hash: String,
+/// Opens the database and brings it up to date with the migrations.
+pub async fn connect() -> Result<SqlitePool> {
+    connect_to(Path::new("/data/facturen.db")).await
 }
 
-impl User {
-    fn new() -> Result<Self> {
-        let secret = env::var("USER_SECRET")?;
-        Ok(User {
-            id: 1,
-            password_hash: secret,
-        })
-    }
+/// Opens the database at `path`, creating it when it does not exist yet,
+/// and brings it up to date with the migrations.
+pub async fn connect_to(path: &Path) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    let db_pool = SqlitePool::connect_with(options).await?;
+    sqlx::migrate!().run(&db_pool).await?;
+    Ok(db_pool)
 }
 
-impl AuthUser<usize> for User {
-    fn get_id(&self) -> usize {
-        self.id
+/// Adds the owner of the first tenant with `password_hash`, unless that
+/// tenant has users already. It changes its password on the account page
+/// from then on.
+pub async fn add_first_user(
+    conn: &mut SqliteConnection,
+    username: &str,
+    password_hash: &str,
+) -> Result<()> {
+    if db::get_users(&mut *conn, 1).await?.is_empty() {
+        db::add_user(&mut *conn, 1, username, password_hash, Role::Eigenaar).await?;
     }
+    Ok(())
+}
 
-    fn get_password_hash(&self) -> SecretVec<u8> {
-        SecretVec::new(self.password_hash.clone().into())
+/// Drafts the recurring invoices that are due, and checks again every hour.
+async fn draft_recurring_invoices(db_pool: SqlitePool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+    loop {
+        interval.tick().await;
+        let today = Utc::now().date_naive();
+        let created = match db_pool.acquire().await {
+            Ok(mut conn) => db::create_due_drafts(&mut conn, today).await,
+            Err(err) => Err(err.into()),
+        };
+        match created {
+            Ok(0) => (),
+            Ok(n) => println!("Drafted {n} recurring invoice(s)"),
+            Err(err) => println!("Failed to draft recurring invoices: {err}"),
+        }
     }
 }
 
 pub async fn run() -> Result<()> {
-    let db_pool = SqlitePool::connect("/data/facturen.db?mode=rwc").await?;
-    sqlx::migrate!().run(&db_pool).await?;
+    let db_pool = connect().await?;
+    tokio::spawn(draft_recurring_invoices(db_pool.clone()));
+
+    {
+        let mut conn = db_pool.acquire().await?;
+        match (env::var("USER_NAME"), env::var("USER_SECRET")) {
+            (Ok(username), Ok(secret)) => {
+                add_first_user(&mut conn, username.trim(), &secret).await?;
+            }
+            _ if db::get_users(&mut conn, 1).await?.is_empty() => {
+                println!(
+                    "There are no users yet, set USER_NAME and USER_SECRET or add one \
+                    with `users add --tenant 1 <username>`"
+                );
+            }
+            _ => (),
+        }
+    }
 
-    let user = User::new()?;
     let state = AppState {
-        db: db_pool,
-        user: user.clone(),
+        db: db_pool.clone(),
+        nummering: Nummering::from_env()?,
+        renderer: render::from_env()?,
+        voorbeelden: Arc::default(),
     };
 
     let secret = rand::thread_rng().r#gen::<[u8; 64]>();
@@ -97,32 +148,106 @@ pub async fn run() -> Result<()> {
     let session_store = SessionMemoryStore::new();
     let session_layer = SessionLayer::new(session_store, &secret);
 
-    let store = Arc::new(RwLock::new(HashMap::default()));
+    let auth_layer = AuthLayer::new(Users::new(db_pool), &secret);
 
-    store.write().await.insert(user.get_id(), user);
-
-    let user_store = AuthMemoryStore::new(&store);
-    let auth_layer = AuthLayer::new(user_store, &secret);
-
-    let router = Router::new()
-        .route("/", get(root_get))
-        .route("/anita", get(routes::anita::get))
-        .route("/anita", post(routes::anita::post))
+    // Bookkeepers only read the reports and download the PDFs, while owners
+    // can also change things
+    let beheren = Router::new()
+        .route("/rooster", get(routes::rooster::get))
+        .route("/rooster", post(routes::rooster::post))
+        .route("/rooster/bron", post(routes::rooster::bron_post))
+        .route("/rooster/bron/verwijder", get(routes::rooster::bron_delete))
+        .route("/agenda", get(routes::agenda::get))
+        .route(
+            "/agenda",
+            post(routes::agenda::post)
+                .layer(DefaultBodyLimit::max(routes::agenda::MAX_CALENDAR_SIZE)),
+        )
         .route("/factuur", get(routes::factuur::get))
         .route("/factuur", post(routes::factuur::post))
-        .route("/download", get(routes::factuur::download))
+        .route("/factuur/voorbeeld", get(routes::factuur::voorbeeld_get))
+        .route("/factuur/voorbeeld", post(routes::factuur::voorbeeld_post))
+        .route("/factuur/bevestig", post(routes::factuur::bevestig))
+        .route("/concept", post(routes::factuur::concept_post))
+        .route("/concept/verwijder", get(routes::factuur::concept_delete))
+        .route("/herhalingen", get(routes::herhaling::get))
+        .route("/herhalingen", post(routes::herhaling::post))
+        .route("/herhalingen/verwijder", get(routes::herhaling::delete))
+        .route(
+            "/klanten/import",
+            post(routes::klant::import_post)
+                .layer(DefaultBodyLimit::max(routes::klant::MAX_IMPORT_SIZE)),
+        )
+        .route(
+            "/klanten/import/bevestig",
+            post(routes::klant::import_bevestig)
+                .layer(DefaultBodyLimit::max(routes::klant::MAX_IMPORT_SIZE)),
+        )
+        .route("/klant", get(routes::klant::get))
+        .route("/klant", post(routes::klant::post))
+        .route("/klant/archiveer", get(routes::klant::archive))
+        .route("/klant/herstel", get(routes::klant::restore))
+        .route("/klant/samenvoegen", post(routes::klant::merge))
+        .route("/instellingen", get(routes::instellingen::get))
+        .route("/instellingen", post(routes::instellingen::post))
         .route("/delete", get(routes::factuur::delete))
         .route("/restore", get(routes::factuur::restore))
+        .route("/betaling", post(routes::factuur::payment_post))
+        .route("/herinneringen", get(routes::herinnering::get))
+        .route("/herinneringen", post(routes::herinnering::post))
+        .route(
+            "/kosten",
+            post(routes::kosten::post)
+                .layer(DefaultBodyLimit::max(routes::kosten::MAX_RECEIPT_SIZE)),
+        )
+        .route("/kosten/delete", get(routes::kosten::delete))
+        .route("/balans/opening", post(routes::balans::opening_post))
+        .route(
+            "/bank",
+            post(routes::bank::post).layer(DefaultBodyLimit::max(routes::bank::MAX_STATEMENT_SIZE)),
+        )
+        .route("/bank/koppel", post(routes::bank::koppel_post))
+        .route("/bank/negeer", get(routes::bank::negeer))
+        .route(
+            "/btw/voorbelasting",
+            post(routes::report::voorbelasting_post),
+        )
+        .route("/tarieven", get(routes::tarief::get))
+        .route("/tarieven", post(routes::tarief::post))
+        .route("/tarieven/verwijder", get(routes::tarief::delete))
+        .route("/account/gebruiker", post(routes::account::user_post))
+        .route("/account/rol", post(routes::account::role_post))
+        .route("/account/verwijder", get(routes::account::delete))
+        .route_layer(
+            RequireAuthorizationLayer::<usize, User, Role>::login_with_role(Role::Eigenaar..),
+        );
+
+    let router = Router::new()
+        .route("/", get(root_get))
+        .route("/klanten", get(routes::klant::list))
+        .route("/download", get(routes::factuur::download))
+        .route("/bekijk", get(routes::factuur::bekijk))
         .route("/facturen", get(routes::report::history_get))
+        .route("/herinnering", get(routes::herinnering::download))
         .route(
             "/verwijderde_facturen",
             get(routes::report::deleted_invoices),
         )
+        .route("/kosten", get(routes::kosten::get))
+        .route("/kosten/bon", get(routes::kosten::receipt))
+        .route("/balans", get(routes::balans::get))
+        .route("/bank", get(routes::bank::get))
         .route("/btw", get(routes::report::btw_get))
-        .route_layer(RequireAuthorizationLayer::<usize, User>::login_or_redirect(
-            Arc::new("/login".into()),
-            None,
-        ))
+        .route("/btw/aangifte", get(routes::report::aangifte_export))
+        .route("/account", get(routes::account::get))
+        .route("/account/wachtwoord", post(routes::account::password_post))
+        .merge(beheren)
+        .route_layer(
+            RequireAuthorizationLayer::<usize, User, Role>::login_or_redirect(
+                Arc::new("/login".into()),
+                None,
+            ),
+        )
         .route("/login", get(routes::auth::login_get))
         .route("/login", post(routes::auth::login_post))
         .layer(auth_layer)
@@ -143,21 +268,47 @@ pub async fn run() -> Result<()> {
 struct PortaalTemplate {
     page: Page,
     clients: Vec<factuur::Client>,
-    omzet: f64,
+    omzet: Money,
+    openstaand: Money,
+    /// Number of overdue invoices that are due for a reminder
+    herinneringen: usize,
     laatste: Option<factuur::Factuur>,
+    concepten: Vec<factuur::Concept>,
+    /// Schedules that invoices are made from, instead of from their client
+    bronnen: Vec<ScheduleSource>,
+    /// Bookkeepers don't get the buttons to make invoices
+    role: Role,
 }
 
-async fn root_get(State(state): State<AppState>) -> PortaalTemplate {
+async fn root_get(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> PortaalTemplate {
     let mut conn = state.db.acquire().await.unwrap();
-    let clients = db::get_all_clients(&mut conn).await.unwrap_or_default();
+    let mut clients = db::get_all_clients(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default();
 
-    let mut invoices = match db::get_invoices(&mut conn, db::InvoiceStatus::Active).await {
-        Ok(invoices) => invoices,
-        Err(err) => {
-            println!("Failed to fetch invoices for dashboard: {err}");
-            vec![]
-        }
-    };
+    let bronnen = db::get_schedule_sources(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default();
+    let gekoppeld: Vec<String> = db::get_client_details(&mut conn, user.tenant)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| bronnen.iter().any(|b| b.client == Some(c.id)))
+        .map(|c| c.name)
+        .collect();
+    clients.retain(|c| !gekoppeld.contains(&c.name));
+
+    let mut invoices =
+        match db::get_invoices(&mut conn, user.tenant, db::InvoiceStatus::Active).await {
+            Ok(invoices) => invoices,
+            Err(err) => {
+                println!("Failed to fetch invoices for dashboard: {err}");
+                vec![]
+            }
+        };
 
     let now = Utc::now();
     let omzet = invoices
@@ -165,15 +316,36 @@ async fn root_get(State(state): State<AppState>) -> PortaalTemplate {
         .filter(|i| i.date.year() == now.year())
         .map(|i| i.subtotal)
         .sum();
+    let openstaand = invoices
+        .iter()
+        .map(|i| i.outstanding(now.date_naive()))
+        .sum();
+    let herinneringen = invoices
+        .iter()
+        .filter(|i| i.reminder_due(now.date_naive()).is_some())
+        .count();
 
     invoices.sort_by_key(|i| i.nummer);
     invoices.reverse();
     let laatste = invoices.first().cloned();
 
+    let concepten = match db::get_drafts(&mut conn, user.tenant).await {
+        Ok(concepten) => concepten,
+        Err(err) => {
+            println!("Failed to fetch drafts for dashboard: {err}");
+            vec![]
+        }
+    };
+
     PortaalTemplate {
         page: Page::Dashboard,
         clients,
         omzet,
+        openstaand,
+        herinneringen,
         laatste,
+        concepten,
+        bronnen,
+        role: user.role,
     }
 }
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::factuur::Factuur;
+use crate::nummering::Nummering;
+
+/// Who sends the invoices and how they look, as set on the settings page.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Settings {
+    pub business_name: String,
+    /// Signs the invoices
+    pub owner_name: String,
+    pub email: String,
+    pub btw_number: String,
+    pub kvk_number: String,
+    /// Name on the bank account that invoices are paid to
+    pub account_holder: String,
+    pub iban: String,
+    /// Where the invoices are dated
+    pub city: String,
+    /// In days, for clients without a payment term of their own
+    pub payment_term: u64,
+    /// Asks for payment, where `{dagen}` is the payment term of the invoice
+    pub closing_note: String,
+    pub greeting: String,
+    pub serif_font: String,
+    pub sans_font: String,
+    pub font_size: String,
+    /// Page margins as for the LaTeX geometry package, e.g. `left=43mm`
+    pub geometry: String,
+    /// How invoice numbers are printed, or empty for the numbering of the
+    /// deployment
+    pub nummering: String,
+}
+
+impl Settings {
+    /// The details that every invoice needs and that are not filled in yet,
+    /// as named on the settings page. BTW and KvK numbers are optional.
+    pub fn missing_details(&self) -> Vec<&'static str> {
+        [
+            ("Bedrijfsnaam", &self.business_name),
+            ("Naam onder de factuur", &self.owner_name),
+            ("E-mail", &self.email),
+            ("Plaats", &self.city),
+            ("Ten name van", &self.account_holder),
+            ("IBAN", &self.iban),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// The lines under the business name at the top of an invoice.
+    pub fn from_lines(&self) -> Vec<String> {
+        [
+            self.email.clone(),
+            prefixed("BTW", &self.btw_number),
+            prefixed("KVK", &self.kvk_number),
+        ]
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .collect()
+    }
+
+    /// The closing note of `factuur`, with its payment term filled in.
+    pub fn closing_note(&self, factuur: &Factuur) -> String {
+        self.closing_note
+            .trim()
+            .replace("\r\n", "\n")
+            .replace("{dagen}", &factuur.payment_term.to_string())
+    }
+
+    /// The numbering of new invoices, which is `default` unless one was set.
+    pub fn nummering(&self, default: &Nummering) -> Result<Nummering> {
+        match self.nummering.trim() {
+            "" => Ok(default.clone()),
+            pattern => pattern.parse(),
+        }
+    }
+
+    /// A page margin from `geometry`, like `43mm` for `left`.
+    pub fn margin(&self, side: &str) -> Option<&str> {
+        self.geometry
+            .split(',')
+            .filter_map(|part| part.split_once('='))
+            .find(|(key, _)| key.trim() == side)
+            .map(|(_, value)| value.trim())
+    }
+}
+
+fn prefixed(prefix: &str, value: &str) -> String {
+    match value.trim() {
+        "" => String::new(),
+        value => format!("{prefix} {value}"),
+    }
+}
@@ -0,0 +1,7 @@
+/// One of the businesses that invoice from this deployment. Its users only
+/// ever see its own clients, invoices, expenses and settings.
+#[derive(Clone, Debug)]
+pub struct Tenant {
+    pub id: i64,
+    pub name: String,
+}
@@ -0,0 +1,127 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use async_trait::async_trait;
+use axum_login::{secrecy::SecretVec, AuthUser, UserStore};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::db;
+
+/// Someone who can log in, to the data of their tenant only.
+#[derive(Debug, Clone)]
+pub struct User {
+    pub id: usize,
+    pub tenant: i64,
+    pub username: String,
+    /// Argon2 PHC string
+    pub password_hash: String,
+    pub role: Role,
+}
+
+/// What a user may do. Roles are ordered, so that an owner can also do
+/// everything that a bookkeeper can.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Reads the reports and downloads the PDFs, without changing anything
+    Boekhouder,
+    Eigenaar,
+}
+
+impl Role {
+    pub const ALL: [Role; 2] = [Role::Eigenaar, Role::Boekhouder];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Boekhouder => "boekhouder",
+            Role::Eigenaar => "eigenaar",
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Role::Boekhouder => "boekhouder, alleen lezen",
+            Role::Eigenaar => "eigenaar",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|r| r.key() == s)
+            .ok_or_else(|| anyhow!("unknown role: {s}"))
+    }
+}
+
+impl User {
+    pub fn verify_password(&self, password: &str) -> Result<()> {
+        let parsed_hash = PasswordHash::new(&self.password_hash)
+            .map_err(|err| anyhow!("Failed to parse password hash of {}: {err}", self.username))?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .map_err(|err| anyhow!("Wrong password for {}: {err}", self.username))
+    }
+}
+
+/// The Argon2 PHC string to store for `password`.
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!("Failed to hash password: {err}"))?;
+    Ok(hash.to_string())
+}
+
+impl AuthUser<usize, Role> for User {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn get_password_hash(&self) -> SecretVec<u8> {
+        SecretVec::new(self.password_hash.clone().into())
+    }
+
+    fn get_role(&self) -> Option<Role> {
+        Some(self.role)
+    }
+}
+
+/// Looks up the logged in user for every request, so that users who are
+/// added while the server runs can log in right away.
+#[derive(Debug, Clone)]
+pub struct Users {
+    db: SqlitePool,
+}
+
+impl Users {
+    pub fn new(db: SqlitePool) -> Self {
+        Users { db }
+    }
+}
+
+#[async_trait]
+impl UserStore<usize, Role> for Users {
+    type User = User;
+    type Error = io::Error;
+
+    async fn load_user(&self, user_id: &usize) -> Result<Option<User>, io::Error> {
+        let res = async {
+            let mut conn = self.db.acquire().await?;
+            db::get_user(&mut conn, *user_id as i64).await
+        };
+        res.await
+            .map_err(|err| io::Error::other(format!("{err:#}")))
+    }
+}
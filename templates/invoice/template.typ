@@ -0,0 +1,137 @@
+// Typst counterpart of template.tex, reading the same details.yml.
+
+#let data = yaml("details.yml")
+
+#let maanden = (
+  "januari", "februari", "maart", "april", "mei", "juni",
+  "juli", "augustus", "september", "oktober", "november", "december",
+)
+
+// Amounts are read from YAML as numbers, so 12.50 arrives as 12.5
+#let bedrag(x) = {
+  let cents = int(calc.round(float(x) * 100))
+  let sign = if cents < 0 { "-" } else { "" }
+  let rest = str(calc.rem(calc.abs(cents), 100))
+  let sep = if data.at("commasep", default: false) { "," } else { "." }
+  sign + str(calc.quo(calc.abs(cents), 100)) + sep + "00".slice(rest.len()) + rest
+}
+
+// The closing note is markdown: paragraphs separated by blank lines, and
+// lines ending in two spaces are hard line breaks.
+#let markdown(text) = {
+  for par in text.trim().split(regex("\n[ \t]*\n")) {
+    let lines = par.split("\n")
+    for (i, line) in lines.enumerate() {
+      line.trim()
+      if i + 1 < lines.len() {
+        if line.ends-with("  ") { linebreak() } else { " " }
+      }
+    }
+    parbreak()
+  }
+}
+
+#let geometry = (:)
+#for part in data.geometry.split(",") {
+  let kv = part.split("=")
+  if kv.len() == 2 {
+    geometry.insert(kv.at(0).trim(), eval(kv.at(1).trim()))
+  }
+}
+
+#let serif = (data.at("seriffont", default: "Liberation Serif"), "Linux Libertine")
+#let sans = (data.at("sansfont", default: "Liberation Sans"), "DejaVu Sans")
+#let size = eval(data.at("fontsize", default: "10pt"))
+
+#set document(
+  author: data.author,
+  title: [#data.author Factuur nr. #data.at("invoice-nr")],
+)
+#set page(
+  paper: "a4",
+  margin: (
+    left: geometry.at("left", default: 43mm),
+    right: geometry.at("right", default: 43mm),
+    top: geometry.at("top", default: 51mm),
+    bottom: geometry.at("bottom", default: 17mm),
+  ),
+)
+#set text(font: serif, size: size, lang: "nl", number-type: "old-style")
+#set par(justify: false)
+#show par: set block(spacing: 1em)
+
+#text(size: 0.9 * size, smallcaps(strong(data.author)))
+#for from in data.at("from", default: ()) [
+  #text(size: 0.9 * size)[• #smallcaps[#from]]
+]
+
+#v(1em)
+
+#text(font: sans, size: 0.9 * size)[
+  #for to in data.at("to", default: ()) [#to \ ]
+]
+
+#v(6em)
+
+#let today = datetime.today()
+#align(right, text(size: 0.9 * size)[
+  #data.city, #today.day() #maanden.at(today.month() - 1) #today.year()
+])
+
+#v(1em)
+
+#text(size: 1.44 * size, weight: "bold")[
+  #smallcaps[#data.at("title", default: "Factuur")] #smallcaps[\##data.at("invoice-nr")]
+]
+
+#let services = data.at("service", default: ())
+#let vat = data.at("vat", default: ())
+#set text(size: 0.8 * size)
+
+#table(
+  columns: (auto, 8.2cm, 1fr),
+  align: (left, left, right),
+  stroke: none,
+  inset: (x: 9pt / 2, y: 0.5em),
+  table.hline(stroke: (thickness: 0.4pt, dash: "dotted")),
+  strong[Pos.], strong[Omschrijving], strong[Prijzen in #data.currency],
+  table.hline(stroke: 0.4pt),
+  ..services
+    .enumerate()
+    .map(((i, service)) => (
+      [#(i + 1)],
+      [
+        #service.description
+        #if "details" in service {
+          set text(size: 0.7 * size)
+          list(..service.details.map(d => [#d]))
+        }
+      ],
+      bedrag(service.price),
+    ))
+    .flatten(),
+  table.hline(stroke: 0.4pt),
+  ..if vat.len() > 0 {
+    (
+      [], align(right)[Subtotaal:], bedrag(data.subtotal),
+      table.hline(start: 2, stroke: 0.4pt),
+      ..vat
+        .map(regel => (
+          [], align(right)[#regel.label:], bedrag(regel.amount),
+          table.hline(start: 2, stroke: 0.4pt),
+        ))
+        .flatten(),
+    )
+  },
+  [], align(right, strong[Totaal:]), strong(bedrag(data.total)),
+  table.hline(start: 2, stroke: 0.4pt),
+)
+
+#v(15mm)
+
+#set text(font: sans, size: 0.9 * size)
+#markdown(data.closingnote)
+
+#v(0.5em)
+
+#data.name
@@ -0,0 +1,126 @@
+use chrono::NaiveDate;
+
+use creatief_vakvrouw::aangifte::{Aangifte, Quarter};
+use creatief_vakvrouw::btw::BtwCategorie;
+use creatief_vakvrouw::expense::Expense;
+use creatief_vakvrouw::factuur::{Factuur, FactuurForm};
+use creatief_vakvrouw::money::Money;
+
+fn factuur(regels: &[(&str, &str)]) -> Factuur {
+    Factuur::try_from(FactuurForm {
+        client_name: "De Nieuwe Anita".to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: regels
+            .iter()
+            .map(|(btw, _)| format!("Werk {btw}"))
+            .collect(),
+        prices: regels.iter().map(|(_, prijs)| prijs.to_string()).collect(),
+        btw_categorieen: regels.iter().map(|(btw, _)| btw.to_string()).collect(),
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap()
+}
+
+fn expense(btw_categorie: BtwCategorie, excl: &str, btw: &str) -> Expense {
+    Expense {
+        date: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+        supplier: "Leverancier".to_owned(),
+        category: "Materiaal".to_owned(),
+        btw_categorie,
+        excl: excl.parse().unwrap(),
+        btw: btw.parse().unwrap(),
+    }
+}
+
+fn euros(euros: i64) -> Money {
+    Money::from_cents(euros * 100)
+}
+
+fn aangifte() -> Aangifte {
+    let invoices = [factuur(&[
+        ("hoog", "100,50"),
+        ("laag", "50,99"),
+        ("nul", "10,75"),
+        ("verlegd", "200,40"),
+        ("vrijgesteld", "30"),
+    ])];
+    let expenses = [
+        expense(BtwCategorie::Verlegd, "80,50", "0"),
+        expense(BtwCategorie::Hoog, "20", "4,20"),
+    ];
+
+    Aangifte::new(&invoices, &expenses, "0,33".parse().unwrap())
+}
+
+#[test]
+fn rubrieken() {
+    let aangifte = aangifte();
+
+    // 21% of 100,50 is 21,105 and 9% of 50,99 is 4,5891, both rounded down
+    // to whole euros
+    assert_eq!(aangifte.rubriek_1a.omzet, euros(100));
+    assert_eq!(aangifte.rubriek_1a.btw, euros(21));
+    assert_eq!(aangifte.rubriek_1b.omzet, euros(50));
+    assert_eq!(aangifte.rubriek_1b.btw, euros(4));
+    assert_eq!(aangifte.rubriek_1e.omzet, euros(10));
+    assert_eq!(aangifte.rubriek_1e.btw, Money::ZERO);
+    assert_eq!(aangifte.rubriek_3b.omzet, euros(200));
+    assert_eq!(aangifte.rubriek_3b.btw, Money::ZERO);
+    // Purchases with verlegde BTW, 21% of 80,50 is 16,905
+    assert_eq!(aangifte.rubriek_4b.omzet, euros(80));
+    assert_eq!(aangifte.rubriek_4b.btw, euros(16));
+
+    // Vrijgestelde omzet is not in any rubriek
+    let omzet: Money = aangifte.rubrieken().iter().map(|(_, _, r)| r.omzet).sum();
+    assert_eq!(omzet, euros(100 + 50 + 10 + 200 + 80));
+}
+
+#[test]
+fn totalen() {
+    let aangifte = aangifte();
+
+    assert_eq!(aangifte.rubriek_5a, euros(21 + 4 + 16));
+    // The verlegde 16,91 plus 4,20 and 0,33 by hand, rounded up
+    assert_eq!(aangifte.rubriek_5b, euros(22));
+    assert_eq!(aangifte.rubriek_5c, euros(19));
+    assert_eq!(aangifte.rubriek_5g, euros(19));
+    assert!(aangifte.te_betalen());
+}
+
+#[test]
+fn csv() {
+    let csv = aangifte().to_csv();
+
+    assert!(csv.starts_with("rubriek;omschrijving;omzet;btw\n"));
+    assert!(csv.contains("\n1a;Leveringen/diensten belast met hoog tarief;100,00;21,00\n"));
+    assert!(
+        csv.contains(
+            "\n1c;Leveringen/diensten belast met overige tarieven, behalve 0%;0,00;0,00\n"
+        )
+    );
+    assert!(csv.contains("\n4b;Leveringen/diensten uit landen binnen de EU;80,00;16,00\n"));
+    assert!(csv.contains("\n5b;Voorbelasting;;22,00\n"));
+    assert!(csv.ends_with("\n5g;Totaal te betalen of terug te vragen;;19,00\n"));
+    assert!(!csv.contains('.'));
+}
+
+#[test]
+fn kwartalen() {
+    let q4 = Quarter::new(2026, 4).unwrap();
+    assert_eq!(
+        q4.first_day(),
+        NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()
+    );
+    assert_eq!(
+        q4.last_day(),
+        NaiveDate::from_ymd_opt(2026, 12, 31).unwrap()
+    );
+    assert_eq!(q4.deadline(), NaiveDate::from_ymd_opt(2027, 1, 31).unwrap());
+    assert_eq!(Quarter::of(&q4.last_day()), q4);
+
+    for kwartaal in [0, 5, 13] {
+        assert!(Quarter::new(2026, kwartaal).is_err(), "{kwartaal}");
+    }
+}
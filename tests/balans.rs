@@ -0,0 +1,95 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use creatief_vakvrouw::aangifte::Quarter;
+use creatief_vakvrouw::btw::BtwCategorie;
+use creatief_vakvrouw::expense::Expense;
+use creatief_vakvrouw::factuur::{Factuur, FactuurForm};
+use creatief_vakvrouw::ledger::{Grootboek, OpeningBalance};
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::payment::{Payment, PaymentMethod};
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn euros(s: &str) -> Money {
+    s.parse().unwrap()
+}
+
+/// A year that starts with money in the bank, an invoice from last year
+/// that is still to be paid and the BTW of the last quarter.
+fn grootboek() -> Grootboek {
+    let mut grootboek = Grootboek::new(OpeningBalance {
+        year: 2026,
+        bank: euros("1000"),
+        debiteuren: euros("242"),
+        btw: euros("42"),
+    });
+
+    // The invoice from last year is paid
+    grootboek.betaling(&Payment {
+        date: date("2026-01-20"),
+        amount: euros("242"),
+        method: PaymentMethod::Overboeking,
+    });
+
+    let mut factuur = Factuur::try_from(FactuurForm {
+        client_name: "De Nieuwe Anita".to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: vec!["Bar 3 februari".to_owned()],
+        prices: vec!["100".to_owned()],
+        btw_categorieen: vec!["hoog".to_owned()],
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap();
+    factuur.date = Utc.with_ymd_and_hms(2026, 2, 10, 12, 0, 0).unwrap();
+    grootboek.factuur(&factuur);
+
+    grootboek.kosten(&Expense {
+        date: date("2026-02-15"),
+        supplier: "Boekhandel".to_owned(),
+        category: "Boeken".to_owned(),
+        btw_categorie: BtwCategorie::Laag,
+        excl: euros("10"),
+        btw: euros("2.10"),
+    });
+
+    grootboek
+}
+
+#[test]
+fn opening_balance() {
+    let balans = grootboek().balans(date("2026-01-01"));
+
+    assert_eq!(balans.bank, euros("1000"));
+    assert_eq!(balans.debiteuren, euros("242"));
+    assert_eq!(balans.btw, euros("42"));
+    assert_eq!(balans.opening_eigen_vermogen, euros("1200"));
+    assert_eq!(balans.resultaat, Money::ZERO);
+    assert_eq!(balans.activa(), balans.passiva());
+}
+
+#[test]
+fn balance_after_the_opening() {
+    let balans = grootboek().balans(date("2026-03-01"));
+
+    // The BTW of the last quarter was paid at the end of January
+    assert_eq!(balans.bank, euros("1187.90"));
+    assert_eq!(balans.debiteuren, euros("121"));
+    assert_eq!(balans.btw, euros("18.90"));
+    assert_eq!(balans.opening_eigen_vermogen, euros("1200"));
+    assert_eq!(balans.resultaat, euros("90"));
+    assert_eq!(balans.eigen_vermogen(), euros("1290"));
+    assert_eq!(balans.activa(), euros("1308.90"));
+    assert_eq!(balans.activa(), balans.passiva());
+
+    assert_eq!(
+        balans.btw_per_kwartaal,
+        vec![
+            (Quarter::new(2025, 4).unwrap(), euros("42"), true),
+            (Quarter::new(2026, 1).unwrap(), euros("18.90"), false),
+        ]
+    );
+}
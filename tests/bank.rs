@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use creatief_vakvrouw::bank::{self, Transaction};
+use creatief_vakvrouw::factuur::{Factuur, FactuurForm};
+use creatief_vakvrouw::money::Money;
+
+fn afschrift(name: &str) -> Vec<Transaction> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bank")
+        .join(name);
+    bank::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn euros(s: &str) -> Money {
+    s.parse().unwrap()
+}
+
+/// An invoice for `price` including BTW, which is 0% so that the total is
+/// the price.
+fn factuur(kenmerk: &str, client: &str, price: &str) -> Factuur {
+    let mut factuur = Factuur::try_from(FactuurForm {
+        client_name: client.to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: vec!["Bar".to_owned()],
+        prices: vec![price.to_owned()],
+        btw_categorieen: vec!["nul".to_owned()],
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap();
+    factuur.kenmerk = kenmerk.to_owned();
+    factuur
+}
+
+fn overboeking(counterparty: &str, amount: &str, description: &str) -> Transaction {
+    Transaction {
+        date: date("2026-10-14"),
+        amount: euros(amount),
+        counterparty: counterparty.to_owned(),
+        iban: "NL91ABNA0417164300".to_owned(),
+        description: description.to_owned(),
+    }
+}
+
+#[test]
+fn camt053() {
+    let transactions = afschrift("camt053.xml");
+    assert_eq!(transactions.len(), 4);
+
+    let anita = &transactions[0];
+    assert_eq!(anita.date, date("2026-10-14"));
+    assert_eq!(anita.amount, euros("121"));
+    assert_eq!(anita.counterparty, "V.O.F. De Nieuwe Anita");
+    assert_eq!(anita.iban, "NL91ABNA0417164300");
+    assert_eq!(anita.description, "Factuur 2026-0042");
+
+    // Money going out is negative, and paid to the creditor
+    let boeken = &transactions[1];
+    assert_eq!(boeken.date, date("2026-10-15"));
+    assert_eq!(boeken.amount, euros("-24.95"));
+    assert!(!boeken.is_credit());
+    assert_eq!(boeken.counterparty, "Boekhandel Van Rossum");
+    assert_eq!(boeken.iban, "NL02RABO0123456789");
+    assert_eq!(boeken.description, "Pinbetaling boeken");
+    assert_eq!(boeken.expense_suggestion().incl, "24.95");
+
+    // The batch is split into its transactions
+    let batch: Vec<(&str, Money)> = transactions[2..]
+        .iter()
+        .map(|t| (t.counterparty.as_str(), t.amount))
+        .collect();
+    assert_eq!(
+        batch,
+        vec![
+            ("Theater Bellevue", euros("100")),
+            ("Paradiso", euros("250"))
+        ]
+    );
+    assert_eq!(transactions[3].description, "Decor oktober");
+}
+
+#[test]
+fn ing_csv() {
+    let komma = afschrift("ing_komma.csv");
+    let puntkomma = afschrift("ing_puntkomma.csv");
+
+    for transactions in [&komma, &puntkomma] {
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].date, date("2026-10-14"));
+        assert_eq!(transactions[0].amount, euros("121"));
+        assert_eq!(transactions[0].counterparty, "V.O.F. De Nieuwe Anita");
+        assert_eq!(transactions[0].iban, "NL91ABNA0417164300");
+        assert!(transactions[0].description.contains("Factuur 2026-0042"));
+
+        assert_eq!(transactions[1].amount, euros("-24.95"));
+        assert_eq!(transactions[1].counterparty, "Boekhandel Van Rossum");
+    }
+    assert_eq!(komma[0].fingerprint(), puntkomma[0].fingerprint());
+
+    assert!(bank::parse("Datum,Bedrag\n20261014,121").is_err());
+}
+
+#[test]
+fn matched_by_kenmerk() {
+    let facturen = [
+        factuur("2026-0042", "V.O.F. De Nieuwe Anita", "121"),
+        factuur("2026-0043", "V.O.F. De Nieuwe Anita", "121"),
+    ];
+
+    // Both are for the client, but only one is mentioned
+    let betaling = overboeking("V.O.F. De Nieuwe Anita", "121", "Factuur 2026-0043");
+    assert_eq!(
+        betaling
+            .match_invoice(&facturen)
+            .map(|f| f.kenmerk.as_str()),
+        Some("2026-0043")
+    );
+
+    // A longer number is another invoice
+    let betaling = overboeking("Iemand", "121", "Factuur 2026-00421");
+    assert!(betaling.match_invoice(&facturen).is_none());
+    let betaling = overboeking("Iemand", "121", "factuur 2026-0042, dank!");
+    assert_eq!(
+        betaling
+            .match_invoice(&facturen)
+            .map(|f| f.kenmerk.as_str()),
+        Some("2026-0042")
+    );
+
+    // Only the outstanding amount counts
+    let betaling = overboeking("Iemand", "120", "Factuur 2026-0042");
+    assert!(betaling.match_invoice(&facturen).is_none());
+}
+
+#[test]
+fn plain_numbers_are_matched_on_their_own() {
+    let facturen = [factuur("42", "Paradiso", "250")];
+
+    let betaling = overboeking("Iemand", "250", "factuur 0042");
+    assert!(betaling.match_invoice(&facturen).is_some());
+    for omschrijving in ["factuur 421", "betaling 142", "bedrag 1.420,00"] {
+        let betaling = overboeking("Iemand", "250", omschrijving);
+        assert!(
+            betaling.match_invoice(&facturen).is_none(),
+            "{omschrijving}"
+        );
+    }
+}
+
+#[test]
+fn matched_by_client_name() {
+    let facturen = [
+        factuur("2026-0042", "V.O.F. De Nieuwe Anita", "121"),
+        factuur("2026-0043", "Paradiso", "250"),
+    ];
+
+    let betaling = overboeking("STICHTING PARADISO", "250", "Decor oktober");
+    assert_eq!(
+        betaling
+            .match_invoice(&facturen)
+            .map(|f| f.kenmerk.as_str()),
+        Some("2026-0043")
+    );
+
+    // Two open invoices of the client for the same amount can't be told apart
+    let facturen = [
+        factuur("2026-0042", "Paradiso", "250"),
+        factuur("2026-0043", "Paradiso", "250"),
+    ];
+    assert!(betaling.match_invoice(&facturen).is_none());
+
+    // Money going out never pays an invoice
+    let terugbetaling = overboeking("Paradiso", "-250", "Factuur 2026-0042");
+    assert!(terugbetaling.match_invoice(&facturen).is_none());
+}
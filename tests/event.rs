@@ -0,0 +1,100 @@
+use creatief_vakvrouw::event::Event;
+use creatief_vakvrouw::factuur::WorkItem;
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::rate::Rates;
+
+fn shift(start: &str, end: &str) -> Event {
+    Event {
+        person: "Noemi".to_owned(),
+        event_type: "Bar".to_owned(),
+        date: start[..10].to_owned(),
+        start_to_end: format!("{} - {}", &start[11..16], &end[11..16]),
+        start: start.to_owned(),
+        end: end.to_owned(),
+    }
+}
+
+fn minutes(start: &str, end: &str) -> i64 {
+    shift(start, end).minutes().unwrap()
+}
+
+#[test]
+fn shifts_in_winter_and_summer_time() {
+    assert_eq!(minutes("2026-01-15T17:00:00", "2026-01-15T23:00:00"), 360);
+    assert_eq!(minutes("2026-07-15T17:00:00", "2026-07-15T23:30:00"), 390);
+}
+
+#[test]
+fn shifts_crossing_midnight() {
+    assert_eq!(minutes("2026-10-02T22:00:00", "2026-10-03T02:00:00"), 240);
+    // L1NDA can give the end on the day of the start
+    assert_eq!(minutes("2026-10-02T22:00:00", "2026-10-02T02:00:00"), 240);
+    assert_eq!(minutes("2026-12-31T20:00:00", "2026-12-31T01:30:00"), 330);
+}
+
+#[test]
+fn the_night_that_summer_time_starts() {
+    // On Sunday the 29th of March 2026 the clock jumps from 02:00 to 03:00
+    assert_eq!(
+        minutes("2026-03-28T22:00:00", "2026-03-29T06:00:00"),
+        7 * 60
+    );
+    assert_eq!(
+        minutes("2026-03-28T23:00:00", "2026-03-28T04:00:00"),
+        4 * 60
+    );
+    assert_eq!(minutes("2026-03-29T01:00:00", "2026-03-29T03:00:00"), 60);
+    // A time in the hour that is skipped is read as winter time
+    assert_eq!(minutes("2026-03-29T01:00:00", "2026-03-29T02:30:00"), 90);
+
+    // The week before and the day after have their full hours
+    assert_eq!(
+        minutes("2026-03-21T22:00:00", "2026-03-22T06:00:00"),
+        8 * 60
+    );
+    assert_eq!(
+        minutes("2026-03-29T22:00:00", "2026-03-30T06:00:00"),
+        8 * 60
+    );
+}
+
+#[test]
+fn the_night_that_summer_time_ends() {
+    // On Sunday the 25th of October 2026 the clock goes back from 03:00 to 02:00
+    assert_eq!(
+        minutes("2026-10-24T22:00:00", "2026-10-25T06:00:00"),
+        9 * 60
+    );
+    assert_eq!(
+        minutes("2026-10-24T23:00:00", "2026-10-24T04:00:00"),
+        6 * 60
+    );
+    assert_eq!(
+        minutes("2026-10-25T01:00:00", "2026-10-25T03:00:00"),
+        3 * 60
+    );
+    // A time in the hour that happens twice is read as the first one
+    assert_eq!(minutes("2026-10-25T01:00:00", "2026-10-25T02:30:00"), 90);
+
+    // In 2027 the last Sunday of October is the 31st, not the 24th
+    assert_eq!(
+        minutes("2027-10-23T22:00:00", "2027-10-24T06:00:00"),
+        8 * 60
+    );
+    assert_eq!(
+        minutes("2027-10-30T22:00:00", "2027-10-31T06:00:00"),
+        9 * 60
+    );
+}
+
+#[test]
+fn work_items_are_billed_by_the_hours_worked() {
+    let nacht = shift("2026-10-24T22:00:00", "2026-10-25T06:00:00");
+    let rates = Rates::new(vec![], None);
+    let item = WorkItem::from_event(nacht, &rates).unwrap();
+    assert_eq!(item.euro, Money::from_cents(9 * 2200));
+    assert_eq!(item.desc, "Bar 2026-10-24 (22:00 - 06:00)");
+
+    let kapot = shift("2026-10-24T22:00:00", "2026-10-25T06:00:00 uur");
+    assert!(WorkItem::from_event(kapot, &rates).is_err());
+}
@@ -0,0 +1,125 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use creatief_vakvrouw::btw::BtwCategorie;
+use creatief_vakvrouw::factuur::{Factuur, FactuurForm};
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::payment::{Payment, PaymentMethod, PaymentStatus};
+
+fn form(regels: &[(&str, &str)], btw_categorieen: &[&str]) -> FactuurForm {
+    FactuurForm {
+        client_name: "De Nieuwe Anita".to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: regels.iter().map(|(desc, _)| desc.to_string()).collect(),
+        prices: regels.iter().map(|(_, prijs)| prijs.to_string()).collect(),
+        btw_categorieen: btw_categorieen.iter().map(|btw| btw.to_string()).collect(),
+        credit_for: None,
+        concept: None,
+    }
+}
+
+#[test]
+fn empty_lines_are_left_out() {
+    let factuur = Factuur::try_from(form(
+        &[("Bar 3 oktober", "100"), ("", ""), ("Workshop", "")],
+        &["laag", "hoog", "hoog"],
+    ))
+    .unwrap();
+
+    assert_eq!(factuur.work_items.len(), 1);
+    assert_eq!(factuur.work_items[0].btw, BtwCategorie::Laag);
+    assert_eq!(factuur.total.cents(), 10900);
+}
+
+#[test]
+fn unreadable_lines_are_rejected() {
+    let prijs = Factuur::try_from(form(&[("Bar 3 oktober", "honderd")], &["hoog"]));
+    assert!(prijs.is_err());
+
+    // Rather than silently charging 21%
+    let onbekend = Factuur::try_from(form(&[("Bar 3 oktober", "100")], &["hoogste"]));
+    assert!(onbekend.is_err());
+    let leeg = Factuur::try_from(form(&[("Bar 3 oktober", "100")], &[""]));
+    assert!(leeg.is_err());
+    let ontbrekend = Factuur::try_from(form(&[("Bar 3 oktober", "100")], &[]));
+    assert!(ontbrekend.is_err());
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn euros(s: &str) -> Money {
+    s.parse().unwrap()
+}
+
+fn betaling(datum: &str, bedrag: &str) -> Payment {
+    Payment {
+        date: date(datum),
+        amount: euros(bedrag),
+        method: PaymentMethod::Overboeking,
+    }
+}
+
+/// An invoice of € 121 incl. BTW on the 1st of October, due on the 15th.
+fn bar() -> Factuur {
+    let mut factuur = Factuur::try_from(form(&[("Bar 3 oktober", "100")], &["hoog"])).unwrap();
+    factuur.nummer = 42;
+    factuur.kenmerk = "2026-0042".to_owned();
+    factuur.date = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+    factuur.payment_term = 14;
+    factuur
+}
+
+#[test]
+fn partially_paid() {
+    let mut factuur = bar();
+    factuur.payments.push(betaling("2026-10-05", "50"));
+
+    assert_eq!(factuur.status(date("2026-10-04")), PaymentStatus::Open);
+    assert_eq!(factuur.outstanding(date("2026-10-04")), euros("121"));
+    assert_eq!(
+        factuur.status(date("2026-10-05")),
+        PaymentStatus::PartiallyPaid
+    );
+    assert_eq!(factuur.outstanding(date("2026-10-05")), euros("71"));
+    assert_eq!(factuur.status(date("2026-10-16")), PaymentStatus::Overdue);
+
+    factuur.payments.push(betaling("2026-10-20", "71"));
+    assert_eq!(factuur.status(date("2026-10-20")), PaymentStatus::Paid);
+    assert_eq!(factuur.outstanding(date("2026-10-20")), Money::ZERO);
+}
+
+#[test]
+fn credit_note_on_a_paid_invoice() {
+    let mut factuur = bar();
+    factuur.payments.push(betaling("2026-10-05", "121"));
+
+    let credit_note = Factuur::try_from(form(&[("Bar 3 oktober", "-50")], &["hoog"])).unwrap();
+    assert_eq!(credit_note.total, euros("-60.50"));
+    let credit_for = factuur.check_credit_note(&credit_note).unwrap();
+    assert_eq!(credit_for.nummer, 42);
+    assert_eq!(credit_for.kenmerk, "2026-0042");
+
+    // What was paid too much is owed back to the client
+    factuur
+        .credits
+        .push((date("2026-10-10"), -credit_note.total));
+    assert_eq!(factuur.outstanding(date("2026-10-10")), euros("-60.50"));
+    assert_eq!(factuur.status(date("2026-10-10")), PaymentStatus::Paid);
+
+    // Only what is left of the invoice can still be credited
+    let te_veel = Factuur::try_from(form(&[("Bar 3 oktober", "-60")], &["hoog"])).unwrap();
+    assert!(factuur.check_credit_note(&te_veel).is_err());
+    let rest = Factuur::try_from(form(&[("Bar 3 oktober", "-50")], &["hoog"])).unwrap();
+    assert!(factuur.check_credit_note(&rest).is_ok());
+
+    let positief = Factuur::try_from(form(&[("Bar 3 oktober", "50")], &["hoog"])).unwrap();
+    assert!(factuur.check_credit_note(&positief).is_err());
+
+    // A credit note is never outstanding, and can't be credited itself
+    let mut credit_note = credit_note;
+    credit_note.credit_for = Some(credit_for);
+    assert_eq!(credit_note.outstanding(date("2026-10-10")), Money::ZERO);
+    assert!(credit_note.check_credit_note(&rest).is_err());
+}
@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use creatief_vakvrouw::event::Event;
+use creatief_vakvrouw::factuur::WorkItem;
+use creatief_vakvrouw::ics::{Filter, Ics};
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::rate::{Rate, Rates};
+use creatief_vakvrouw::schedule::{Schedule, Shifts};
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+async fn agenda(filter: Filter, from: &str, until: &str) -> Shifts {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/agenda.ics");
+    let contents = std::fs::read_to_string(path).unwrap();
+    Ics::new(contents, filter)
+        .shifts(date(from), date(until))
+        .await
+        .unwrap()
+}
+
+async fn oktober(filter: Filter) -> Shifts {
+    agenda(filter, "2026-10-01", "2026-10-31").await
+}
+
+fn starts(events: &[Event]) -> Vec<&str> {
+    events.iter().map(|e| e.start.as_str()).collect()
+}
+
+#[tokio::test]
+async fn events_in_the_period() {
+    let shifts = oktober(Filter::default()).await;
+    // Without the cancelled workshop, the fair that takes the whole day and
+    // the lessons in the autumn holiday
+    assert_eq!(
+        starts(&shifts.events),
+        [
+            "2026-10-01T19:00:00",
+            "2026-10-03T10:00:00",
+            "2026-10-06T19:00:00",
+            "2026-10-08T19:00:00",
+            "2026-10-11T14:00:00",
+            "2026-10-13T19:00:00",
+            "2026-10-14T09:00:00",
+            "2026-10-15T08:30:00",
+            "2026-10-15T19:00:00",
+            // The lesson of the 27th was moved, and the one of the 29th cancelled
+            "2026-10-28T18:00:00",
+        ]
+    );
+    // Monthly meetings are not read, but mentioned
+    assert_eq!(shifts.skipped, ["Overleg atelier vanaf 2026-10-01"]);
+
+    // Times in UTC are in summer time, and a duration gives the end
+    let styling = &shifts.events[6];
+    assert_eq!(styling.event_type, "Styling fotoshoot, Magazine");
+    assert_eq!(styling.start_to_end, "09:00 - 14:00");
+    assert_eq!(styling.minutes().unwrap(), 300);
+
+    // Repeating events keep their time on the clock after summer time ends,
+    // until the last day of the rule
+    let november = agenda(Filter::default(), "2026-11-01", "2026-11-30").await;
+    assert_eq!(
+        starts(&november.events),
+        ["2026-11-03T19:00:00", "2026-11-05T19:00:00"]
+    );
+    assert!(november
+        .skipped
+        .contains(&"Overleg atelier vanaf 2026-10-01".to_owned()));
+}
+
+#[tokio::test]
+async fn events_are_filtered() {
+    let werk = oktober(Filter {
+        calendar: Some("werk".to_owned()),
+        category: Some("Factureren".to_owned()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(werk.events.len(), 8);
+    assert!(werk
+        .events
+        .iter()
+        .all(|e| e.person == "Werk" && e.event_type != "Tandarts"));
+
+    let workshops = oktober(Filter {
+        title: Some("workshop".to_owned()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(
+        starts(&workshops.events),
+        ["2026-10-03T10:00:00", "2026-10-11T14:00:00"]
+    );
+
+    let patroon = oktober(Filter {
+        title: Some("Workshop *en".to_owned()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(starts(&patroon.events), ["2026-10-03T10:00:00"]);
+
+    let categorie = oktober(Filter {
+        category: Some("workshops".to_owned()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(starts(&categorie.events), ["2026-10-03T10:00:00"]);
+}
+
+#[tokio::test]
+async fn events_are_priced_by_their_title() {
+    let les = Rate {
+        id: 0,
+        client: None,
+        event_type: Some("Les keramiek".to_owned()),
+        valid_from: date("2026-01-01"),
+        valid_until: None,
+        hourly_rate: Money::from_cents(4500),
+        evening_surcharge: 0,
+        weekend_surcharge: 0,
+    };
+    let rates = Rates::new(vec![les], None);
+
+    let shifts = oktober(Filter::default()).await;
+    let items: Vec<WorkItem> = shifts
+        .events
+        .into_iter()
+        .map(|e| WorkItem::from_event(e, &rates).unwrap())
+        .collect();
+    assert_eq!(items[0].desc, "Les keramiek 2026-10-01 (19:00 - 21:00)");
+    assert_eq!(items[0].euro, Money::from_cents(9000));
+    // Work without its own rate is billed at the default hourly rate
+    assert_eq!(
+        items[1].desc,
+        "Workshop bloemschikken 2026-10-03 (10:00 - 13:30)"
+    );
+    assert_eq!(items[1].euro, Money::from_cents(7700));
+}
+
+#[tokio::test]
+async fn times_are_converted_to_amsterdam() {
+    let contents = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:londen
+SUMMARY:Workshop Londen
+DTSTART;TZID=Europe/London:20261003T100000
+DTEND;TZID=Europe/London:20261003T120000
+END:VEVENT
+BEGIN:VEVENT
+UID:outlook
+SUMMARY:Overleg
+DTSTART;TZID=\"W. Europe Standard Time\":20261005T100000
+DTEND;TZID=\"W. Europe Standard Time\":20261005T110000
+END:VEVENT
+BEGIN:VEVENT
+UID:utc
+SUMMARY:Styling
+DTSTART;TZID=Etc/UTC:20261124T080000
+DTEND;TZID=Etc/UTC:20261124T100000
+END:VEVENT
+BEGIN:VEVENT
+UID:new-york
+SUMMARY:Beurs New York
+DTSTART;TZID=America/New_York:20261007T090000
+DTEND;TZID=America/New_York:20261007T170000
+END:VEVENT
+BEGIN:VEVENT
+UID:later
+SUMMARY:Beurs Tokio
+DTSTART;TZID=Asia/Tokyo:20261207T090000
+DTEND;TZID=Asia/Tokyo:20261207T170000
+END:VEVENT
+END:VCALENDAR
+";
+    let shifts = Ics::new(contents.to_owned(), Filter::default())
+        .shifts(date("2026-10-01"), date("2026-11-30"))
+        .await
+        .unwrap();
+
+    // London is an hour behind, like UTC is once summer time has ended
+    assert_eq!(
+        starts(&shifts.events),
+        [
+            "2026-10-03T11:00:00",
+            "2026-10-05T10:00:00",
+            "2026-11-24T09:00:00"
+        ]
+    );
+    // Other time zones are left to fill in by hand
+    assert_eq!(
+        shifts.skipped,
+        ["Beurs New York op 2026-10-07 (America/New_York)"]
+    );
+}
+
+#[tokio::test]
+async fn other_files_are_refused() {
+    let shifts = Ics::new(
+        "BEGIN:VCARD\nFN:Noemi\nEND:VCARD".to_owned(),
+        Filter::default(),
+    )
+    .shifts(date("2026-10-01"), date("2026-10-31"))
+    .await;
+    assert!(shifts.is_err());
+}
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+use creatief_vakvrouw::client::{self, ClientDetails, Wijziging};
+use creatief_vakvrouw::db;
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::server;
+
+const TENANT: i64 = 1;
+
+fn bestand(name: &str) -> Vec<ClientDetails> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/klanten")
+        .join(name);
+    client::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn namen(clients: &[ClientDetails]) -> Vec<&str> {
+    clients.iter().map(|c| c.name.as_str()).collect()
+}
+
+#[test]
+fn clients_json() {
+    let clients = bestand("clients.json");
+    assert_eq!(namen(&clients), vec!["V.O.F. De Nieuwe Anita", "Paradiso"]);
+
+    let anita = &clients[0];
+    assert_eq!(anita.address, "Frederik Hendrikstraat 111");
+    assert_eq!(anita.zip, "1052 HN Amsterdam");
+    assert_eq!(anita.kvk, "12345678");
+    assert_eq!(anita.payment_term, Some(30));
+    assert_eq!(anita.hourly_rate, Some(Money::from_cents(3250)));
+    assert_eq!(clients[1].payment_term, None);
+    assert_eq!(clients[1].hourly_rate, None);
+}
+
+#[test]
+fn csv() {
+    // Dutch headers, separated by semicolons. Rows without a name are left out.
+    let clients = bestand("klanten.csv");
+    assert_eq!(
+        namen(&clients),
+        vec!["V.O.F. De Nieuwe Anita", "Theater Bellevue"]
+    );
+
+    let anita = &clients[0];
+    assert_eq!(anita.email, "info@denieuweanita.nl");
+    assert_eq!(anita.contact, "Anita");
+    assert_eq!(anita.payment_term, None);
+
+    let bellevue = &clients[1];
+    assert_eq!(bellevue.address, "Leidsekade 90");
+    assert_eq!(bellevue.zip, "1017 PN Amsterdam");
+    assert_eq!(bellevue.payment_term, Some(14));
+    assert_eq!(bellevue.hourly_rate, Some(Money::from_cents(4500)));
+
+    // English headers, separated by commas
+    let clients = client::parse("name,zip,payment_term\nParadiso,1017 SG Amsterdam,7\n").unwrap();
+    assert_eq!(namen(&clients), vec!["Paradiso"]);
+    assert_eq!(clients[0].zip, "1017 SG Amsterdam");
+    assert_eq!(clients[0].payment_term, Some(7));
+
+    assert!(client::parse("adres\nWeteringschans 6-8\n").is_err());
+    assert!(client::parse("naam;betalingstermijn\nParadiso;een week\n").is_err());
+}
+
+#[test]
+fn vcard() {
+    let clients = bestand("klanten.vcf");
+    assert_eq!(
+        namen(&clients),
+        vec!["V.O.F. De Nieuwe Anita", "Jan de Vries, fotograaf"]
+    );
+
+    // The company is the client, and the person its contact
+    let anita = &clients[0];
+    assert_eq!(anita.contact, "Anita Jansen");
+    assert_eq!(anita.email, "info@denieuweanita.nl");
+    assert_eq!(anita.address, "Frederik Hendrikstraat 111");
+    assert_eq!(anita.zip, "1052 HN Amsterdam");
+    assert_eq!(anita.country, "Nederland");
+
+    // A folded line is one address
+    let jan = &clients[1];
+    assert_eq!(jan.contact, "");
+    assert_eq!(
+        jan.address,
+        "Lange straatnaam die over twee regels doorloopt 1"
+    );
+    assert_eq!(jan.zip, "3511 AB Utrecht");
+
+    assert!(client::parse("BEGIN:VCARD\nVERSION:3.0\nEND:VCARD\n").is_err());
+}
+
+#[tokio::test]
+async fn import_shows_what_changes() {
+    let dir = TempDir::new().unwrap();
+    let db = server::connect_to(&dir.path().join("facturen.db"))
+        .await
+        .unwrap();
+    let mut conn = db.acquire().await.unwrap();
+
+    // Everything is new at first, in the Netherlands unless said otherwise
+    let bestaand = db::get_client_details(&mut conn, TENANT).await.unwrap();
+    let wijzigingen = client::vergelijk(&bestaand, bestand("clients.json"));
+    assert!(wijzigingen
+        .iter()
+        .all(|w| matches!(w, Wijziging::Nieuw(klant) if klant.country == "Nederland")));
+    assert_eq!(
+        db::import_clients(&mut conn, TENANT, &wijzigingen)
+            .await
+            .unwrap(),
+        2
+    );
+
+    // The CSV adds a client, and fills in fields of one that exists. The
+    // fields that are empty in the CSV keep their value.
+    let bestaand = db::get_client_details(&mut conn, TENANT).await.unwrap();
+    let wijzigingen = client::vergelijk(&bestaand, bestand("klanten.csv"));
+    let overzicht: Vec<String> = wijzigingen.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        overzicht,
+        vec![
+            "~ V.O.F. De Nieuwe Anita\n    \
+            E-mail: \"\" -> \"info@denieuweanita.nl\"\n    \
+            Contactpersoon: \"\" -> \"Anita\"",
+            "+ Theater Bellevue (Leidsekade 90, 1017 PN Amsterdam)",
+        ]
+    );
+    let anita = wijzigingen[0].klant();
+    assert_eq!(anita.payment_term, Some(30));
+    assert_eq!(anita.kvk, "12345678");
+    db::import_clients(&mut conn, TENANT, &wijzigingen)
+        .await
+        .unwrap();
+
+    // Importing the same file again changes nothing
+    let bestaand = db::get_client_details(&mut conn, TENANT).await.unwrap();
+    let wijzigingen = client::vergelijk(&bestaand, bestand("klanten.csv"));
+    assert!(wijzigingen.iter().all(|w| !w.is_change()));
+    assert_eq!(
+        db::import_clients(&mut conn, TENANT, &wijzigingen)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        db::get_client_details(&mut conn, TENANT)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn duplicates_in_an_import_are_combined() {
+    let import = client::parse(
+        "naam,e-mail,uurtarief\n\
+        Paradiso,info@paradiso.nl,\n\
+        paradiso ,,40\n",
+    )
+    .unwrap();
+    let wijzigingen = client::vergelijk(&[], import);
+
+    assert_eq!(wijzigingen.len(), 1);
+    let paradiso = wijzigingen[0].klant();
+    assert_eq!(paradiso.name, "Paradiso");
+    assert_eq!(paradiso.email, "info@paradiso.nl");
+    assert_eq!(paradiso.hourly_rate, Some(Money::from_cents(4000)));
+}
@@ -0,0 +1,37 @@
+use creatief_vakvrouw::expense::Receipt;
+
+fn bon(filename: &str, content_type: &str) -> Receipt {
+    Receipt {
+        filename: filename.to_owned(),
+        content_type: content_type.to_owned(),
+        file: b"bon".to_vec(),
+    }
+}
+
+#[test]
+fn pictures_and_pdfs_are_shown() {
+    assert_eq!(
+        bon("bon.jpg", "image/jpeg").headers(),
+        (
+            "image/jpeg".to_owned(),
+            "inline; filename=\"bon.jpg\"".to_owned()
+        )
+    );
+    assert!(bon("bon.pdf", "application/pdf").shown_inline());
+    assert!(bon("bon.PDF", "Application/PDF; name=bon.PDF").shown_inline());
+}
+
+#[test]
+fn other_files_are_downloaded() {
+    for content_type in ["text/html", "image/svg+xml", "application/xhtml+xml", ""] {
+        let (served_as, disposition) = bon("bon", content_type).headers();
+        assert_eq!(served_as, "application/octet-stream", "{content_type}");
+        assert!(disposition.starts_with("attachment;"), "{content_type}");
+    }
+}
+
+#[test]
+fn filenames_stay_within_their_quotes() {
+    let (_, disposition) = bon("bon\".pdf\r\nX-Header: \\1", "application/pdf").headers();
+    assert_eq!(disposition, "inline; filename=\"bon.pdfX-Header: 1\"");
+}
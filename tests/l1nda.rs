@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::Result;
+use chrono::{Datelike, IsoWeek, NaiveDate, Weekday};
+
+use creatief_vakvrouw::event::Week;
+use creatief_vakvrouw::l1nda::{self, L1nda};
+use creatief_vakvrouw::schedule::{self, Shifts};
+
+fn week(year: i32, week: u32) -> IsoWeek {
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+        .unwrap()
+        .iso_week()
+}
+
+fn weeks(year: i32, month: u32) -> Vec<(i32, u32)> {
+    l1nda::weeks_of_month(year, month)
+        .unwrap()
+        .iter()
+        .map(|w| (w.year(), w.week()))
+        .collect()
+}
+
+/// A week as recorded from L1NDA, or a week without shifts when there is no
+/// recording of it.
+async fn fixture(week: IsoWeek) -> Result<Week> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/l1nda")
+        .join(format!("{}-{:02}.json", week.year(), week.week()));
+    match std::fs::read_to_string(path) {
+        Ok(body) => Ok(serde_json::from_str(&body)?),
+        Err(_) => {
+            let monday =
+                NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Mon).unwrap();
+            let sunday =
+                NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Sun).unwrap();
+            Ok(Week {
+                start_date: monday.to_string(),
+                end_date: sunday.to_string(),
+                schedule: vec![],
+            })
+        }
+    }
+}
+
+fn noemi() -> L1nda {
+    L1nda::new(1, "denieuweanita", "Noemi").unwrap()
+}
+
+async fn shifts(month: &str) -> Shifts {
+    let (from, until) = schedule::month(month).unwrap();
+    let weeks = l1nda::weeks_between(from, until);
+    let fetched = l1nda::fetch_weeks(&weeks, fixture).await;
+    noemi().shifts_between(from, until, fetched)
+}
+
+fn dates(shifts: &Shifts) -> Vec<&str> {
+    shifts.events.iter().map(|e| e.date.as_str()).collect()
+}
+
+#[test]
+fn weeks_cover_the_whole_month() {
+    // The 1st of October 2026 is a Thursday, in the week of the 28th of September
+    assert_eq!(weeks(2026, 9).last(), Some(&(2026, 40)));
+    assert_eq!(
+        weeks(2026, 10),
+        [(2026, 40), (2026, 41), (2026, 42), (2026, 43), (2026, 44)]
+    );
+
+    // Months that start on a Monday don't need the week before
+    assert_eq!(weeks(2026, 6).first(), Some(&(2026, 23)));
+    assert_eq!(
+        weeks(2026, 2),
+        [(2026, 5), (2026, 6), (2026, 7), (2026, 8), (2026, 9)]
+    );
+}
+
+#[test]
+fn weeks_cross_the_year() {
+    // 2026 has 53 weeks, the last one ending on the 3rd of January 2027
+    assert_eq!(
+        weeks(2026, 12),
+        [(2026, 49), (2026, 50), (2026, 51), (2026, 52), (2026, 53)]
+    );
+    assert_eq!(
+        weeks(2027, 1),
+        [(2026, 53), (2027, 1), (2027, 2), (2027, 3), (2027, 4)]
+    );
+
+    // The 29th of December 2025 already is in week 1 of 2026
+    assert_eq!(weeks(2025, 12).last(), Some(&(2026, 1)));
+    assert_eq!(weeks(2026, 1).first(), Some(&(2026, 1)));
+}
+
+#[test]
+fn invalid_months_are_refused() {
+    assert!(l1nda::weeks_of_month(2026, 0).is_err());
+    assert!(l1nda::weeks_of_month(2026, 13).is_err());
+    assert!(schedule::month("2026").is_err());
+    assert!(schedule::month("oktober-2026").is_err());
+}
+
+#[tokio::test]
+async fn shifts_in_any_period() {
+    // The last days of December and the first of January, over week 53
+    let from = NaiveDate::from_ymd_opt(2026, 12, 28).unwrap();
+    let until = NaiveDate::from_ymd_opt(2027, 1, 3).unwrap();
+    let weeks = l1nda::weeks_between(from, until);
+    assert_eq!(weeks, [week(2026, 53)]);
+
+    let fetched = l1nda::fetch_weeks(&weeks, fixture).await;
+    let shifts = noemi().shifts_between(from, until, fetched);
+    assert_eq!(dates(&shifts), ["2026-12-31", "2027-01-02"]);
+}
+
+#[tokio::test]
+async fn shifts_stay_in_their_month() {
+    let september = shifts("2026-09").await;
+    assert_eq!(dates(&september), ["2026-09-30"]);
+
+    // Only the shifts of the employee, not those of colleagues
+    let oktober = shifts("2026-10").await;
+    assert_eq!(dates(&oktober), ["2026-10-02"]);
+    assert_eq!(oktober.events[0].start_to_end, "16:00 - 22:30");
+    assert!(oktober.missing_weeks.is_empty());
+}
+
+#[tokio::test]
+async fn week_53_is_split_over_the_years() {
+    let december = shifts("2026-12").await;
+    assert_eq!(dates(&december), ["2026-12-31"]);
+    assert_eq!(december.events[0].event_type, "Oudjaar");
+
+    // Week 2 of 2027 is a login page, as when the session has expired. It is
+    // left out, while the other weeks are still imported in order.
+    let januari = shifts("2027-01").await;
+    assert_eq!(dates(&januari), ["2027-01-02", "2027-01-08"]);
+    assert_eq!(januari.missing_weeks, [week(2027, 2)]);
+}
+
+#[tokio::test]
+async fn failed_weeks_are_tried_again() {
+    let attempts = AtomicU32::new(0);
+    let weeks = l1nda::weeks_of_month(2026, 10).unwrap();
+
+    let fetched = l1nda::fetch_weeks(&weeks, |w| {
+        let attempt = if w == week(2026, 40) {
+            attempts.fetch_add(1, Ordering::SeqCst)
+        } else {
+            1
+        };
+        async move {
+            match attempt {
+                0 => Err(anyhow::anyhow!("connection reset by peer")),
+                _ => fixture(w).await,
+            }
+        }
+    })
+    .await;
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    let (from, until) = schedule::month("2026-10").unwrap();
+    let oktober = noemi().shifts_between(from, until, fetched);
+    assert_eq!(dates(&oktober), ["2026-10-02"]);
+    assert!(oktober.missing_weeks.is_empty());
+}
+
+#[test]
+fn only_subdomains_of_l1nda() {
+    for subdomain in ["denieuweanita", "cafe-de-2-zussen"] {
+        assert!(L1nda::new(2, subdomain, "Noemi").is_ok(), "{subdomain}");
+    }
+    // These would send the session key to another host
+    for subdomain in ["evil.example#", "evil.example/", "a.b", "Anita", "", "x@y"] {
+        assert!(!l1nda::valid_subdomain(subdomain), "{subdomain}");
+        assert!(L1nda::new(2, subdomain, "Noemi").is_err(), "{subdomain}");
+    }
+}
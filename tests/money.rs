@@ -0,0 +1,68 @@
+use creatief_vakvrouw::money::Money;
+
+fn cents(s: &str) -> i64 {
+    s.parse::<Money>().unwrap().cents()
+}
+
+#[test]
+fn parse() {
+    assert_eq!(cents("12"), 1200);
+    assert_eq!(cents("12,50"), 1250);
+    assert_eq!(cents("12.5"), 1250);
+    assert_eq!(cents(" -12,05 "), -1205);
+    assert_eq!(cents("+3"), 300);
+    assert_eq!(cents(",75"), 75);
+    // Beyond the second decimal, half a cent rounds away from zero
+    assert_eq!(cents("0,004"), 0);
+    assert_eq!(cents("0,005"), 1);
+    assert_eq!(cents("-0,005"), -1);
+    assert_eq!(cents("9,999"), 1000);
+
+    // Thousands separators would be read as a decimal separator
+    assert!("1.234,56".parse::<Money>().is_err());
+    assert!("1,234.56".parse::<Money>().is_err());
+    for s in ["", "-", ",", "€ 12", "12 euro", "1e3", "--1"] {
+        assert!(s.parse::<Money>().is_err(), "{s:?} should not parse");
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(Money::from_cents(1205).to_string(), "12.05");
+    assert_eq!(Money::from_cents(-5).to_string(), "-0.05");
+    assert_eq!(Money::ZERO.to_string(), "0.00");
+}
+
+#[test]
+fn mul_div_rounds_half_away_from_zero() {
+    assert_eq!(Money::from_cents(5).mul_div(1, 2), Money::from_cents(3));
+    assert_eq!(Money::from_cents(-5).mul_div(1, 2), Money::from_cents(-3));
+    assert_eq!(Money::from_cents(5).mul_div(-1, 2), Money::from_cents(-3));
+    assert_eq!(Money::from_cents(-5).mul_div(-1, 2), Money::from_cents(3));
+    assert_eq!(Money::from_cents(7).mul_div(1, 3), Money::from_cents(2));
+    assert_eq!(Money::from_cents(-7).mul_div(1, 3), Money::from_cents(-2));
+
+    // 21% of 100,50 is 21,105
+    assert_eq!(
+        Money::from_cents(10050).percentage(2100),
+        Money::from_cents(2111)
+    );
+    assert_eq!(
+        Money::from_cents(-10050).percentage(2100),
+        Money::from_cents(-2111)
+    );
+}
+
+#[test]
+fn whole_euros() {
+    let floor = |c| Money::from_cents(c).floor_euros().cents();
+    let ceil = |c| Money::from_cents(c).ceil_euros().cents();
+
+    assert_eq!((floor(1250), ceil(1250)), (1200, 1300));
+    assert_eq!((floor(1200), ceil(1200)), (1200, 1200));
+    assert_eq!((floor(1), ceil(1)), (0, 100));
+    // Towards negative and positive infinity, not towards zero
+    assert_eq!((floor(-1250), ceil(-1250)), (-1300, -1200));
+    assert_eq!((floor(-1200), ceil(-1200)), (-1200, -1200));
+    assert_eq!((floor(-1), ceil(-1)), (-100, 0));
+}
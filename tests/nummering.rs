@@ -0,0 +1,199 @@
+use chrono::{TimeZone, Utc};
+use sqlx::pool::PoolConnection;
+use sqlx::Sqlite;
+use tempfile::TempDir;
+
+use creatief_vakvrouw::db;
+use creatief_vakvrouw::factuur::{Factuur, FactuurForm};
+use creatief_vakvrouw::nummering::{self, Nummering};
+use creatief_vakvrouw::server;
+
+const TENANT: i64 = 1;
+
+#[test]
+fn patterns() {
+    let jaarlijks: Nummering = "{jaar}-{nummer:4}".parse().unwrap();
+    assert_eq!(jaarlijks.format(2026, 42), "2026-0042");
+    assert_eq!(jaarlijks.format(2026, 12345), "2026-12345");
+    assert!(jaarlijks.resets_yearly());
+
+    let doortellen: Nummering = "F{nummer}".parse().unwrap();
+    assert_eq!(doortellen.format(2026, 7), "F7");
+    assert!(!doortellen.resets_yearly());
+
+    assert_eq!(Nummering::default().format(2026, 7), "7");
+    assert_eq!(
+        "{nummer:3}/{jaar}"
+            .parse::<Nummering>()
+            .unwrap()
+            .format(2027, 5),
+        "005/2027"
+    );
+
+    for pattern in [
+        "{jaar}",
+        "",
+        "{nummer",
+        "{nummer:}",
+        "{nummer:x}",
+        "{Jaar}-{nummer}",
+    ] {
+        assert!(
+            pattern.parse::<Nummering>().is_err(),
+            "{pattern:?} should not parse"
+        );
+    }
+}
+
+#[test]
+fn gaps() {
+    assert_eq!(
+        nummering::gaps(vec![6, 3, 1, 5, 9, 9]),
+        vec![2..=2, 4..=4, 7..=8]
+    );
+    assert!(nummering::gaps(vec![2, 1, 3]).is_empty());
+    assert!(nummering::gaps(vec![4]).is_empty());
+    assert!(nummering::gaps(vec![]).is_empty());
+}
+
+async fn database() -> (TempDir, PoolConnection<Sqlite>) {
+    let dir = TempDir::new().unwrap();
+    let db = server::connect_to(&dir.path().join("facturen.db"))
+        .await
+        .unwrap();
+    let conn = db.acquire().await.unwrap();
+    (dir, conn)
+}
+
+async fn add_invoice(
+    conn: &mut PoolConnection<Sqlite>,
+    nummering: &Nummering,
+    (jaar, maand, dag): (i32, u32, u32),
+) -> Factuur {
+    let mut factuur = Factuur::try_from(FactuurForm {
+        client_name: "Klant".to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: vec!["Bar".to_owned()],
+        prices: vec!["100".to_owned()],
+        btw_categorieen: vec!["hoog".to_owned()],
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap();
+    factuur.date = Utc.with_ymd_and_hms(jaar, maand, dag, 12, 0, 0).unwrap();
+    db::add_invoice(&mut *conn, TENANT, &mut factuur, None, nummering, |_| {
+        Ok(b"%PDF".to_vec())
+    })
+    .await
+    .unwrap();
+    factuur
+}
+
+#[tokio::test]
+async fn numbers_start_over_every_year() {
+    let (_dir, mut conn) = database().await;
+    let jaarlijks: Nummering = "{jaar}-{nummer:4}".parse().unwrap();
+
+    let kenmerken = [
+        add_invoice(&mut conn, &jaarlijks, (2026, 12, 30)).await,
+        add_invoice(&mut conn, &jaarlijks, (2026, 12, 31)).await,
+        add_invoice(&mut conn, &jaarlijks, (2027, 1, 1)).await,
+    ]
+    .map(|f| f.kenmerk);
+    assert_eq!(kenmerken, ["2026-0001", "2026-0002", "2027-0001"]);
+    assert_eq!(
+        db::next_kenmerk(&mut conn, TENANT, &jaarlijks, 2027)
+            .await
+            .unwrap(),
+        "2027-0002"
+    );
+
+    // Without the year in the pattern, the numbers keep counting up
+    let doortellen = Nummering::default();
+    let volgende = add_invoice(&mut conn, &doortellen, (2027, 1, 2)).await;
+    assert_eq!(volgende.kenmerk, "3");
+}
+
+#[tokio::test]
+async fn gaps_are_found_per_year() {
+    let (_dir, mut conn) = database().await;
+    let jaarlijks: Nummering = "{jaar}-{nummer:4}".parse().unwrap();
+
+    let mut facturen = vec![];
+    for datum in [
+        (2026, 12, 1),
+        (2026, 12, 2),
+        (2026, 12, 3),
+        (2027, 1, 1),
+        (2027, 1, 2),
+    ] {
+        facturen.push(add_invoice(&mut conn, &jaarlijks, datum).await);
+    }
+    assert!(db::get_invoice_gaps(&mut conn, TENANT, &jaarlijks)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Like invoices that were numbered by hand before
+    for (factuur, volgnummer) in [(&facturen[2], 5), (&facturen[4], 3)] {
+        let nummer = factuur.nummer as i64;
+        sqlx::query!(
+            "UPDATE invoice SET volgnummer = ? WHERE nummer = ?",
+            volgnummer,
+            nummer
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+        db::get_invoice_gaps(&mut conn, TENANT, &jaarlijks)
+            .await
+            .unwrap(),
+        ["2026-0003 t/m 2026-0004", "2027-0002"]
+    );
+}
+
+#[tokio::test]
+async fn a_draft_becomes_one_invoice() {
+    let (_dir, mut conn) = database().await;
+    let nummering = Nummering::default();
+    let factuur = add_invoice(&mut conn, &nummering, (2026, 10, 1)).await;
+    let concept = db::save_draft(
+        &mut conn,
+        TENANT,
+        None,
+        &factuur.client,
+        &factuur.work_items,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Confirming the same draft twice, like a double click, issues it once
+    let mut gemaakt = vec![];
+    for _ in 0..2 {
+        let mut kopie = factuur.clone();
+        gemaakt.push(
+            db::add_invoice(
+                &mut conn,
+                TENANT,
+                &mut kopie,
+                Some(concept),
+                &nummering,
+                |_| Ok(b"%PDF".to_vec()),
+            )
+            .await
+            .is_ok(),
+        );
+    }
+    assert_eq!(gemaakt, vec![true, false]);
+
+    let facturen = db::get_invoices(&mut conn, TENANT, db::InvoiceStatus::Active)
+        .await
+        .unwrap();
+    assert_eq!(facturen.len(), 2);
+    assert_eq!(facturen.iter().filter(|f| f.kenmerk == "2").count(), 1);
+    assert!(db::get_draft(&mut conn, TENANT, concept).await.is_err());
+}
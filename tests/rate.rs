@@ -0,0 +1,139 @@
+use chrono::NaiveDate;
+
+use creatief_vakvrouw::client::ClientDetails;
+use creatief_vakvrouw::event::Event;
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::rate::{Rate, Rates};
+
+const ANITA: i64 = 7;
+
+fn anita() -> ClientDetails {
+    ClientDetails {
+        id: ANITA,
+        name: "V.O.F. De Nieuwe Anita".to_owned(),
+        hourly_rate: Some(Money::from_cents(1800)),
+        ..Default::default()
+    }
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn rate(client: Option<i64>, event_type: Option<&str>, valid_from: &str, cents: i64) -> Rate {
+    Rate {
+        id: 0,
+        client,
+        event_type: event_type.map(str::to_owned),
+        valid_from: date(valid_from),
+        valid_until: None,
+        hourly_rate: Money::from_cents(cents),
+        evening_surcharge: 0,
+        weekend_surcharge: 0,
+    }
+}
+
+fn shift(event_type: &str, start: &str, end: &str) -> Event {
+    Event {
+        person: "Noemi".to_owned(),
+        event_type: event_type.to_owned(),
+        date: start[..10].to_owned(),
+        start_to_end: format!("{} - {}", &start[11..16], &end[11..16]),
+        start: start.to_owned(),
+        end: end.to_owned(),
+    }
+}
+
+fn price(rates: &Rates, event_type: &str, start: &str, end: &str) -> Money {
+    rates.price(&shift(event_type, start, end)).unwrap()
+}
+
+#[test]
+fn the_hourly_rate_of_the_client_without_rates() {
+    let rates = Rates::new(vec![], Some(&anita()));
+    let p = price(&rates, "Bar", "2026-10-14T12:00:00", "2026-10-14T16:30:00");
+    assert_eq!(p, Money::from_cents(4 * 1800 + 900));
+
+    let rates = Rates::new(vec![], None);
+    let p = hour(&rates, "Bar");
+    assert_eq!(p, Money::from_cents(2200));
+}
+
+fn hour(rates: &Rates, event_type: &str) -> Money {
+    price(
+        rates,
+        event_type,
+        "2026-10-14T12:00:00",
+        "2026-10-14T13:00:00",
+    )
+}
+
+#[test]
+fn the_most_specific_rate_wins() {
+    let rates = vec![
+        rate(None, None, "2026-01-01", 2000),
+        rate(None, Some("Bar"), "2026-01-01", 2100),
+        rate(Some(ANITA), None, "2026-01-01", 2300),
+        rate(Some(ANITA), Some("Keuken"), "2026-01-01", 2500),
+        rate(Some(ANITA + 1), Some("Bar"), "2026-01-01", 9900),
+    ];
+
+    let anita = Rates::new(rates.clone(), Some(&anita()));
+    assert_eq!(hour(&anita, "Keuken"), Money::from_cents(2500));
+    assert_eq!(hour(&anita, "Bar"), Money::from_cents(2300));
+
+    // Without a client only the rates for all clients apply
+    let others = Rates::new(rates, None);
+    assert_eq!(hour(&others, "Bar"), Money::from_cents(2100));
+    assert_eq!(hour(&others, "Keuken"), Money::from_cents(2000));
+}
+
+#[test]
+fn raises_apply_from_their_date() {
+    let mut tot_juli = rate(Some(ANITA), None, "2025-01-01", 1900);
+    tot_juli.valid_until = Some(date("2026-06-30"));
+    let rates = Rates::new(
+        vec![
+            rate(Some(ANITA), None, "2026-01-01", 2000),
+            rate(Some(ANITA), None, "2026-11-01", 2200),
+            tot_juli,
+        ],
+        Some(&anita()),
+    );
+
+    let find = |d| rates.find("Bar", date(d)).map(|r| r.hourly_rate.cents());
+    assert_eq!(find("2025-12-31"), Some(1900));
+    assert_eq!(find("2026-06-30"), Some(2000));
+    assert_eq!(find("2026-10-31"), Some(2000));
+    assert_eq!(find("2026-11-01"), Some(2200));
+
+    // A shift goes by the rate of the day it starts
+    let p = price(&rates, "Bar", "2026-10-31T22:00:00", "2026-11-01T02:00:00");
+    assert_eq!(p, Money::from_cents(4 * 2000));
+}
+
+#[test]
+fn evening_and_weekend_hours_get_a_surcharge() {
+    let mut toeslagen = rate(Some(ANITA), None, "2026-01-01", 2000);
+    toeslagen.evening_surcharge = 25;
+    toeslagen.weekend_surcharge = 50;
+    let rates = Rates::new(vec![toeslagen], Some(&anita()));
+
+    // Wednesday 18:00 to 22:00: two normal hours and two evening hours
+    let p = price(&rates, "Bar", "2026-10-14T18:00:00", "2026-10-14T22:00:00");
+    assert_eq!(p, Money::from_cents(2 * 2000 + 2 * 2500));
+
+    // Friday 22:00 to Saturday 02:00: the hours after midnight are in the
+    // weekend, where the highest surcharge counts
+    let p = price(&rates, "Bar", "2026-10-16T22:00:00", "2026-10-17T02:00:00");
+    assert_eq!(p, Money::from_cents(2 * 2500 + 2 * 3000));
+
+    // Sunday morning 05:00 to 07:00 on the night that summer time ends
+    let p = price(&rates, "Bar", "2026-10-25T05:00:00", "2026-10-25T07:00:00");
+    assert_eq!(p, Money::from_cents(2 * 3000));
+
+    // Saturday night into the Sunday that summer time ends: 4 hours before
+    // midnight and 6 after, as the hour from 02:00 happens twice
+    let p = price(&rates, "Bar", "2026-10-24T20:00:00", "2026-10-25T05:00:00");
+    assert_eq!(p, Money::from_cents(10 * 3000));
+}
@@ -0,0 +1,145 @@
+use chrono::NaiveDate;
+use tempfile::TempDir;
+
+use creatief_vakvrouw::btw::BtwCategorie;
+use creatief_vakvrouw::db;
+use creatief_vakvrouw::factuur::{Client, WorkItem};
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::recurring::{self, Frequency, RecurringInvoice};
+use creatief_vakvrouw::server;
+
+const TENANT: i64 = 1;
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn lessen(frequency: Frequency, next_run: &str) -> RecurringInvoice {
+    RecurringInvoice {
+        id: 0,
+        client: Client {
+            name: "Muziekschool".to_owned(),
+            address: "Frederik Hendrikstraat 111".to_owned(),
+            zip: "1052 HN Amsterdam".to_owned(),
+        },
+        work_items: vec![WorkItem {
+            desc: "Lessen {maand} {jaar}".to_owned(),
+            euro: Money::from_cents(25000),
+            btw: BtwCategorie::Vrijgesteld,
+        }],
+        frequency,
+        next_run: date(next_run),
+    }
+}
+
+#[test]
+fn missed_runs_are_caught_up_on() {
+    let maandelijks = lessen(Frequency::Maandelijks, "2026-08-01");
+    assert_eq!(
+        maandelijks.due_runs(date("2026-10-18")),
+        vec![date("2026-08-01"), date("2026-09-01"), date("2026-10-01")]
+    );
+    assert!(maandelijks.due_runs(date("2026-07-31")).is_empty());
+    assert_eq!(
+        maandelijks.due_runs(date("2026-08-01")),
+        vec![date("2026-08-01")]
+    );
+
+    let wekelijks = lessen(Frequency::Wekelijks, "2026-10-01");
+    assert_eq!(
+        wekelijks.due_runs(date("2026-10-18")),
+        vec![date("2026-10-01"), date("2026-10-08"), date("2026-10-15")]
+    );
+}
+
+#[test]
+fn runs_at_the_end_of_the_month() {
+    // The 31st of January moves to the 28th of February, and stays there
+    let mut run = date("2026-01-31");
+    let mut runs = vec![];
+    for _ in 0..3 {
+        run = Frequency::Maandelijks.next(run);
+        runs.push(run);
+    }
+    assert_eq!(
+        runs,
+        vec![date("2026-02-28"), date("2026-03-28"), date("2026-04-28")]
+    );
+
+    assert_eq!(
+        Frequency::Kwartaal.next(date("2026-11-30")),
+        date("2027-02-28")
+    );
+    assert_eq!(
+        Frequency::Jaarlijks.next(date("2028-02-29")),
+        date("2029-02-28")
+    );
+}
+
+#[test]
+fn placeholders() {
+    assert_eq!(
+        recurring::fill_placeholders("Lessen {month} {year}", date("2026-10-01")),
+        "Lessen oktober 2026"
+    );
+    assert_eq!(
+        recurring::fill_placeholders("{maand} {jaar}, {maand} {jaar}", date("2027-01-31")),
+        "januari 2027, januari 2027"
+    );
+    assert_eq!(
+        recurring::fill_placeholders("Lessen {dag}", date("2026-10-01")),
+        "Lessen {dag}"
+    );
+
+    let items = lessen(Frequency::Maandelijks, "2026-10-01").work_items_for(date("2026-12-01"));
+    assert_eq!(items[0].desc, "Lessen december 2026");
+    assert_eq!(items[0].euro, Money::from_cents(25000));
+}
+
+#[tokio::test]
+async fn due_runs_are_drafted_once() {
+    let dir = TempDir::new().unwrap();
+    let db = server::connect_to(&dir.path().join("facturen.db"))
+        .await
+        .unwrap();
+    let mut conn = db.acquire().await.unwrap();
+    let id = db::add_recurring_invoice(
+        &mut conn,
+        TENANT,
+        &lessen(Frequency::Maandelijks, "2026-08-01"),
+    )
+    .await
+    .unwrap();
+
+    // Two missed months and this one
+    let today = date("2026-10-18");
+    assert_eq!(db::create_due_drafts(&mut conn, today).await.unwrap(), 3);
+    assert_eq!(db::create_due_drafts(&mut conn, today).await.unwrap(), 0);
+
+    let mut drafts = db::get_drafts(&mut conn, TENANT).await.unwrap();
+    drafts.sort_by_key(|d| d.id);
+    let descs: Vec<&str> = drafts
+        .iter()
+        .map(|d| d.work_items[0].desc.as_str())
+        .collect();
+    assert_eq!(
+        descs,
+        vec![
+            "Lessen augustus 2026",
+            "Lessen september 2026",
+            "Lessen oktober 2026"
+        ]
+    );
+    assert!(drafts.iter().all(|d| d.recurring == Some(id)));
+
+    let recurring = db::get_recurring_invoices(&mut conn, TENANT).await.unwrap();
+    assert_eq!(recurring[0].next_run, date("2026-11-01"));
+
+    // The next month is drafted when it is due
+    assert_eq!(
+        db::create_due_drafts(&mut conn, date("2026-11-01"))
+            .await
+            .unwrap(),
+        1
+    );
+}
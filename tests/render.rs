@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+
+use chrono::{TimeZone, Utc};
+
+use creatief_vakvrouw::factuur::{Factuur, FactuurError, FactuurForm, FactuurRef};
+use creatief_vakvrouw::render::{Html, InvoiceRenderer, Pandoc, PdfRenderer, Tekst, Typst};
+use creatief_vakvrouw::settings::Settings;
+
+fn factuur() -> Factuur {
+    let mut factuur = Factuur::try_from(FactuurForm {
+        client_name: "De Nieuwe Anita".to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: vec!["Bar 3 oktober".to_owned(), "Workshop <zeefdruk>".to_owned()],
+        prices: vec!["100".to_owned(), "50,50".to_owned()],
+        btw_categorieen: vec!["hoog".to_owned(), "laag".to_owned()],
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap();
+    factuur.nummer = 42;
+    factuur.kenmerk = "2026-0042".to_owned();
+    factuur.date = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+    factuur
+}
+
+fn settings() -> Settings {
+    Settings {
+        business_name: "Creatief Vakvrouw".to_owned(),
+        owner_name: "Noemi van der Wurf".to_owned(),
+        email: "noemi.vanderwurf@gmail.com".to_owned(),
+        btw_number: "NL004011638B89".to_owned(),
+        kvk_number: String::new(),
+        account_holder: "N.Z. van der Wurf".to_owned(),
+        iban: "NL65 INGB 0001 0008 60".to_owned(),
+        city: "Amsterdam".to_owned(),
+        payment_term: 14,
+        closing_note: "Graag binnen {dagen} dagen betalen op:".to_owned(),
+        greeting: "Met vriendelijke groeten,".to_owned(),
+        serif_font: "Liberation Serif".to_owned(),
+        sans_font: "Liberation Sans".to_owned(),
+        font_size: "10pt".to_owned(),
+        geometry: "a4paper, left=43mm, right=43mm, top=51mm, bottom=17mm".to_owned(),
+        nummering: "{jaar}-{nummer:4}".to_owned(),
+    }
+}
+
+fn render(renderer: &dyn InvoiceRenderer, factuur: &Factuur) -> String {
+    String::from_utf8(renderer.render(factuur, &settings()).unwrap()).unwrap()
+}
+
+#[test]
+fn html() {
+    let html = render(&Html, &factuur());
+
+    assert_eq!(Html.content_type(), "text/html; charset=utf-8");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>Factuur #2026-0042</h1>"));
+    assert!(html.contains("Amsterdam, 18 oktober 2026"));
+    assert!(html.contains("Workshop &lt;zeefdruk&gt;"));
+    assert!(html.contains("BTW 21%:"));
+    assert!(html.contains("<strong>176,05</strong>"));
+    assert!(html.contains("IBAN: NL65 INGB 0001 0008 60"));
+    assert!(html.contains("&bull; BTW NL004011638B89"));
+    assert!(!html.contains("KVK"));
+}
+
+#[test]
+fn tekst() {
+    let tekst = render(&Tekst, &factuur());
+
+    assert_eq!(Tekst.content_type(), "text/plain; charset=utf-8");
+    assert!(tekst.starts_with("Factuur 2026-0042\n"));
+    assert!(tekst.contains("De Nieuwe Anita\nFrederik Hendrikstraat 111\n1052 HN Amsterdam\n"));
+    assert!(tekst.contains("  1. Bar 3 oktober"));
+    assert!(tekst.contains("  2. Workshop <zeefdruk>"));
+    assert!(tekst.contains("Subtotaal:     150,50\n"));
+    assert!(tekst.contains("BTW 21%:      21,00\n"));
+    assert!(tekst.contains("BTW 9%:       4,55\n"));
+    assert!(tekst.contains("Totaal:     176,05\n"));
+    assert!(tekst.contains("Graag binnen 14 dagen betalen op:\n\nN.Z. van der Wurf\n"));
+    assert!(tekst.ends_with("Met vriendelijke groeten,\n\nNoemi van der Wurf"));
+}
+
+#[test]
+fn tekst_credit_note() {
+    let mut factuur = factuur();
+    factuur.credit_for = Some(FactuurRef {
+        nummer: 41,
+        kenmerk: "2026-0041".to_owned(),
+    });
+
+    let tekst = render(&Tekst, &factuur);
+    assert!(tekst.starts_with("Creditfactuur 2026-0042\n"));
+    assert!(tekst.contains("corrigeert factuur 2026-0041"));
+    assert!(!tekst.contains("IBAN"));
+}
+
+/// Passes the details on to Typst, keeping them to look at afterwards.
+#[derive(Debug, Default)]
+struct Opname(Mutex<String>);
+
+impl InvoiceRenderer for Opname {
+    fn content_type(&self) -> &'static str {
+        Typst.content_type()
+    }
+
+    fn render(&self, factuur: &Factuur, settings: &Settings) -> Result<Vec<u8>, FactuurError> {
+        factuur.generate_pdf(self, settings)
+    }
+}
+
+impl PdfRenderer for Opname {
+    fn render_pdf(&self, details: &str) -> Result<Vec<u8>, FactuurError> {
+        *self.0.lock().unwrap() = details.to_owned();
+        Typst.render_pdf(details)
+    }
+}
+
+#[test]
+fn typst_pdf() {
+    let opname = Opname::default();
+    let pdf = opname.render(&factuur(), &settings()).unwrap();
+
+    assert_eq!(Typst.content_type(), "application/pdf");
+    assert!(pdf.starts_with(b"%PDF-"));
+    // The text on the pages is compressed, but the title is not
+    let pdf = String::from_utf8_lossy(&pdf);
+    assert!(pdf.contains("/Title (Creatief Vakvrouw Factuur nr. 2026-0042)"));
+
+    // What the template reads to fill the pages
+    let details = opname.0.into_inner().unwrap();
+    assert!(details.contains("invoice-nr: 2026-0042\n"));
+    assert!(details.contains("to:\n- De Nieuwe Anita\n- Frederik Hendrikstraat 111\n"));
+    assert!(details.contains("- description: Workshop <zeefdruk>\n  price: 50.50\n"));
+    assert!(details.contains("- label: \"BTW 9%\"\n  amount: 4.55\n"));
+    assert!(details.contains("total: 176.05\n"));
+    assert!(details.contains("IBAN: NL65 INGB 0001 0008 60"));
+}
+
+#[test]
+#[ignore = "needs pandoc and xelatex"]
+fn pandoc_pdf() {
+    let pdf = Pandoc.render(&factuur(), &settings()).unwrap();
+
+    assert_eq!(Pandoc.content_type(), "application/pdf");
+    assert!(pdf.starts_with(b"%PDF-"));
+}
@@ -0,0 +1,553 @@
+use chrono::{Datelike, NaiveDate};
+use sqlx::pool::PoolConnection;
+use sqlx::Sqlite;
+use tempfile::TempDir;
+
+use creatief_vakvrouw::aangifte::Quarter;
+use creatief_vakvrouw::bank::Transaction;
+use creatief_vakvrouw::btw::BtwCategorie;
+use creatief_vakvrouw::client::ClientDetails;
+use creatief_vakvrouw::db::{self, BankLink, InvoiceStatus, SoftDeleteAction};
+use creatief_vakvrouw::expense::{Expense, Receipt};
+use creatief_vakvrouw::factuur::{Client, Factuur, FactuurForm};
+use creatief_vakvrouw::ledger::OpeningBalance;
+use creatief_vakvrouw::money::Money;
+use creatief_vakvrouw::nummering::Nummering;
+use creatief_vakvrouw::payment::{Payment, PaymentMethod};
+use creatief_vakvrouw::recurring::{Frequency, RecurringInvoice};
+use creatief_vakvrouw::reminder::{Reminder, ReminderKind};
+use creatief_vakvrouw::schedule::{ScheduleSource, SourceKind};
+use creatief_vakvrouw::server;
+use creatief_vakvrouw::user::{self, Role};
+
+/// The first tenant, which owns everything from before there were tenants.
+const A: i64 = 1;
+
+/// A fresh database with a second tenant next to the first one. The
+/// directory has to be kept around for as long as the database is used.
+async fn database() -> (TempDir, PoolConnection<Sqlite>, i64) {
+    let dir = TempDir::new().unwrap();
+    let db = server::connect_to(&dir.path().join("facturen.db"))
+        .await
+        .unwrap();
+    let mut conn = db.acquire().await.unwrap();
+    let b = db::add_tenant(&mut conn, "Tweede Zaak").await.unwrap();
+    (dir, conn, b)
+}
+
+fn factuur(client: &str, price: &str) -> Factuur {
+    Factuur::try_from(FactuurForm {
+        client_name: client.to_owned(),
+        client_address: "Frederik Hendrikstraat 111".to_owned(),
+        client_zip: "1052 HN Amsterdam".to_owned(),
+        tasks: vec!["Bar 3 oktober".to_owned()],
+        prices: vec![price.to_owned()],
+        btw_categorieen: vec!["hoog".to_owned()],
+        credit_for: None,
+        concept: None,
+    })
+    .unwrap()
+}
+
+async fn add_invoice(
+    conn: &mut PoolConnection<Sqlite>,
+    tenant: i64,
+    nummering: &Nummering,
+    client: &str,
+) -> Factuur {
+    let mut factuur = factuur(client, "100");
+    db::add_invoice(&mut *conn, tenant, &mut factuur, None, nummering, |_| {
+        Ok(b"%PDF".to_vec())
+    })
+    .await
+    .unwrap();
+    factuur
+}
+
+fn klant(name: &str, address: &str) -> ClientDetails {
+    ClientDetails {
+        name: name.to_owned(),
+        address: address.to_owned(),
+        zip: "1234 AB Amsterdam".to_owned(),
+        country: "Nederland".to_owned(),
+        ..Default::default()
+    }
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+}
+
+async fn tenant_name(conn: &mut PoolConnection<Sqlite>, tenant: i64) -> String {
+    let tenants = db::get_tenants(conn).await.unwrap();
+    tenants.into_iter().find(|t| t.id == tenant).unwrap().name
+}
+
+#[tokio::test]
+async fn clients_stay_with_their_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    // Both businesses can have a client with the same name
+    let a_id = db::save_client(&mut conn, A, &klant("Klant", "Straat 1"))
+        .await
+        .unwrap();
+    let b_id = db::save_client(&mut conn, b, &klant("Klant", "Laan 2"))
+        .await
+        .unwrap();
+    db::save_client(&mut conn, b, &klant("Alleen B", "Plein 3"))
+        .await
+        .unwrap();
+
+    let a_clients = db::get_client_details(&mut conn, A).await.unwrap();
+    assert_eq!(a_clients.len(), 1);
+    assert_eq!(a_clients[0].address, "Straat 1");
+    let b_names: Vec<String> = db::get_all_clients(&mut conn, b)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(b_names, ["Alleen B", "Klant"]);
+
+    let b_klant = db::get_client(&mut conn, b, "Klant")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((b_klant.id, b_klant.address.as_str()), (b_id, "Laan 2"));
+    assert!(db::get_client(&mut conn, A, "Alleen B")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db::get_client_by_id(&mut conn, b, a_id).await.is_err());
+
+    // Nor can a client of the other tenant be changed, archived or merged
+    let mut overgenomen = klant("Overgenomen", "Weg 4");
+    overgenomen.id = a_id;
+    assert!(db::save_client(&mut conn, b, &overgenomen).await.is_err());
+    db::set_client_archived(&mut conn, b, a_id, true)
+        .await
+        .unwrap();
+    assert!(db::merge_clients(&mut conn, b, b_id, a_id).await.is_err());
+
+    let a_klant = db::get_client_by_id(&mut conn, A, a_id).await.unwrap();
+    assert_eq!(a_klant.name, "Klant");
+    assert!(!a_klant.archived);
+}
+
+#[tokio::test]
+async fn schedules_stay_with_their_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    // The schedule from before there were sources belongs to the first tenant
+    let a_bronnen = db::get_schedule_sources(&mut conn, A).await.unwrap();
+    assert_eq!(a_bronnen.len(), 1);
+    assert_eq!(a_bronnen[0].subdomain, "denieuweanita");
+    assert_eq!(a_bronnen[0].employee, "Noemi");
+    assert!(db::get_schedule_sources(&mut conn, b)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let dubbel = db::save_client(&mut conn, b, &klant("Café", "Straat 1"))
+        .await
+        .unwrap();
+    let cafe = db::save_client(&mut conn, b, &klant("Café Zomer", "Straat 1"))
+        .await
+        .unwrap();
+    let bron = ScheduleSource {
+        id: 0,
+        name: "Café".to_owned(),
+        kind: SourceKind::L1nda,
+        subdomain: "cafe".to_owned(),
+        employee: "Noemi".to_owned(),
+        client: Some(dubbel),
+    };
+    let b_id = db::add_schedule_source(&mut conn, b, &bron).await.unwrap();
+    assert!(db::get_schedule_source(&mut conn, A, b_id).await.is_err());
+    db::delete_schedule_source(&mut conn, A, b_id)
+        .await
+        .unwrap();
+
+    // The schedule follows its client when it is merged into another
+    db::merge_clients(&mut conn, b, cafe, dubbel).await.unwrap();
+    let b_bron = db::get_schedule_source(&mut conn, b, b_id).await.unwrap();
+    assert_eq!(b_bron.client, Some(cafe));
+}
+
+#[tokio::test]
+async fn invoices_are_numbered_per_tenant() {
+    let (_dir, mut conn, b) = database().await;
+    let jaarlijks: Nummering = "{jaar}-{nummer:4}".parse().unwrap();
+    let doortellen = Nummering::default();
+
+    let a_eerste = add_invoice(&mut conn, A, &jaarlijks, "Klant").await;
+    let b_eerste = add_invoice(&mut conn, b, &doortellen, "Klant").await;
+    let a_tweede = add_invoice(&mut conn, A, &jaarlijks, "Klant").await;
+
+    let jaar = a_eerste.date.year();
+    assert_eq!(a_eerste.kenmerk, format!("{jaar}-0001"));
+    assert_eq!(a_tweede.kenmerk, format!("{jaar}-0002"));
+    assert_eq!(b_eerste.kenmerk, "1");
+    assert_eq!(
+        db::next_kenmerk(&mut conn, b, &doortellen, jaar)
+            .await
+            .unwrap(),
+        "2"
+    );
+
+    // The internal nummer stays unique over all tenants
+    assert_ne!(a_eerste.nummer, b_eerste.nummer);
+    assert_ne!(a_tweede.nummer, b_eerste.nummer);
+
+    let b_invoices = db::get_invoices(&mut conn, b, InvoiceStatus::Active)
+        .await
+        .unwrap();
+    assert_eq!(b_invoices.len(), 1);
+    assert_eq!(b_invoices[0].nummer, b_eerste.nummer);
+    assert_eq!(
+        db::get_invoices(&mut conn, A, InvoiceStatus::Active)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Deleting an invoice of A leaves a gap for A only
+    db::soft_delete_invoice(
+        &mut conn,
+        A,
+        a_eerste.nummer as u32,
+        SoftDeleteAction::Delete,
+    )
+    .await
+    .unwrap();
+    add_invoice(&mut conn, b, &doortellen, "Klant").await;
+    assert!(db::get_invoice_gaps(&mut conn, A, &jaarlijks)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db::get_invoice_gaps(&mut conn, b, &doortellen)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn invoices_of_another_tenant_are_out_of_reach() {
+    let (_dir, mut conn, b) = database().await;
+    let nummering = Nummering::default();
+    let a_factuur = add_invoice(&mut conn, A, &nummering, "Klant").await;
+    let nummer = a_factuur.nummer as u32;
+
+    assert!(db::get_pdf(&mut conn, A, nummer).await.is_ok());
+    assert!(db::get_pdf(&mut conn, b, nummer).await.is_err());
+
+    let payment = Payment {
+        date: date(20),
+        amount: a_factuur.total,
+        method: PaymentMethod::Overboeking,
+    };
+    assert!(db::add_payment(&mut conn, b, nummer, &payment)
+        .await
+        .is_err());
+
+    let reminder = Reminder {
+        kind: ReminderKind::Eerste,
+        date: date(20),
+    };
+    let pdf = b"%PDF".to_vec();
+    assert!(db::add_reminder(&mut conn, b, nummer, &reminder, &pdf)
+        .await
+        .is_err());
+    assert!(db::add_reminder(&mut conn, A, nummer, &reminder, &pdf)
+        .await
+        .unwrap());
+
+    // Sending it twice, like on a double submit, keeps one reminder and PDF
+    let pdfs = || sqlx::query_scalar!("SELECT COUNT(*) FROM pdf");
+    let voor = pdfs().fetch_one(&mut *conn).await.unwrap();
+    assert!(!db::add_reminder(&mut conn, A, nummer, &reminder, &pdf)
+        .await
+        .unwrap());
+    assert_eq!(pdfs().fetch_one(&mut *conn).await.unwrap(), voor);
+    assert!(
+        db::get_reminder_pdf(&mut conn, b, nummer, ReminderKind::Eerste)
+            .await
+            .is_err()
+    );
+
+    db::soft_delete_invoice(&mut conn, b, nummer, SoftDeleteAction::Delete)
+        .await
+        .unwrap();
+    assert!(db::get_invoices(&mut conn, b, InvoiceStatus::Deleted)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let a_invoices = db::get_invoices(&mut conn, A, InvoiceStatus::Active)
+        .await
+        .unwrap();
+    assert_eq!(a_invoices.len(), 1);
+    assert!(a_invoices[0].payments.is_empty());
+    assert_eq!(a_invoices[0].reminders.len(), 1);
+}
+
+#[tokio::test]
+async fn settings_are_per_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    let nieuw = db::get_settings(&mut conn, b).await.unwrap();
+    assert_eq!(nieuw.business_name, "Tweede Zaak");
+    assert_eq!(nieuw.iban, "");
+    assert_eq!(nieuw.payment_term, 14);
+    assert_eq!(nieuw.nummering, "");
+
+    // The first business starts out without details, and without a name
+    let mut settings = db::get_settings(&mut conn, A).await.unwrap();
+    assert_eq!(settings.missing_details().len(), 6);
+    assert_eq!(tenant_name(&mut conn, A).await, "");
+
+    settings.business_name = "Creatief Vakvrouw B.V.".to_owned();
+    settings.payment_term = 30;
+    settings.nummering = "{jaar}-{nummer:4}".to_owned();
+    db::set_settings(&mut conn, A, &settings).await.unwrap();
+    assert_eq!(tenant_name(&mut conn, A).await, "Creatief Vakvrouw B.V.");
+    assert!(!settings.missing_details().contains(&"Bedrijfsnaam"));
+
+    let a = db::get_settings(&mut conn, A).await.unwrap();
+    assert_eq!(a.business_name, "Creatief Vakvrouw B.V.");
+    assert_eq!(a.payment_term, 30);
+    let b = db::get_settings(&mut conn, b).await.unwrap();
+    assert_eq!(b.business_name, "Tweede Zaak");
+    assert_eq!(b.payment_term, 14);
+    assert_eq!(
+        b.nummering(&Nummering::default()).unwrap(),
+        Nummering::default()
+    );
+}
+
+#[tokio::test]
+async fn bookkeeping_stays_with_its_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    let expense = Expense {
+        date: date(1),
+        supplier: "Boekhandel".to_owned(),
+        category: "Boeken".to_owned(),
+        btw_categorie: BtwCategorie::Laag,
+        excl: Money::from_cents(1000),
+        btw: Money::from_cents(90),
+    };
+    let receipt = Receipt {
+        filename: "bon.pdf".to_owned(),
+        content_type: "application/pdf".to_owned(),
+        file: b"%PDF".to_vec(),
+    };
+    let expense_id = db::add_expense(&mut conn, A, &expense, Some(&receipt))
+        .await
+        .unwrap();
+
+    assert!(db::get_expenses(&mut conn, b).await.unwrap().is_empty());
+    assert!(db::get_receipt(&mut conn, b, expense_id).await.is_err());
+    db::delete_expense(&mut conn, b, expense_id).await.unwrap();
+    assert_eq!(db::get_expenses(&mut conn, A).await.unwrap().len(), 1);
+
+    let q3 = Quarter::new(2026, 3).unwrap();
+    db::set_voorbelasting(&mut conn, A, &q3, Money::from_cents(500))
+        .await
+        .unwrap();
+    db::set_voorbelasting(&mut conn, b, &q3, Money::from_cents(700))
+        .await
+        .unwrap();
+    assert_eq!(
+        db::get_voorbelasting(&mut conn, A, &q3).await.unwrap(),
+        Money::from_cents(500)
+    );
+    assert_eq!(
+        db::get_all_voorbelasting(&mut conn, b).await.unwrap().len(),
+        1
+    );
+
+    let opening = OpeningBalance {
+        year: 2026,
+        bank: Money::from_cents(10000),
+        ..Default::default()
+    };
+    db::set_opening_balance(&mut conn, A, &opening)
+        .await
+        .unwrap();
+    assert!(db::get_opening_balance(&mut conn, b, 2026)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The same statement can be imported by both tenants
+    let transaction = Transaction {
+        date: date(2),
+        amount: Money::from_cents(12100),
+        counterparty: "Klant".to_owned(),
+        iban: "NL91 ABNA 0417 1643 00".to_owned(),
+        description: "Factuur 1".to_owned(),
+    };
+    let a_id = db::add_bank_transaction(&mut conn, A, &transaction)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(db::add_bank_transaction(&mut conn, A, &transaction)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db::add_bank_transaction(&mut conn, b, &transaction)
+        .await
+        .unwrap()
+        .is_some());
+
+    assert!(db::get_bank_transaction(&mut conn, b, a_id).await.is_err());
+    db::link_bank_transaction(&mut conn, b, a_id, BankLink::Ignored)
+        .await
+        .unwrap();
+    assert_eq!(
+        db::get_open_bank_transactions(&mut conn, A)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn drafts_stay_with_their_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    let recurring = RecurringInvoice {
+        id: 0,
+        client: Client {
+            name: "Klant".to_owned(),
+            address: "Straat 1".to_owned(),
+            zip: "1234 AB Amsterdam".to_owned(),
+        },
+        work_items: factuur("Klant", "100").work_items,
+        frequency: Frequency::Maandelijks,
+        next_run: date(1),
+    };
+    db::add_recurring_invoice(&mut conn, A, &recurring)
+        .await
+        .unwrap();
+    assert!(db::get_recurring_invoices(&mut conn, b)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Due recurring invoices become drafts of the tenant that set them up
+    assert_eq!(db::create_due_drafts(&mut conn, date(18)).await.unwrap(), 1);
+    assert!(db::get_drafts(&mut conn, b).await.unwrap().is_empty());
+    let drafts = db::get_drafts(&mut conn, A).await.unwrap();
+    assert_eq!(drafts.len(), 1);
+
+    let id = drafts[0].id;
+    assert!(db::get_draft(&mut conn, b, id).await.is_err());
+    assert!(
+        db::save_draft(&mut conn, b, Some(id), &recurring.client, &[], None)
+            .await
+            .is_err()
+    );
+    db::delete_draft(&mut conn, b, id).await.unwrap();
+    let draft = db::get_draft(&mut conn, A, id).await.unwrap();
+    assert_eq!(draft.work_items.len(), 1);
+}
+
+#[tokio::test]
+async fn users_log_in_to_their_tenant() {
+    let (_dir, mut conn, b) = database().await;
+
+    let hash = user::hash_password("correct horse battery staple").unwrap();
+    let id = db::add_user(&mut conn, b, "boekhouder", &hash, Role::Boekhouder)
+        .await
+        .unwrap();
+
+    let user = db::get_user_by_name(&mut conn, "boekhouder")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (user.id, user.tenant, user.role),
+        (id as usize, b, Role::Boekhouder)
+    );
+    assert!(user.verify_password("correct horse battery staple").is_ok());
+    assert!(user.verify_password("tr0ub4dor&3").is_err());
+
+    // Usernames decide the tenant, so they are unique over all tenants
+    assert!(
+        db::add_user(&mut conn, A, "boekhouder", &hash, Role::Eigenaar)
+            .await
+            .is_err()
+    );
+    assert!(
+        db::add_user(&mut conn, 42, "niemand", &hash, Role::Eigenaar)
+            .await
+            .is_err()
+    );
+
+    // The first tenant starts without users, until its owner is set up
+    assert!(db::get_users(&mut conn, A).await.unwrap().is_empty());
+    server::add_first_user(&mut conn, "noemi", &hash)
+        .await
+        .unwrap();
+    server::add_first_user(&mut conn, "iemand", &hash)
+        .await
+        .unwrap();
+    let users = db::get_users(&mut conn, A).await.unwrap();
+    assert_eq!(
+        (users[0].username.as_str(), users[0].tenant, users[0].role),
+        ("noemi", A, Role::Eigenaar)
+    );
+
+    // Owners only manage the users of their own tenant
+    assert_eq!(db::get_users(&mut conn, A).await.unwrap().len(), 1);
+    assert!(db::set_role(&mut conn, A, id, Role::Eigenaar)
+        .await
+        .is_err());
+    db::delete_user(&mut conn, A, id).await.unwrap();
+    let user = db::get_user(&mut conn, id).await.unwrap().unwrap();
+    assert_eq!(user.role, Role::Boekhouder);
+
+    db::set_role(&mut conn, b, id, Role::Eigenaar)
+        .await
+        .unwrap();
+    assert_eq!(
+        db::get_users(&mut conn, b).await.unwrap()[0].role,
+        Role::Eigenaar
+    );
+    db::delete_user(&mut conn, b, id).await.unwrap();
+    assert!(db::get_user(&mut conn, id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn gaps_are_shown_in_the_numbering_of_the_tenant() {
+    let (_dir, mut conn, b) = database().await;
+    let mut settings = db::get_settings(&mut conn, b).await.unwrap();
+    settings.nummering = "B{jaar}-{nummer:3}".to_owned();
+    db::set_settings(&mut conn, b, &settings).await.unwrap();
+
+    // The server default is only used by tenants without a pattern
+    let nummering = db::get_settings(&mut conn, b)
+        .await
+        .unwrap()
+        .nummering(&Nummering::default())
+        .unwrap();
+    add_invoice(&mut conn, b, &nummering, "Klant").await;
+    let tweede = add_invoice(&mut conn, b, &nummering, "Klant").await;
+    let nummer = tweede.nummer as i64;
+    sqlx::query!("UPDATE invoice SET volgnummer = 3 WHERE nummer = ?", nummer)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    let jaar = tweede.date.year();
+    assert_eq!(
+        db::get_invoice_gaps(&mut conn, b, &nummering)
+            .await
+            .unwrap(),
+        vec![format!("B{jaar}-002")]
+    );
+}